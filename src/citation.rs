@@ -0,0 +1,360 @@
+//! Citations parsed from the `{{cite ...}}` templates of an article.
+
+/// A citation template, like `{{cite web}}` or `{{cite book}}`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Citation {
+    /// The template name in lowercase, for example "cite web".
+    pub kind: String,
+    pub title: Option<String>,
+    /// The authors in the order they are listed, as "Last, First" when the
+    /// names are given separately.
+    pub authors: Vec<String>,
+    pub date: Option<String>,
+    pub url: Option<String>,
+    pub doi: Option<String>,
+    pub isbn: Option<String>,
+    /// The publisher, or the website or journal if there is no publisher.
+    pub publisher: Option<String>,
+    pub archive_url: Option<String>,
+    pub access_date: Option<String>,
+    /// The name of the `<ref>` the citation is in, if it has one.
+    pub ref_name: Option<String>,
+    /// The id of the footnote the citation is in, like "cite_note-3", as
+    /// rendered in the article html. `None` if it is not in a `<ref>`.
+    pub anchor: Option<String>,
+}
+
+/// Finds all citation templates in `wikitext`.
+pub fn parse_citations(wikitext: &str) -> Vec<Citation> {
+    let text = strip_comments(wikitext);
+    let refs = find_refs(&text);
+    let mut citations = Vec::new();
+    for (start, body) in find_templates(&text, 0) {
+        if let Some(mut citation) = citation_from_template(body) {
+            if let Some(r) = refs.iter().find(|r| r.start <= start && start < r.end) {
+                citation.ref_name = r.name.clone();
+                citation.anchor = Some(r.anchor.clone());
+            }
+            citations.push(citation);
+        }
+    }
+    citations
+}
+
+/// A `<ref>` tag and the byte range of its content.
+struct Ref {
+    start: usize,
+    end: usize,
+    name: Option<String>,
+    anchor: String,
+}
+
+/// Removes html comments and the content of `<nowiki>` tags, neither of which
+/// can hold citations. Removed text is replaced by spaces so offsets stay the
+/// same.
+fn strip_comments(wikitext: &str) -> String {
+    let mut text = wikitext.to_owned();
+    for (open, close) in [("<!--", "-->"), ("<nowiki>", "</nowiki>")] {
+        let mut from = 0;
+        while let Some(i) = find_ignore_case(&text, open, from) {
+            let end = match find_ignore_case(&text, close, i + open.len()) {
+                Some(j) => j + close.len(),
+                None => text.len(),
+            };
+            // Multi-byte characters become several spaces to keep offsets
+            let blank = text[i..end]
+                .chars()
+                .map(|c| if c == '\n' { "\n".to_owned() } else { " ".repeat(c.len_utf8()) })
+                .collect::<String>();
+            text.replace_range(i..end, &blank);
+            from = end;
+        }
+    }
+    text
+}
+
+pub(crate) fn find_ignore_case(haystack: &str, needle: &str, from: usize) -> Option<usize> {
+    let bytes = haystack.as_bytes();
+    let needle = needle.as_bytes();
+    if needle.len() > bytes.len() {
+        return None;
+    }
+    (from..=bytes.len() - needle.len())
+        .find(|&i| bytes[i..i + needle.len()].eq_ignore_ascii_case(needle))
+}
+
+/// Finds all `<ref>` tags, numbering them the way the Cite extension does:
+/// every new reference gets the next key, and named references reuse the key
+/// of their first use.
+fn find_refs(text: &str) -> Vec<Ref> {
+    let mut refs = Vec::new();
+    let mut keys: Vec<(Option<String>, String, usize)> = Vec::new();
+    let mut from = 0;
+    while let Some(i) = find_ignore_case(text, "<ref", from) {
+        let after = text[i + 4..].chars().next();
+        let tag_end = match text[i..].find('>') {
+            Some(j) => i + j,
+            None => break,
+        };
+        if !matches!(after, Some(' ') | Some('\t') | Some('\n') | Some('>') | Some('/')) {
+            from = i + 4;
+            continue;
+        }
+        let self_closing = text[..tag_end].ends_with('/');
+        let attributes = &text[i + 4..if self_closing { tag_end - 1 } else { tag_end }];
+        let name = attribute(attributes, "name");
+        let group = attribute(attributes, "group").unwrap_or_default();
+        let (start, end) = if self_closing {
+            (tag_end + 1, tag_end + 1)
+        } else {
+            let close = find_ignore_case(text, "</ref>", tag_end).unwrap_or(text.len());
+            (tag_end + 1, close)
+        };
+        let key = match keys.iter().find(|k| name.is_some() && k.0 == name && k.1 == group) {
+            Some(k) => k.2,
+            None => {
+                let key = keys.len() + 1;
+                keys.push((name.clone(), group, key));
+                key
+            },
+        };
+        let anchor = match name {
+            Some(ref n) => format!("cite_note-{}-{}", n.replace(' ', "_"), key),
+            None => format!("cite_note-{}", key),
+        };
+        refs.push(Ref { start, end, name, anchor });
+        from = end;
+    }
+    refs
+}
+
+/// Reads the value of `name` from the attributes of an html tag.
+pub(crate) fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let lower = attributes.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(i) = lower[from..].find(name).map(|i| from + i) {
+        from = i + name.len();
+        // Make sure it is the whole attribute name
+        if i > 0 && !lower[..i].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let rest = attributes[from..].trim_start();
+        let rest = match rest.strip_prefix('=') {
+            Some(r) => r.trim_start(),
+            None => continue,
+        };
+        let value = match rest.chars().next() {
+            Some(q) if q == '"' || q == '\'' => rest[1..].split(q).next().unwrap_or(""),
+            _ => rest.split(char::is_whitespace).next().unwrap_or(""),
+        };
+        let value = value.trim();
+        return if value.is_empty() { None } else { Some(value.to_owned()) };
+    }
+    None
+}
+
+/// Finds the templates in `text`, returning the offset of each one, relative
+/// to the start of the original text, and its content without the braces.
+/// Templates nested in other templates are returned after their parent.
+fn find_templates(text: &str, offset: usize) -> Vec<(usize, &str)> {
+    let mut templates = Vec::new();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        if bytes[i] == b'{' && bytes[i + 1] == b'{' {
+            match matching_braces(text, i) {
+                Some(end) => {
+                    let body = &text[i + 2..end - 2];
+                    templates.push((offset + i, body));
+                    templates.extend(find_templates(body, offset + i + 2));
+                    i = end;
+                },
+                None => i += 2,
+            }
+        } else {
+            i += 1;
+        }
+    }
+    templates
+}
+
+/// Given the offset of an opening `{{`, returns the offset after its closing
+/// `}}`.
+fn matching_braces(text: &str, start: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        if bytes[i] == b'{' && bytes[i + 1] == b'{' {
+            depth += 1;
+            i += 2;
+        } else if bytes[i] == b'}' && bytes[i + 1] == b'}' {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Some(i);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Splits the content of a template on the `|` that are not inside nested
+/// templates or links.
+fn split_params(body: &str) -> Vec<&str> {
+    let bytes = body.as_bytes();
+    let mut parts = Vec::new();
+    let (mut braces, mut brackets) = (0i32, 0i32);
+    let mut last = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let pair = if i + 1 < bytes.len() { &bytes[i..i + 2] } else { &bytes[i..i + 1] };
+        match pair {
+            b"{{" => { braces += 1; i += 2; continue },
+            b"}}" => { braces -= 1; i += 2; continue },
+            b"[[" => { brackets += 1; i += 2; continue },
+            b"]]" => { brackets -= 1; i += 2; continue },
+            _ => (),
+        }
+        if bytes[i] == b'|' && braces <= 0 && brackets <= 0 {
+            parts.push(&body[last..i]);
+            last = i + 1;
+        }
+        i += 1;
+    }
+    parts.push(&body[last..]);
+    parts
+}
+
+fn citation_from_template(body: &str) -> Option<Citation> {
+    let parts = split_params(body);
+    let kind = parts[0].trim().replace('_', " ").to_lowercase();
+    if !kind.starts_with("cite ") && kind != "citation" {
+        return None;
+    }
+    let params = parts[1..]
+        .iter()
+        .filter_map(|p| p.find('=').map(|i| (p[..i].trim().to_lowercase(), clean_value(&p[i + 1..]))))
+        .filter(|p| !p.1.is_empty())
+        .collect::<Vec<_>>();
+    let get = |names: &[&str]| names
+        .iter()
+        .find_map(|n| params.iter().find(|p| p.0 == *n))
+        .map(|p| p.1.clone());
+
+    Some(Citation {
+        title: get(&["title", "chapter", "script-title"]),
+        authors: authors(&params),
+        date: get(&["date", "year"]),
+        url: get(&["url", "chapter-url", "chapterurl"]),
+        doi: get(&["doi"]),
+        isbn: get(&["isbn", "isbn13"]),
+        publisher: get(&["publisher", "website", "work", "journal", "newspaper", "magazine"]),
+        archive_url: get(&["archive-url", "archiveurl"]),
+        access_date: get(&["access-date", "accessdate"]),
+        kind,
+        ref_name: None,
+        anchor: None,
+    })
+}
+
+/// Collects the authors from the many ways citation templates list them.
+fn authors(params: &[(String, String)]) -> Vec<String> {
+    let get = |name: &str| params.iter().find(|p| p.0 == name).map(|p| &*p.1);
+    let mut authors = Vec::new();
+    for n in 0..100 {
+        let suffix = if n == 0 { "".to_owned() } else { n.to_string() };
+        let last = get(&format!("last{}", suffix))
+            .or_else(|| get(&format!("surname{}", suffix)))
+            .or_else(|| get(&format!("author{}-last", suffix)));
+        let first = get(&format!("first{}", suffix))
+            .or_else(|| get(&format!("given{}", suffix)))
+            .or_else(|| get(&format!("author{}-first", suffix)));
+        let author = get(&format!("author{}", suffix));
+        match (last, first, author) {
+            (Some(l), Some(f), _) => authors.push(format!("{}, {}", l, f)),
+            (Some(l), None, _) => authors.push(l.to_owned()),
+            (None, _, Some(a)) => authors.push(a.to_owned()),
+            // `author` and `author1` are the same, keep looking after 0
+            _ => if n > 0 { break },
+        }
+    }
+    if authors.is_empty() {
+        if let Some(list) = get("vauthors").or_else(|| get("authors")) {
+            authors.extend(list.split(',').map(|a| a.trim().to_owned()).filter(|a| !a.is_empty()));
+        }
+    }
+    authors
+}
+
+/// Turns a parameter value into plain text, keeping the text of links and
+/// removing bold and italics.
+fn clean_value(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(i) = rest.find("[[") {
+        out.push_str(&rest[..i]);
+        let end = match rest[i..].find("]]") {
+            Some(j) => i + j,
+            None => break,
+        };
+        let link = &rest[i + 2..end];
+        out.push_str(link.rsplit('|').next().unwrap_or(link));
+        rest = &rest[end + 2..];
+    }
+    out.push_str(rest);
+    out.replace("'''", "").replace("''", "").trim().to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_citations, Citation};
+
+    #[test]
+    fn cite_templates() {
+        let wikitext = "Text.<ref name=\"smith\">{{Cite web |url=https://example.com |title=''Hello'' |last=Smith |first=John |date=2020-01-01 |access-date=2021-02-02 |website=[[Example|Example.com]] |archive-url=https://archive.org/x}}</ref> More.<ref>{{cite book|author=Jane Doe|title=World|isbn=978-3-16-148410-0|publisher=Books}}</ref> Again.<ref name=smith /> <!-- {{cite web|title=Hidden}} --> {{cite journal |vauthors=Doe J, Roe R |title=Study |doi=10.1000/182}}";
+        assert_eq!(parse_citations(wikitext), vec![
+            Citation {
+                kind: "cite web".to_owned(),
+                title: Some("Hello".to_owned()),
+                authors: vec!["Smith, John".to_owned()],
+                date: Some("2020-01-01".to_owned()),
+                url: Some("https://example.com".to_owned()),
+                publisher: Some("Example.com".to_owned()),
+                archive_url: Some("https://archive.org/x".to_owned()),
+                access_date: Some("2021-02-02".to_owned()),
+                ref_name: Some("smith".to_owned()),
+                anchor: Some("cite_note-smith-1".to_owned()),
+                ..Default::default()
+            },
+            Citation {
+                kind: "cite book".to_owned(),
+                title: Some("World".to_owned()),
+                authors: vec!["Jane Doe".to_owned()],
+                isbn: Some("978-3-16-148410-0".to_owned()),
+                publisher: Some("Books".to_owned()),
+                anchor: Some("cite_note-2".to_owned()),
+                ..Default::default()
+            },
+            Citation {
+                kind: "cite journal".to_owned(),
+                title: Some("Study".to_owned()),
+                authors: vec!["Doe J".to_owned(), "Roe R".to_owned()],
+                doi: Some("10.1000/182".to_owned()),
+                ..Default::default()
+            },
+        ]);
+    }
+
+    #[test]
+    fn numbered_authors_and_nested_templates() {
+        let wikitext = "<ref group=note>{{efn|{{citation|last1=A|first1=B|last2=C|title=T {{!}} U}}}}</ref>";
+        let citations = parse_citations(wikitext);
+        assert_eq!(citations.len(), 1);
+        assert_eq!(citations[0].kind, "citation".to_owned());
+        assert_eq!(citations[0].authors, vec!["A, B".to_owned(), "C".to_owned()]);
+        assert_eq!(citations[0].anchor, Some("cite_note-1".to_owned()));
+    }
+}
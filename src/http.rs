@@ -6,6 +6,16 @@ pub trait HttpClient {
     async fn get<'a, I>(&self, base_url: &str, args: I) -> Result<String, Error>
     where
         I: Iterator<Item = (&'a str, &'a str)>;
+
+    /// Sends `args` as a form in the body of a POST request, for payloads
+    /// too large for an url. Returns an error if not implemented, instead of
+    /// sending the payload in the url.
+    async fn post<'a, I>(&self, _base_url: &str, _args: I) -> Result<String, Error>
+    where
+        I: Iterator<Item = (&'a str, &'a str)>,
+    {
+        Err(failure::err_msg("POST requests are not implemented by this client"))
+    }
 }
 
 
@@ -55,5 +65,26 @@ pub mod default {
 
             Ok(response_str)
         }
+
+        async fn post<'a, I>(&self, base_url: &str, args: I) -> Result<String, Error>
+        where
+            I: Iterator<Item = (&'a str, &'a str)>,
+        {
+            // Keep origin=* in the url, it is not read from the body
+            let url = reqwest::Url::parse_with_params(base_url, &[("origin", "*")])?;
+
+            let client = reqwest::Client::new();
+            let response = client
+                .post(url)
+                .header(reqwest::header::USER_AGENT, self.user_agent.clone())
+                .form(&args.collect::<Vec<(&'a str, &'a str)>>())
+                .send().await?;
+
+            ensure!(response.status().is_success(), err_msg("Bad status"));
+
+            let response_str = response.text().await?;
+
+            Ok(response_str)
+        }
     }
 }
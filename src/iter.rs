@@ -109,17 +109,27 @@ impl<I: AsyncIterator> IntoAsyncIterator for I
 }
 
 pub struct Iter<'a, A: 'a + http::HttpClient, B: IterItem> {
-    page: &'a Page<'a, A>,
+    page: Page<'a, A>,
+    query: B::Query,
+    state: B::State,
     inner: IntoIter<Value>,
     cont: Option<Vec<(String, String)>>,
     phantom: PhantomData<B>
 }
 
 impl<'a, A: http::HttpClient, B: IterItem> Iter<'a, A, B> {
-    pub async fn new(page: &'a Page<'_, A>) -> Result<Iter<'a, A, B>> {
-        let (array, cont) = B::request_next(page, &None).await?;
+    pub async fn new(page: &Page<'a, A>) -> Result<Iter<'a, A, B>> {
+        Iter::with_query(page, B::Query::default()).await
+    }
+
+    /// Creates an iterator that fetches the items using `query`.
+    pub async fn with_query(page: &Page<'a, A>, query: B::Query) -> Result<Iter<'a, A, B>> {
+        let mut state = B::State::default();
+        let (array, cont) = B::request_next(page, &query, &mut state, &None).await?;
         Ok(Iter {
-            page: page,
+            page: page.clone(),
+            query,
+            state,
             inner: array.into_iter(),
             cont: cont,
             phantom: PhantomData,
@@ -128,7 +138,7 @@ impl<'a, A: http::HttpClient, B: IterItem> Iter<'a, A, B> {
 
     async fn fetch_next(&mut self) -> Result <()> {
         if self.cont.is_some() {
-            let (array, cont) = B::request_next(self.page, &self.cont).await?;
+            let (array, cont) = B::request_next(&self.page, &self.query, &mut self.state, &self.cont).await?;
             self.inner = array.into_iter();
             self.cont = cont;
         }
@@ -139,36 +149,94 @@ impl<'a, A: http::HttpClient, B: IterItem> Iter<'a, A, B> {
 impl<'a, A: http::HttpClient, B: IterItem> AsyncIterator for Iter<'a, A, B> {
     type Item = B;
     async fn next(&mut self) -> Option<Self::Item> {
-        match self.inner.next() {
-            Some(ref v) => B::from_value(&v),
-            None => match self.cont {
-                Some(_) => match self.fetch_next().await {
-                    Ok(_) => self.inner.next().and_then(|x| B::from_value(&x)),
-                    Err(_) => None,
-                },
-                None => None,
+        loop {
+            match self.inner.next() {
+                Some(ref v) => return B::from_value(v),
+                // A batch can be empty, for example once filtered, while
+                // more are left
+                None => match self.cont {
+                    Some(_) => if self.fetch_next().await.is_err() {
+                        return None;
+                    },
+                    None => return None,
+                }
             }
         }
     }
 }
 
 pub trait IterItem: Sized {
-    async fn request_next<A: http::HttpClient>(page: &Page<A>, cont: &Option<Vec<(String, String)>>)
+    /// Options used when requesting the items, `()` if there are none.
+    type Query: Default;
+    /// Kept between the requests of an iterator, `()` if nothing is.
+    type State: Default;
+
+    async fn request_next<A: http::HttpClient>(page: &Page<A>, query: &Self::Query, state: &mut Self::State,
+                                               cont: &Option<Vec<(String, String)>>)
             -> Result<(Vec<Value>, Option<Vec<(String, String)>>)>;
     fn from_value(value: &Value) -> Option<Self>;
 }
 
-#[derive(Debug, PartialEq)]
+/// Options for `Page::get_images_with`. By default only the urls are fetched.
+#[derive(Debug, Clone, Default)]
+pub struct ImageQuery {
+    /// Fetch the dimensions, size, mime type, sha1, timestamp and uploader.
+    pub details: bool,
+    /// Fetch the license and attribution of the images.
+    pub license: bool,
+    /// Fetch a thumbnail scaled to this width.
+    pub thumb_width: Option<u32>,
+    /// Fetch a thumbnail scaled to this height.
+    pub thumb_height: Option<u32>,
+}
+
+/// A scaled version of an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnail {
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// License and attribution of an image, as html snippets from `extmetadata`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageLicense {
+    /// The license name, like "CC BY-SA 4.0".
+    pub short_name: Option<String>,
+    pub artist: Option<String>,
+    pub attribution: Option<String>,
+    pub credit: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
 pub struct Image {
     pub url: String,
     pub title: String,
     pub description_url: String,
+    /// Width in pixels, with `ImageQuery::details`.
+    pub width: Option<u32>,
+    /// Height in pixels, with `ImageQuery::details`.
+    pub height: Option<u32>,
+    /// Size in bytes, with `ImageQuery::details`.
+    pub size: Option<u64>,
+    pub mime: Option<String>,
+    pub sha1: Option<String>,
+    /// When this version was uploaded, in ISO 8601 format.
+    pub timestamp: Option<String>,
+    pub uploader: Option<String>,
+    /// With `ImageQuery::license`.
+    pub license: Option<ImageLicense>,
+    /// With `ImageQuery::thumb_width` or `ImageQuery::thumb_height`.
+    pub thumbnail: Option<Thumbnail>,
 }
 
 impl IterItem for Image {
-    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, cont: &Option<Vec<(String, String)>>)
+    type Query = ImageQuery;
+    type State = ();
+
+    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, query: &ImageQuery, _state: &mut (), cont: &Option<Vec<(String, String)>>)
                                                -> Result<(Vec<Value>, Option<Vec<(String, String)>>)> {
-        page.request_images(&cont).await
+        page.request_images(query, cont).await
     }
 
     fn from_value(value: &Value) -> Option<Image> {
@@ -181,74 +249,210 @@ impl IterItem for Image {
             .get("title")
             .and_then(|x| x.as_str())
             .unwrap_or("").to_owned();
-        let url = obj
+        let info = obj
             .get("imageinfo")
             .and_then(|x| x.as_array())
-            .and_then(|x| x.into_iter().next())
-            .and_then(|x| x.as_object())
-            .and_then(|x| x.get("url"))
+            .and_then(|x| x.iter().next())
+            .and_then(|x| x.as_object());
+        let string = |key: &str| info
+            .and_then(|x| x.get(key))
             .and_then(|x| x.as_str())
-            .unwrap_or("").to_owned();
-        let description_url = obj
-            .get("imageinfo")
-            .and_then(|x| x.as_array())
-            .and_then(|x| x.into_iter().next())
+            .map(|x| x.to_owned());
+        let number = |key: &str| info
+            .and_then(|x| x.get(key))
+            .and_then(|x| x.as_u64());
+
+        let license = info
+            .and_then(|x| x.get("extmetadata"))
             .and_then(|x| x.as_object())
-            .and_then(|x| x.get("descriptionurl"))
-            .and_then(|x| x.as_str())
-            .unwrap_or("").to_owned();
+            .map(|metadata| {
+                let field = |key: &str| metadata
+                    .get(key)
+                    .and_then(|x| x.get("value"))
+                    .and_then(|x| x.as_str())
+                    .map(|x| x.to_owned());
+                ImageLicense {
+                    short_name: field("LicenseShortName"),
+                    artist: field("Artist"),
+                    attribution: field("Attribution"),
+                    credit: field("Credit"),
+                }
+            });
+        let thumbnail = string("thumburl").map(|url| Thumbnail {
+            url,
+            width: number("thumbwidth").unwrap_or(0) as u32,
+            height: number("thumbheight").unwrap_or(0) as u32,
+        });
 
         Some(Image {
-            url: url.to_owned(),
+            url: string("url").unwrap_or_default(),
             title: title.to_owned(),
-            description_url: description_url.to_owned(),
+            description_url: string("descriptionurl").unwrap_or_default(),
+            width: number("width").map(|x| x as u32),
+            height: number("height").map(|x| x as u32),
+            size: number("size"),
+            mime: string("mime"),
+            sha1: string("sha1"),
+            timestamp: string("timestamp"),
+            uploader: string("user"),
+            license,
+            thumbnail,
         })
     }
 }
 
+/// Options for `Page::get_references_with` and `Wikipedia::external_url_usage`.
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceQuery {
+    /// Only list links using this protocol, like "https" or "mailto".
+    pub protocol: Option<String>,
+    /// Only list links to this domain, "*.example.com" also matches its
+    /// subdomains.
+    pub domain: Option<String>,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Reference {
     pub url: String,
+    /// The scheme of the url without the colon, like "https" or "mailto".
+    pub protocol: String,
+    /// The host of the url, or the domain of the address for "mailto" links.
+    /// Empty if the url has none.
+    pub domain: String,
+}
+
+impl Reference {
+    pub(crate) fn from_url(url: &str) -> Reference {
+        // Protocol-relative urls are only returned if they could not be expanded
+        let url = if url.starts_with("//") {
+            format!("https:{}", url)
+        } else {
+            url.to_owned()
+        };
+        let (protocol, rest) = match url.find(':') {
+            Some(i) => (&url[..i], &url[i + 1..]),
+            None => ("", &*url),
+        };
+        let domain = match rest.strip_prefix("//") {
+            Some(rest) => {
+                let authority = &rest[..rest.find(['/', '?', '#']).unwrap_or(rest.len())];
+                let host = authority.rsplit('@').next().unwrap_or("");
+                // Keep IPv6 hosts like [::1] whole, but strip the port
+                match host.rfind(':') {
+                    Some(i) if !host[i..].contains(']') => &host[..i],
+                    _ => host,
+                }
+            },
+            None if protocol.eq_ignore_ascii_case("mailto") => {
+                let address = &rest[..rest.find('?').unwrap_or(rest.len())];
+                address.rsplit('@').next().filter(|_| address.contains('@')).unwrap_or("")
+            },
+            None => "",
+        };
+        Reference {
+            protocol: protocol.to_ascii_lowercase(),
+            domain: domain.to_ascii_lowercase(),
+            url: url.clone(),
+        }
+    }
 }
 
 impl IterItem for Reference {
-    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, cont: &Option<Vec<(String, String)>>)
+    type Query = ReferenceQuery;
+    type State = ();
+
+    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, query: &ReferenceQuery, _state: &mut (), cont: &Option<Vec<(String, String)>>)
                                                -> Result<(Vec<Value>, Option<Vec<(String, String)>>)> {
-        page.request_extlinks(&cont).await
+        page.request_extlinks(query, cont).await
     }
 
     fn from_value(value: &Value) -> Option<Reference> {
-        value
-            .as_object()
-            .and_then(|x| x.get("*"))
+        let obj = value.as_object()?;
+        obj
+            .get("url")
+            .or_else(|| obj.get("*"))
             .and_then(|x| x.as_str())
-            .map(|s| Reference {
-                url: if s.starts_with("http:") {
-                    s.to_owned()
-                } else {
-                    format!("http:{}", s)
-                },
-            })
+            .map(Reference::from_url)
+    }
+}
+
+/// Options for `Page::get_links_with`.
+#[derive(Debug, Clone)]
+pub struct LinkQuery {
+    /// Only list links to these namespaces, all of them if empty.
+    /// Defaults to the main namespace only.
+    pub namespaces: Vec<i32>,
+    /// Only list links to these titles, useful to check whether the page
+    /// links to them. All links are listed if empty.
+    pub titles: Vec<String>,
+}
+
+impl Default for LinkQuery {
+    fn default() -> Self {
+        LinkQuery {
+            namespaces: vec![0],
+            titles: Vec::new(),
+        }
     }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Link {
     pub title: String,
+    /// The namespace id of the linked page.
+    pub ns: i32,
+    /// Whether the linked page exists, red links are `false`.
+    pub exists: bool,
 }
 
 impl IterItem for Link {
-    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, cont: &Option<Vec<(String, String)>>)
+    type Query = LinkQuery;
+    type State = ();
+
+    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, query: &LinkQuery, _state: &mut (), cont: &Option<Vec<(String, String)>>)
                                                -> Result<(Vec<Value>, Option<Vec<(String, String)>>)> {
-        page.request_links(&cont).await
+        page.request_links(query, cont).await
     }
 
     fn from_value(value: &Value) -> Option<Link> {
-        value
-            .as_object()
-            .and_then(|x| x.get("title"))
-            .and_then(|x| x.as_str())
-            .map(|s| Link { title: s.to_owned() })
+        let obj = value.as_object()?;
+        Some(Link {
+            title: obj.get("title").and_then(|x| x.as_str())?.to_owned(),
+            ns: obj.get("ns").and_then(|x| x.as_i64()).unwrap_or(0) as i32,
+            exists: !obj.contains_key("missing") && !obj.contains_key("invalid"),
+        })
+    }
+}
+
+/// Options for `Page::get_templates_with`.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateQuery {
+    /// Only list templates in these namespaces, all of them if empty.
+    pub namespaces: Vec<i32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    pub title: String,
+    /// The namespace id of the template, usually 10.
+    pub ns: i32,
+}
+
+impl IterItem for Template {
+    type Query = TemplateQuery;
+    type State = ();
+
+    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, query: &TemplateQuery, _state: &mut (), cont: &Option<Vec<(String, String)>>)
+                                               -> Result<(Vec<Value>, Option<Vec<(String, String)>>)> {
+        page.request_templates(query, cont).await
+    }
+
+    fn from_value(value: &Value) -> Option<Template> {
+        let obj = value.as_object()?;
+        Some(Template {
+            title: obj.get("title").and_then(|x| x.as_str())?.to_owned(),
+            ns: obj.get("ns").and_then(|x| x.as_i64()).unwrap_or(0) as i32,
+        })
     }
 }
 
@@ -262,7 +466,10 @@ pub struct LangLink {
 }
 
 impl IterItem for LangLink {
-    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, cont: &Option<Vec<(String, String)>>)
+    type Query = ();
+    type State = ();
+
+    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, _query: &(), _state: &mut (), cont: &Option<Vec<(String, String)>>)
                                                -> Result<(Vec<Value>, Option<Vec<(String, String)>>)> {
         page.request_langlinks(&cont).await
     }
@@ -277,28 +484,211 @@ impl IterItem for LangLink {
     }
 }
 
+/// Options for `Page::get_categories_with`.
+#[derive(Debug, Clone, Default)]
+pub struct CategoryQuery {
+    /// Leave out hidden categories, which are usually used for maintenance.
+    pub exclude_hidden: bool,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Category {
+    /// The category name, without the namespace prefix.
     pub title: String,
+    /// The sortkey of the page in this category, as a hexadecimal string.
+    pub sortkey: String,
+    /// The human-readable part of the sortkey, may be empty.
+    pub sortkey_prefix: String,
+    /// When the page was added to the category, in ISO 8601 format.
+    pub timestamp: Option<String>,
+    pub hidden: bool,
 }
 
 impl IterItem for Category {
-    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, cont: &Option<Vec<(String, String)>>)
+    type Query = CategoryQuery;
+    type State = Option<Vec<String>>;
+
+    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, query: &CategoryQuery, state: &mut Option<Vec<String>>, cont: &Option<Vec<(String, String)>>)
                                                -> Result<(Vec<Value>, Option<Vec<(String, String)>>)> {
-        page.request_categories(&cont).await
+        page.request_categories(query, state, cont).await
     }
 
     fn from_value(value: &Value) -> Option<Category> {
-        value
-            .as_object()
-            .and_then(|x| x.get("title"))
-            .and_then(|x| x.as_str())
-            .map(|s| Category {
-                title: if s.starts_with("Category: ") {
-                    s[10..].to_owned()
-                } else {
-                    s.to_owned()
-                },
-            })
+        let obj = value.as_object()?;
+        let title = obj.get("title").and_then(|x| x.as_str())?;
+        Some(Category {
+            title: title.strip_prefix("Category:").unwrap_or(title).to_owned(),
+            sortkey: obj.get("sortkey").and_then(|x| x.as_str()).unwrap_or("").to_owned(),
+            sortkey_prefix: obj.get("sortkeyprefix").and_then(|x| x.as_str()).unwrap_or("").to_owned(),
+            timestamp: obj.get("timestamp").and_then(|x| x.as_str()).map(|x| x.to_owned()),
+            // Only present, with an empty value, for hidden categories
+            hidden: obj.get("hidden").map(|x| !matches!(*x, Value::Bool(false))).unwrap_or(false),
+        })
+    }
+}
+
+/// The kind of page a category member is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CategoryMemberType {
+    Page,
+    Subcat,
+    File,
+}
+
+impl CategoryMemberType {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            CategoryMemberType::Page => "page",
+            CategoryMemberType::Subcat => "subcat",
+            CategoryMemberType::File => "file",
+        }
     }
+
+    fn from_name(s: &str) -> Option<CategoryMemberType> {
+        match s {
+            "page" => Some(CategoryMemberType::Page),
+            "subcat" => Some(CategoryMemberType::Subcat),
+            "file" => Some(CategoryMemberType::File),
+            _ => None,
+        }
+    }
+}
+
+/// How category members are sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CategoryMemberSort {
+    #[default]
+    Sortkey,
+    /// By the time the member was added to the category.
+    Timestamp,
+}
+
+/// Options for `Wikipedia::category_members`.
+#[derive(Debug, Clone, Default)]
+pub struct CategoryMemberQuery {
+    /// Only list members of these types, all of them if empty. When sorting
+    /// by timestamp the API ignores this, so the members are filtered after
+    /// each request instead.
+    pub types: Vec<CategoryMemberType>,
+    /// Only list members in these namespaces, all of them if empty.
+    pub namespaces: Vec<i32>,
+    pub sort: CategoryMemberSort,
+    /// List the members in descending order.
+    pub descending: bool,
 }
+
+impl CategoryMemberQuery {
+    /// Whether `member` passes the type and namespace filters.
+    pub fn matches(&self, member: &CategoryMember) -> bool {
+        (self.types.is_empty() || self.types.contains(&member.member_type)) &&
+            (self.namespaces.is_empty() || self.namespaces.contains(&member.ns))
+    }
+
+    pub(crate) fn types_param(&self) -> String {
+        self.types.iter().map(|x| x.as_str()).collect::<Vec<_>>().join("|")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryMember {
+    pub pageid: u64,
+    pub ns: i32,
+    pub title: String,
+    pub member_type: CategoryMemberType,
+    /// The human-readable part of the sortkey, may be empty.
+    pub sortkey_prefix: String,
+    /// When the member was added to the category, in ISO 8601 format.
+    pub timestamp: Option<String>,
+}
+
+impl IterItem for CategoryMember {
+    type Query = CategoryMemberQuery;
+    type State = ();
+
+    async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, query: &CategoryMemberQuery, _state: &mut (), cont: &Option<Vec<(String, String)>>)
+                                               -> Result<(Vec<Value>, Option<Vec<(String, String)>>)> {
+        page.request_category_members(query, cont).await
+    }
+
+    fn from_value(value: &Value) -> Option<CategoryMember> {
+        let obj = value.as_object()?;
+        Some(CategoryMember {
+            pageid: obj.get("pageid").and_then(|x| x.as_u64()).unwrap_or(0),
+            ns: obj.get("ns").and_then(|x| x.as_i64()).unwrap_or(0) as i32,
+            title: obj.get("title").and_then(|x| x.as_str())?.to_owned(),
+            member_type: obj
+                .get("type")
+                .and_then(|x| x.as_str())
+                .and_then(CategoryMemberType::from_name)
+                .unwrap_or(CategoryMemberType::Page),
+            sortkey_prefix: obj.get("sortkeyprefix").and_then(|x| x.as_str()).unwrap_or("").to_owned(),
+            timestamp: obj.get("timestamp").and_then(|x| x.as_str()).map(|x| x.to_owned()),
+        })
+    }
+}
+
+/// Which pages to keep depending on whether they are redirects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedirectFilter {
+    #[default]
+    All,
+    Redirects,
+    NonRedirects,
+}
+
+/// Options for the iterators over pages linking to a `Page`, like
+/// `Page::get_backlinks`.
+#[derive(Debug, Clone, Default)]
+pub struct LinkingPageQuery {
+    /// Only list pages in these namespaces, all of them if empty.
+    pub namespaces: Vec<i32>,
+    pub redirects: RedirectFilter,
+}
+
+macro_rules! linking_page {
+    ($(#[$attr: meta])* $name: ident, $request: ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $name {
+            pub pageid: u64,
+            pub ns: i32,
+            pub title: String,
+            /// Whether the page is a redirect.
+            pub redirect: bool,
+        }
+
+        impl IterItem for $name {
+            type Query = LinkingPageQuery;
+            type State = ();
+
+            async fn request_next<A: http::HttpClient>(page: &Page<'_, A>, query: &LinkingPageQuery, _state: &mut (), cont: &Option<Vec<(String, String)>>)
+                                                       -> Result<(Vec<Value>, Option<Vec<(String, String)>>)> {
+                page.$request(query, cont).await
+            }
+
+            fn from_value(value: &Value) -> Option<$name> {
+                let obj = value.as_object()?;
+                Some($name {
+                    pageid: obj.get("pageid").and_then(|x| x.as_u64()).unwrap_or(0),
+                    ns: obj.get("ns").and_then(|x| x.as_i64()).unwrap_or(0) as i32,
+                    title: obj.get("title").and_then(|x| x.as_str())?.to_owned(),
+                    // Only present, with an empty value, for redirects
+                    redirect: obj.get("redirect").map(|x| !matches!(*x, Value::Bool(false))).unwrap_or(false),
+                })
+            }
+        }
+    }
+}
+
+linking_page!(
+    /// A page linking to another one, from `list=backlinks`.
+    Backlink, request_backlinks);
+linking_page!(
+    /// A page linking to another one, from `prop=linkshere`.
+    LinkHere, request_linkshere);
+linking_page!(
+    /// A page transcluding another one, usually a template.
+    TranscludedIn, request_transcludedin);
+linking_page!(
+    /// A page using a file.
+    FileUsage, request_fileusage);
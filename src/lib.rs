@@ -20,14 +20,23 @@ extern crate serde_json;
 #[macro_use] extern crate failure;
 
 use std::cmp::PartialEq;
+use std::collections::{HashSet, VecDeque};
 use std::io;
 use std::result;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 pub mod iter;
 pub mod http;
+pub mod citation;
+pub mod wikidata;
+pub mod sparql;
+pub mod wikitext;
+pub mod markdown;
 pub use iter::Iter;
+use iter::{AsyncIterator, IterItem};
 
 const LANGUAGE_URL_MARKER:&'static str = "{language}";
+const CATEGORY_NAMESPACE: i32 = 14;
 
 macro_rules! results {
     ($data: expr, $query_field: expr) => {
@@ -71,7 +80,7 @@ macro_rules! cont {
             .and_then(|x| x.as_object())
             .ok_or(Error::JSONPathError)?;
 
-        Ok((pages.values().cloned().collect(), $this.parse_cont(&q)?))
+        Ok((pages.values().cloned().collect(), parse_cont(&q)?))
     } }
 }
 
@@ -97,6 +106,1018 @@ pub enum Error {
 
 pub type Result<T> = result::Result<T, Error>;
 
+/// Decodes the `%XX` escapes of an url path.
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Receive a json object and extracts any `continue` parameters to be
+/// used when browsing following pages.
+fn parse_cont(q: &serde_json::Value) -> Result<Option<Vec<(String, String)>>> {
+    let cont = match q
+        .as_object()
+        .and_then(|x| x.get("continue"))
+        .and_then(|x| x.as_object()) {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let mut cont_v = vec![];
+    for (k, v) in cont.into_iter() {
+        let value = match *v {
+            serde_json::Value::Null => "".to_owned(),
+            serde_json::Value::Bool(b) => if b { "1" } else { "0" }.to_owned(),
+            serde_json::Value::Number(ref f) => format!("{}", f),
+            serde_json::Value::String(ref s) => s.clone(),
+            _ => return Err(Error::JSONPathError),
+        };
+        cont_v.push((k.clone(), value));
+    }
+    Ok(Some(cont_v))
+}
+
+/// Given a response including `meta=siteinfo&siprop=namespaces`, returns the
+/// localized and canonical names of namespace `ns`.
+fn namespace_names(q: &serde_json::Value, ns: i32) -> Vec<String> {
+    let namespace = q
+        .as_object()
+        .and_then(|x| x.get("query"))
+        .and_then(|x| x.as_object())
+        .and_then(|x| x.get("namespaces"))
+        .and_then(|x| x.as_object())
+        .and_then(|x| x.get(&ns.to_string()))
+        .and_then(|x| x.as_object());
+    let mut names = Vec::new();
+    if let Some(namespace) = namespace {
+        for key in ["*", "canonical"] {
+            if let Some(name) = namespace.get(key).and_then(|x| x.as_str()) {
+                if !name.is_empty() && !names.iter().any(|x| x == name) {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Removes the first of `prefixes` followed by a colon from `title`.
+fn strip_namespace<'t>(title: &'t str, prefixes: &[String]) -> &'t str {
+    for prefix in prefixes {
+        if let Some(name) = title.strip_prefix(&**prefix).and_then(|x| x.strip_prefix(':')) {
+            return name;
+        }
+    }
+    title
+}
+
+/// Joins namespace ids with `|` as expected by the API.
+fn join_namespaces(namespaces: &[i32]) -> String {
+    namespaces.iter().map(|x| x.to_string()).collect::<Vec<_>>().join("|")
+}
+
+/// An article suggested by `Wikipedia::autocomplete`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub title: String,
+    /// The namespace id of the page.
+    pub ns: i32,
+    /// The short description of the page, if it has one.
+    pub description: Option<String>,
+    pub url: String,
+}
+
+/// A page linking to an external url, from `Wikipedia::external_url_usage`.
+#[derive(Debug, PartialEq)]
+pub struct UrlUsage {
+    pub pageid: u64,
+    pub ns: i32,
+    pub title: String,
+    /// The external url the page links to.
+    pub reference: iter::Reference,
+}
+
+/// A point associated to an article, from `prop=coordinates`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coordinate {
+    pub lat: f64,
+    pub lon: f64,
+    /// Whether this is the main location of the article.
+    pub primary: bool,
+    /// The celestial body, like "earth" or "moon".
+    pub globe: String,
+    /// The approximate size of the object, in meters.
+    pub dim: Option<u64>,
+    /// The type of object, like "city" or "landmark".
+    pub kind: Option<String>,
+    pub name: Option<String>,
+    /// The ISO 3166-1 alpha-2 country code.
+    pub country: Option<String>,
+    /// The ISO 3166-2 region code, without the country.
+    pub region: Option<String>,
+}
+
+impl Coordinate {
+    fn from_value(value: &serde_json::Value) -> Option<Coordinate> {
+        let string = |key: &str| value.get(key).and_then(|x| x.as_str()).map(|x| x.to_owned());
+        Some(Coordinate {
+            lat: value.get("lat")?.as_f64()?,
+            lon: value.get("lon")?.as_f64()?,
+            primary: value.get("primary").is_some(),
+            globe: string("globe").unwrap_or_else(|| "earth".to_owned()),
+            // `dim` is sometimes sent as a string
+            dim: value.get("dim").and_then(|x| x.as_u64().or_else(|| x.as_str().and_then(|x| x.parse().ok()))),
+            kind: string("type"),
+            name: string("name"),
+            country: string("country"),
+            region: string("region"),
+        })
+    }
+}
+
+/// Where the short description of an article comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DescriptionSource {
+    /// Set on the wiki itself, usually with `{{Short description}}`.
+    #[default]
+    Local,
+    /// The description of the Wikidata item.
+    Central,
+}
+
+impl DescriptionSource {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            DescriptionSource::Local => "local",
+            DescriptionSource::Central => "central",
+        }
+    }
+}
+
+/// The short description of an article, from `prop=description`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Description {
+    pub text: String,
+    pub source: DescriptionSource,
+}
+
+/// The representative image of an article, from `prop=pageimages`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageImage {
+    /// The file name, without the namespace prefix.
+    pub file: String,
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PageImage {
+    /// Reads the image of a page object, either the `thumbnail` or the
+    /// `original` one.
+    fn from_page(page: &serde_json::Value, key: &str) -> Option<PageImage> {
+        let image = page.get(key).and_then(|x| x.as_object())?;
+        Some(PageImage {
+            file: page.get("pageimage").and_then(|x| x.as_str()).unwrap_or("").to_owned(),
+            url: image.get("source").and_then(|x| x.as_str())?.to_owned(),
+            width: image.get("width").and_then(|x| x.as_u64()).unwrap_or(0) as u32,
+            height: image.get("height").and_then(|x| x.as_u64()).unwrap_or(0) as u32,
+        })
+    }
+}
+
+/// Which coordinates `Wikipedia::geosearch_with` looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GeoPrimary {
+    #[default]
+    Primary,
+    Secondary,
+    All,
+}
+
+/// Options for `Wikipedia::geosearch_with`. Exactly one of `coordinates`,
+/// `page` and `bbox` must be set.
+#[derive(Debug, Clone, Default)]
+pub struct GeoQuery {
+    /// Search around this latitude and longitude.
+    pub coordinates: Option<(f64, f64)>,
+    /// Search around the coordinates of this article.
+    pub page: Option<String>,
+    /// Search within a bounding box, as (top, left, bottom, right).
+    pub bbox: Option<(f64, f64, f64, f64)>,
+    /// Search radius in meters, between 10 and 10000. Defaults to 10000.
+    pub radius: Option<u32>,
+    /// The celestial body, "earth" if not set.
+    pub globe: Option<String>,
+    /// Only list pages in these namespaces, articles if empty.
+    pub namespaces: Vec<i32>,
+    /// Fetch the type, dimension, country and region of each result.
+    pub details: bool,
+    pub primary: GeoPrimary,
+    /// Number of results, defaults to `Wikipedia::search_results`.
+    pub limit: Option<u32>,
+}
+
+/// An article found by `Wikipedia::geosearch_with`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoHit {
+    pub title: String,
+    pub pageid: u64,
+    pub ns: i32,
+    pub lat: f64,
+    pub lon: f64,
+    /// Distance in meters from the center of the search, if there is one.
+    pub dist: Option<f64>,
+    /// Whether these are the main coordinates of the article.
+    pub primary: bool,
+    /// The type of object, like "city" or "landmark".
+    pub kind: Option<String>,
+    /// The approximate size of the object, in meters.
+    pub dim: Option<u64>,
+    pub country: Option<String>,
+    pub region: Option<String>,
+}
+
+/// A section of an article, from `action=parse&prop=sections`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// The heading text.
+    pub line: String,
+    /// The heading level, 2 for `== Heading ==`.
+    pub level: u32,
+    /// The depth in the table of contents, starting at 1.
+    pub toclevel: u32,
+    /// The position in the table of contents, like "2.3.1".
+    pub number: String,
+    /// The id of the heading, to link to it with `#anchor`.
+    pub anchor: String,
+    /// The section index, used to fetch or edit the section. Sections
+    /// coming from a template have an index like "T-1".
+    pub index: String,
+    /// The byte offset of the heading in the wikitext, if the section is
+    /// not coming from a template.
+    pub byteoffset: Option<u64>,
+}
+
+impl Section {
+    fn from_value(value: &serde_json::Value) -> Option<Section> {
+        let string = |key: &str| value.get(key).and_then(|x| x.as_str()).map(|x| x.to_owned());
+        let number = |key: &str| value.get(key).and_then(|x| x.as_u64().or_else(|| x.as_str().and_then(|x| x.parse().ok())));
+        Some(Section {
+            line: string("line")?,
+            level: number("level").unwrap_or(0) as u32,
+            toclevel: number("toclevel").unwrap_or(0) as u32,
+            number: string("number").unwrap_or_default(),
+            anchor: string("anchor").unwrap_or_default(),
+            index: string("index").unwrap_or_default(),
+            byteoffset: value.get("byteoffset").and_then(|x| x.as_u64()),
+        })
+    }
+}
+
+/// A section and its subsections, see `Page::get_section_tree`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionNode {
+    pub section: Section,
+    pub children: Vec<SectionNode>,
+}
+
+impl SectionNode {
+    /// Nests `sections` by their `toclevel`.
+    fn tree(sections: Vec<Section>) -> Vec<SectionNode> {
+        // The open nodes, from the outermost to the innermost
+        let mut stack: Vec<SectionNode> = Vec::new();
+        let mut roots = Vec::new();
+        for section in sections {
+            while stack.last().is_some_and(|x| x.section.toclevel >= section.toclevel) {
+                let node = stack.pop().unwrap();
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => roots.push(node),
+                }
+            }
+            stack.push(SectionNode { section, children: Vec::new() });
+        }
+        while let Some(node) = stack.pop() {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => roots.push(node),
+            }
+        }
+        roots
+    }
+}
+
+/// The source of a revision of an article.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wikitext {
+    pub content: String,
+    /// The id of the revision the content comes from.
+    pub revid: u64,
+    /// The content model, usually "wikitext".
+    pub content_model: String,
+}
+
+impl Wikitext {
+    /// Parses the content into a tree of wikitext nodes.
+    pub fn parse(&self) -> Vec<wikitext::Node> {
+        wikitext::parse(&self.content)
+    }
+}
+
+/// The infobox of an article, the summary table at its top.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Infobox {
+    /// The type of infobox, like "person" for `{{Infobox person}}`. Empty
+    /// when read from the rendered html, which does not say it.
+    pub kind: String,
+    /// The parameters with a value, in the order they are written.
+    pub params: Vec<(String, InfoboxValue)>,
+}
+
+/// The value of an infobox parameter.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InfoboxValue {
+    /// The value as plain text, with one line per list item.
+    pub text: String,
+    /// The titles of the articles linked from the value.
+    pub links: Vec<String>,
+}
+
+impl Infobox {
+    /// Gets the value of the parameter `name`.
+    pub fn get(&self, name: &str) -> Option<&InfoboxValue> {
+        self.params.iter().find(|x| x.0 == name).map(|x| &x.1)
+    }
+
+    /// Finds the first `{{Infobox ...}}` template in `nodes`.
+    fn from_wikitext(nodes: &[wikitext::Node]) -> Option<Infobox> {
+        let template = wikitext::descendants(nodes).into_iter().find_map(|node| match *node {
+            wikitext::Node::Template(ref template) if template.name().to_lowercase().starts_with("infobox") => Some(template),
+            _ => None,
+        })?;
+        let name = template.name();
+        let params = template.params
+            .iter()
+            .filter_map(|param| {
+                let value = InfoboxValue {
+                    text: wikitext::plain_text(&param.value),
+                    links: wikitext::descendants(&param.value)
+                        .into_iter()
+                        .filter_map(|node| match *node {
+                            wikitext::Node::InternalLink(ref link) if !link.is_media() && !link.is_category() => link.title(),
+                            _ => None,
+                        })
+                        .collect(),
+                };
+                if value.text.is_empty() && value.links.is_empty() {
+                    return None;
+                }
+                Some((param.name()?.to_owned(), value))
+            })
+            .collect();
+        Some(Infobox {
+            kind: name["infobox".len()..].trim().to_owned(),
+            params,
+        })
+    }
+
+    /// Reads the first `<table class="infobox">` of `html`, taking each row
+    /// with both a header and a data cell as a parameter.
+    fn from_html(html: &str) -> Option<Infobox> {
+        let mut from = 0;
+        let start = loop {
+            let start = from + html[from..].find("<table")?;
+            let end = start + html[start..].find('>')?;
+            let classes = citation::attribute(&html[start + 6..end], "class").unwrap_or_default();
+            if classes.split_whitespace().any(|x| x == "infobox") {
+                break end + 1;
+            }
+            from = end;
+        };
+        let table = &html[start..html_element_end(html, start, "table")];
+        let mut params = Vec::new();
+        let mut from = 0;
+        while let Some(i) = table[from..].find("<tr") {
+            let row_start = from + i;
+            let row_end = row_start + table[row_start..].find("</tr>").unwrap_or(table.len() - row_start);
+            let row = &table[row_start..row_end];
+            from = row_end;
+            let (th, td) = match (html_cell(row, "th"), html_cell(row, "td")) {
+                (Some(th), Some(td)) => (th, td),
+                _ => continue,
+            };
+            let key = html_text(th);
+            let value = InfoboxValue { text: html_text(td), links: html_links(td) };
+            if !key.is_empty() && !value.text.is_empty() {
+                params.push((key, value));
+            }
+        }
+        Some(Infobox { kind: String::new(), params })
+    }
+}
+
+/// A table of an article, with merged cells repeated in every row and
+/// column they span, so all the rows have the same length.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub caption: Option<String>,
+    /// The text of the header rows at the top of the table, with the texts
+    /// of a column joined by " / " when there are several.
+    pub headers: Vec<String>,
+    /// The text of the cells of the other rows.
+    pub rows: Vec<Vec<String>>,
+}
+
+/// A table cell before expanding the merged cells.
+struct SpanCell {
+    header: bool,
+    text: String,
+    rowspan: usize,
+    colspan: usize,
+}
+
+impl SpanCell {
+    fn new(header: bool, text: String, attributes: &str) -> SpanCell {
+        let span = |name| citation::attribute(attributes, name)
+            .and_then(|x| x.trim().parse::<usize>().ok())
+            .map_or(1, |x| x.clamp(1, 1000));
+        SpanCell {
+            header,
+            text: text.replace('\n', " "),
+            rowspan: span("rowspan"),
+            colspan: span("colspan"),
+        }
+    }
+}
+
+impl Table {
+    /// Creates a table from a parsed wikitext table.
+    pub fn from_wikitext(table: &wikitext::Table) -> Table {
+        let rows = table.rows
+            .iter()
+            .map(|row| row.cells
+                .iter()
+                .map(|cell| SpanCell::new(
+                    cell.header,
+                    wikitext::plain_text(&cell.content),
+                    cell.attributes.as_deref().unwrap_or(""),
+                ))
+                .collect())
+            .collect();
+        let caption = table.caption.as_ref().map(|x| wikitext::plain_text(&x.content));
+        Table::from_cells(caption, rows)
+    }
+
+    /// Reads the `<table class="wikitable">` elements of `html`.
+    fn from_html(html: &str) -> Vec<Table> {
+        let mut tables = Vec::new();
+        let mut from = 0;
+        while let Some(i) = html[from..].find("<table") {
+            let start = from + i;
+            let open_end = match html[start..].find('>') {
+                Some(x) => start + x + 1,
+                None => break,
+            };
+            let classes = citation::attribute(&html[start + 6..open_end - 1], "class").unwrap_or_default();
+            if !classes.split_whitespace().any(|x| x == "wikitable") {
+                from = open_end;
+                continue;
+            }
+            let end = html_element_end(html, open_end, "table");
+            tables.push(Table::from_html_content(&html[open_end..end]));
+            from = end;
+        }
+        tables
+    }
+
+    /// Reads the rows of a table from the html inside its `<table>`,
+    /// skipping the tables nested in it.
+    fn from_html_content(html: &str) -> Table {
+        let mut caption = None;
+        let mut rows: Vec<Vec<SpanCell>> = Vec::new();
+        let mut from = 0;
+        while let Some(i) = html[from..].find('<') {
+            let start = from + i;
+            let name_len = html[start + 1..].find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(0);
+            let name = &html[start + 1..start + 1 + name_len];
+            let open_end = match html[start..].find('>') {
+                Some(x) => start + x + 1,
+                None => break,
+            };
+            from = open_end;
+            match name {
+                "table" => from = html_element_end(html, open_end, name),
+                "tr" => rows.push(Vec::new()),
+                "th" | "td" | "caption" => {
+                    let end = html_element_end(html, open_end, name);
+                    let text = html_text(&html[open_end..end]);
+                    if name == "caption" {
+                        caption = Some(text);
+                    } else {
+                        if rows.is_empty() {
+                            rows.push(Vec::new());
+                        }
+                        let attributes = &html[start + 1 + name_len..open_end - 1];
+                        rows.last_mut().unwrap().push(SpanCell::new(name == "th", text, attributes));
+                    }
+                    from = end;
+                },
+                _ => (),
+            }
+        }
+        Table::from_cells(caption, rows)
+    }
+
+    /// Expands the merged cells of `rows` into a grid and splits the header
+    /// rows at the top from the others.
+    fn from_cells(caption: Option<String>, rows: Vec<Vec<SpanCell>>) -> Table {
+        // The cells still spanning down into the next rows, by column
+        let mut pending: Vec<Option<(usize, bool, String)>> = Vec::new();
+        let mut grid: Vec<Vec<(bool, String)>> = Vec::new();
+        let take_pending = |line: &mut Vec<(bool, String)>, pending: &mut Vec<Option<(usize, bool, String)>>| {
+            while let Some(Some((left, header, ref text))) = pending.get_mut(line.len()) {
+                line.push((*header, text.clone()));
+                *left -= 1;
+                if *left == 0 {
+                    pending[line.len() - 1] = None;
+                }
+            }
+        };
+        for row in rows.into_iter().filter(|x| !x.is_empty()) {
+            let mut line: Vec<(bool, String)> = Vec::new();
+            for cell in row {
+                take_pending(&mut line, &mut pending);
+                for _ in 0..cell.colspan {
+                    if cell.rowspan > 1 {
+                        if pending.len() <= line.len() {
+                            pending.resize(line.len() + 1, None);
+                        }
+                        pending[line.len()] = Some((cell.rowspan - 1, cell.header, cell.text.clone()));
+                    }
+                    line.push((cell.header, cell.text.clone()));
+                }
+            }
+            take_pending(&mut line, &mut pending);
+            grid.push(line);
+        }
+        let width = grid.iter().map(|x| x.len()).max().unwrap_or(0);
+        for line in &mut grid {
+            line.resize(width, (false, String::new()));
+        }
+
+        let header_rows = grid.iter().take_while(|x| x.iter().all(|x| x.0)).count();
+        let headers = (0..width)
+            .map(|column| {
+                let mut texts: Vec<&str> = Vec::new();
+                for line in &grid[..header_rows] {
+                    let text = &*line[column].1;
+                    if !text.is_empty() && !texts.contains(&text) {
+                        texts.push(text);
+                    }
+                }
+                texts.join(" / ")
+            })
+            .collect();
+        let headers = if header_rows == 0 { Vec::new() } else { headers };
+        let rows = grid
+            .into_iter()
+            .skip(header_rows)
+            .map(|x| x.into_iter().map(|x| x.1).collect())
+            .collect();
+        Table { caption, headers, rows }
+    }
+
+    /// Writes the table as CSV, with the headers as the first line when
+    /// there are any.
+    pub fn to_csv(&self) -> String {
+        let line = |cells: &[String]| cells
+            .iter()
+            .map(|cell| if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut csv = String::new();
+        if !self.headers.is_empty() {
+            csv.push_str(&line(&self.headers));
+            csv.push('\n');
+        }
+        for row in &self.rows {
+            csv.push_str(&line(row));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Returns the table as a JSON object with its `caption`, `headers`
+    /// and `rows`.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "caption": self.caption,
+            "headers": self.headers,
+            "rows": self.rows,
+        })
+    }
+}
+
+/// The parts of a page `Page::parse` can return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseProp {
+    Text,
+    Sections,
+    Links,
+    Templates,
+    Images,
+    ExternalLinks,
+    Categories,
+    DisplayTitle,
+    HeadHtml,
+}
+
+impl ParseProp {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            ParseProp::Text => "text",
+            ParseProp::Sections => "sections",
+            ParseProp::Links => "links",
+            ParseProp::Templates => "templates",
+            ParseProp::Images => "images",
+            ParseProp::ExternalLinks => "externallinks",
+            ParseProp::Categories => "categories",
+            ParseProp::DisplayTitle => "displaytitle",
+            ParseProp::HeadHtml => "headhtml",
+        }
+    }
+}
+
+/// How `Page::get_extract` writes the section headings of plain text
+/// extracts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExtractSectionFormat {
+    /// As lines of text, with no markup.
+    #[default]
+    Plain,
+    /// As wikitext, like `== Heading ==`.
+    Wiki,
+    /// With the heading level between control characters, like
+    /// `\u{1}\u{2}2\u{2}\u{1}Heading`, to be formatted by the caller.
+    Raw,
+}
+
+impl ExtractSectionFormat {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            ExtractSectionFormat::Plain => "plain",
+            ExtractSectionFormat::Wiki => "wiki",
+            ExtractSectionFormat::Raw => "raw",
+        }
+    }
+}
+
+/// Options for `Page::get_extract`.
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Return at most this many sentences, from 1 to 10.
+    pub sentences: Option<u32>,
+    /// Return about this many characters, from 1 to 1200, finishing the
+    /// last word or sentence. Cannot be used together with `sentences`.
+    pub chars: Option<u32>,
+    /// Only return the text before the first heading.
+    pub intro: bool,
+    /// Return html instead of plain text.
+    pub html: bool,
+    /// The format of the section headings, only used for plain text.
+    pub section_format: ExtractSectionFormat,
+}
+
+/// Options for `Page::parse`.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// The parts to return, only the text if empty.
+    pub props: Vec<ParseProp>,
+    /// Leave out the "[edit]" links next to the headings.
+    pub disable_edit_section: bool,
+    /// Leave out the table of contents.
+    pub disable_toc: bool,
+    /// Return the html of the mobile site.
+    pub mobile_format: bool,
+    /// The skin used to render `ParseProp::HeadHtml`, like "vector".
+    pub use_skin: Option<String>,
+    /// Only parse the section with this index, as in `Section::index`.
+    pub section: Option<String>,
+}
+
+/// How `Page::get_markdown` writes the links to other articles.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MarkdownLinks {
+    /// `[text](https://en.wikipedia.org/wiki/Target)`
+    #[default]
+    Urls,
+    /// `[[Target|text]]`, as used by wikis and note taking apps.
+    WikiLinks,
+}
+
+/// Options for `Page::get_markdown`.
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownOptions {
+    pub links: MarkdownLinks,
+    /// Leave out the references instead of writing them as footnotes.
+    pub drop_references: bool,
+    /// Leave out the infoboxes instead of writing them as tables.
+    pub drop_infoboxes: bool,
+    /// Leave out the `{{Navbox}}` templates instead of writing them as
+    /// lists.
+    pub drop_navboxes: bool,
+}
+
+/// The result of `Page::parse`. The fields whose `ParseProp` was not
+/// requested are empty.
+#[derive(Debug, Default, PartialEq)]
+pub struct ParseResult {
+    pub title: String,
+    pub pageid: u64,
+    pub revid: Option<u64>,
+    pub text: Option<String>,
+    pub sections: Vec<Section>,
+    pub links: Vec<iter::Link>,
+    pub templates: Vec<iter::Template>,
+    /// The file names of the images, without the namespace prefix.
+    pub images: Vec<String>,
+    pub external_links: Vec<String>,
+    /// The categories, whose `sortkey_prefix` is the sortkey given in the
+    /// page, if any.
+    pub categories: Vec<iter::Category>,
+    /// The title as displayed, which may contain html.
+    pub display_title: Option<String>,
+    /// The html of the page up to the opening `<body>` tag.
+    pub head_html: Option<String>,
+}
+
+impl ParseResult {
+    fn from_value(parse: &serde_json::Value) -> ParseResult {
+        let array = |key: &str| parse.get(key).and_then(|x| x.as_array()).map(|x| &**x).unwrap_or(&[]);
+        let string = |value: &serde_json::Value, key: &str| value.get(key).and_then(|x| x.as_str()).map(|x| x.to_owned());
+        let ns = |value: &serde_json::Value| value.get("ns").and_then(|x| x.as_i64()).unwrap_or(0) as i32;
+        ParseResult {
+            title: string(parse, "title").unwrap_or_default(),
+            pageid: parse.get("pageid").and_then(|x| x.as_u64()).unwrap_or(0),
+            revid: parse.get("revid").and_then(|x| x.as_u64()),
+            text: parse.get("text").and_then(|x| string(x, "*")),
+            sections: array("sections").iter().filter_map(Section::from_value).collect(),
+            links: array("links").iter().filter_map(|x| Some(iter::Link {
+                title: string(x, "*")?,
+                ns: ns(x),
+                exists: x.get("exists").is_some(),
+            })).collect(),
+            templates: array("templates").iter().filter_map(|x| Some(iter::Template {
+                title: string(x, "*")?,
+                ns: ns(x),
+            })).collect(),
+            images: array("images").iter().filter_map(|x| x.as_str()).map(|x| x.replace('_', " ")).collect(),
+            external_links: array("externallinks").iter().filter_map(|x| x.as_str()).map(|x| x.to_owned()).collect(),
+            categories: array("categories").iter().filter_map(|x| Some(iter::Category {
+                title: string(x, "*")?.replace('_', " "),
+                sortkey: String::new(),
+                sortkey_prefix: string(x, "sortkey").unwrap_or_default(),
+                timestamp: None,
+                hidden: x.get("hidden").is_some(),
+            })).collect(),
+            display_title: string(parse, "displaytitle"),
+            head_html: parse.get("headhtml").and_then(|x| string(x, "*")),
+        }
+    }
+}
+
+/// The format of the content returned by `Page::get_section_content_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SectionFormat {
+    #[default]
+    PlainText,
+    /// Not available for sections coming from a template, see
+    /// `Page::get_section_wikitext`.
+    Wikitext,
+    Html,
+}
+
+/// Options for `Page::get_section_content_with`.
+#[derive(Debug, Clone, Default)]
+pub struct SectionQuery {
+    pub format: SectionFormat,
+    /// Keep the content of the subsections, with their headings.
+    pub include_subsections: bool,
+}
+
+/// A `== Heading ==` line in wikitext or in a plain text extract.
+struct Heading {
+    /// Byte offset of the start of the line.
+    start: usize,
+    /// Byte offset after the end of the line.
+    end: usize,
+    level: u32,
+    title: String,
+}
+
+/// Finds the heading lines of `text`.
+fn headings(text: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        let leading = trimmed.len() - trimmed.trim_start_matches('=').len();
+        let trailing = trimmed.len() - trimmed.trim_end_matches('=').len();
+        if leading > 0 && trailing > 0 && trimmed.len() > leading + trailing {
+            let level = leading.min(trailing).min(6);
+            headings.push(Heading {
+                start,
+                end: start + line.len(),
+                level: level as u32,
+                title: trimmed[level..trimmed.len() - level].trim().to_owned(),
+            });
+        }
+        start += line.len();
+    }
+    headings
+}
+
+/// Returns the text under `headings[position]`, up to the next heading of
+/// the same or a higher level, or up to any heading if `include_subsections`
+/// is false.
+fn heading_body(text: &str, headings: &[Heading], position: usize, include_subsections: bool) -> String {
+    let heading = &headings[position];
+    let end = headings[position + 1..]
+        .iter()
+        .find(|x| !include_subsections || x.level <= heading.level)
+        .map_or(text.len(), |x| x.start);
+    text[heading.end..end].trim().to_owned()
+}
+
+/// Removes the html tags of `html`, leaving the text.
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+    text
+}
+
+/// Finds the start of the html headings of `html`, including the
+/// `<div class="mw-heading">` wrapping them, and the end of each one.
+fn html_headings(html: &str) -> Vec<(usize, usize)> {
+    let mut headings = Vec::new();
+    let mut from = 0;
+    while let Some(i) = html[from..].find("<h") {
+        let start = from + i;
+        let level = html.as_bytes().get(start + 2).copied().unwrap_or(0);
+        if !(b'1'..=b'6').contains(&level) {
+            from = start + 2;
+            continue;
+        }
+        let close = format!("</h{}>", level as char);
+        let mut end = html[start..].find(&*close).map_or(html.len(), |x| start + x + close.len());
+        let mut wrapped_start = start;
+        if let Some(div) = html[..start].rfind("<div class=\"mw-heading") {
+            if html[div..start].find('>').is_some_and(|x| html[div + x + 1..start].trim().is_empty()) {
+                wrapped_start = div;
+                if let Some(rest) = html[end..].trim_start().strip_prefix("</div>") {
+                    end = html.len() - rest.len();
+                }
+            }
+        }
+        headings.push((wrapped_start, end));
+        from = end;
+    }
+    headings
+}
+
+/// Returns the html of a section fetched with `action=parse&section=N`,
+/// without its heading.
+fn html_section_body(html: &str, include_subsections: bool) -> String {
+    let html = html.trim();
+    let html = match html.strip_prefix("<div class=\"mw-parser-output\">") {
+        Some(inner) => inner.strip_suffix("</div>").unwrap_or(inner),
+        None => html,
+    };
+    let headings = html_headings(html);
+    let start = headings.first().map_or(0, |x| x.1);
+    let end = match headings.get(1) {
+        Some(next) if !include_subsections => next.0,
+        _ => html.len(),
+    };
+    html[start..end].trim().to_owned()
+}
+
+/// Finds the end of the `tag` element whose content starts at `start`,
+/// before its closing tag, skipping the elements of the same kind inside.
+fn html_element_end(html: &str, start: usize, tag: &str) -> usize {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut depth = 0;
+    let mut from = start;
+    while let Some(i) = html[from..].find(&*close) {
+        let end = from + i;
+        depth += html[from..end]
+            .match_indices(&*open)
+            .filter(|&(j, _)| html[from + j + open.len()..].starts_with([' ', '>']))
+            .count();
+        if depth == 0 {
+            return end;
+        }
+        depth -= 1;
+        from = end + close.len();
+    }
+    html.len()
+}
+
+/// Returns the content of the first `tag` cell of an html table row.
+fn html_cell<'t>(row: &'t str, tag: &str) -> Option<&'t str> {
+    let open = format!("<{}", tag);
+    let mut from = 0;
+    loop {
+        let start = from + row[from..].find(&*open)?;
+        let after = &row[start + open.len()..];
+        from = start + open.len();
+        if after.starts_with([' ', '>']) {
+            let content = start + open.len() + after.find('>')? + 1;
+            return Some(&row[content..html_element_end(row, content, tag)]);
+        }
+    }
+}
+
+/// Turns rendered html into plain text, without the references and styles,
+/// with one line per line break or list item.
+fn html_text(html: &str) -> String {
+    let mut html = html.to_owned();
+    for (open, close) in [("<sup", "</sup>"), ("<style", "</style>")] {
+        let mut from = 0;
+        while let Some(i) = html[from..].find(open) {
+            let start = from + i;
+            let tag_end = html[start..].find('>').map_or(html.len(), |x| start + x);
+            let is_reference = open == "<style" || html[start..tag_end].contains("reference");
+            match html[start..].find(close) {
+                Some(end) if is_reference => html.replace_range(start..start + end + close.len(), ""),
+                _ => from = start + open.len(),
+            }
+        }
+    }
+    let html = html.replace("<br", "\n<br").replace("<li", "\n<li").replace("</p>", "</p>\n");
+    wikitext::clean_whitespace(&wikitext::decode_entities(&strip_tags(&html)))
+}
+
+/// Returns the titles of the articles linked from rendered html.
+fn html_links(html: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    let mut from = 0;
+    while let Some(i) = html[from..].find("<a ") {
+        let start = from + i;
+        let end = html[start..].find('>').map_or(html.len(), |x| start + x);
+        let attributes = &html[start + 2..end];
+        from = end;
+        let is_article = citation::attribute(attributes, "href").is_some_and(|x| x.starts_with("/wiki/"));
+        if let Some(title) = citation::attribute(attributes, "title").filter(|_| is_article) {
+            links.push(wikitext::decode_entities(&title));
+        }
+    }
+    links
+}
+
+/// Keeps track of the latest autocomplete request, see
+/// `Wikipedia::autocomplete_latest`.
+#[derive(Debug, Default)]
+pub struct AutocompleteSession {
+    generation: AtomicU64,
+}
+
+impl AutocompleteSession {
+    /// Marks the start of a new request, making any previous one stale.
+    pub fn begin(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Whether the request started as `generation` is still the latest one.
+    pub fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+}
+
 #[derive(Debug)]
 pub struct Wikipedia<A: http::HttpClient> {
     /// HttpClient struct.
@@ -217,11 +1238,72 @@ impl<A: http::HttpClient> Wikipedia<A> {
 
     async fn query<'a, I>(&self, args: I) -> Result<serde_json::Value>
             where I: Iterator<Item=(&'a str, &'a str)> {
-        let response_str = self.client.get(&*self.base_url(), args).await.map_err(|_| Error::HTTPError)?;
+        self.query_url(&self.base_url(), args).await
+    }
+
+    /// Like `query`, using the api at `base_url`.
+    pub(crate) async fn query_url<'a, I>(&self, base_url: &str, args: I) -> Result<serde_json::Value>
+            where I: Iterator<Item=(&'a str, &'a str)> {
+        let response_str = self.client.get(base_url, args).await.map_err(|_| Error::HTTPError)?;
         let json = serde_json::from_str(&*response_str).map_err(Error::JSONError)?;
         Ok(json)
     }
 
+    async fn post_query<'a, I>(&self, args: I) -> Result<serde_json::Value>
+            where I: Iterator<Item=(&'a str, &'a str)> {
+        let response_str = self.client.post(&self.base_url(), args).await.map_err(|_| Error::HTTPError)?;
+        let json = serde_json::from_str(&response_str).map_err(Error::JSONError)?;
+        Ok(json)
+    }
+
+    /// Renders `text` as html, as if it was the content of the article
+    /// `title`. The text is sent in the body of a POST request, so it can
+    /// be large.
+    pub async fn parse_wikitext(&self, text: &str, title: Option<&str>) -> Result<String> {
+        let mut params = vec![
+            ("prop", "text"),
+            ("contentmodel", "wikitext"),
+            ("text", text),
+        ];
+        if let Some(title) = title {
+            params.push(("title", title));
+        }
+        params.extend(vec![
+            ("format", "json"),
+            ("action", "parse"),
+        ]);
+        let q = self.post_query(params.into_iter()).await?;
+
+        Ok(q
+            .as_object()
+            .and_then(|x| x.get("parse"))
+            .and_then(|x| x.get("text"))
+            .and_then(|x| x.get("*"))
+            .and_then(|x| x.as_str())
+            .ok_or(Error::JSONPathError)?
+            .to_owned())
+    }
+
+    /// Expands the templates, parser functions and variables in `text`,
+    /// returning the resulting wikitext. The text is sent in the body of a
+    /// POST request, so it can be large.
+    pub async fn expand_templates(&self, text: &str) -> Result<String> {
+        let q = self.post_query(vec![
+            ("prop", "wikitext"),
+            ("text", text),
+            ("format", "json"),
+            ("action", "expandtemplates"),
+        ].into_iter()).await?;
+
+        Ok(q
+            .as_object()
+            .and_then(|x| x.get("expandtemplates"))
+            .and_then(|x| x.get("wikitext"))
+            .and_then(|x| x.as_str())
+            .ok_or(Error::JSONPathError)?
+            .to_owned())
+    }
+
     /// Searches for a string and returns a list of relevant page titles.
     ///
     /// # Examples
@@ -280,24 +1362,325 @@ impl<A: http::HttpClient> Wikipedia<A> {
         Ok(results!(data, "geosearch"))
     }
 
-    /// Fetches `count` random articles' title.
-    pub async fn random_count(&self, count: u8) -> Result<Vec<String>> {
-        let data = self.query(vec![
-            ("list", "random"),
-            ("rnnamespace", "0"),
-            ("rnlimit", &*format!("{}", count)),
-            ("format", "json"),
-            ("action", "query"),
-        ].into_iter()).await?;
-        let r:Vec<String> = results!(data, "random");
-        Ok(r)
-    }
+    /// Search articles by location, around some coordinates or an article,
+    /// or within a bounding box. The results are sorted by distance.
+    pub async fn geosearch_with(&self, query: GeoQuery) -> Result<Vec<GeoHit>> {
+        let mut params = vec![("list".to_owned(), "geosearch".to_owned())];
+        match (query.coordinates, query.page, query.bbox) {
+            (Some((latitude, longitude)), None, None) => {
+                if !(-90.0..=90.0).contains(&latitude) {
+                    return Err(Error::InvalidParameter("latitude".to_string()))
+                }
+                if !(-180.0..=180.0).contains(&longitude) {
+                    return Err(Error::InvalidParameter("longitude".to_string()))
+                }
+                params.push(("gscoord".to_owned(), format!("{}|{}", latitude, longitude)));
+            },
+            (None, Some(page), None) => params.push(("gspage".to_owned(), page)),
+            (None, None, Some((top, left, bottom, right))) => {
+                params.push(("gsbbox".to_owned(), format!("{}|{}|{}|{}", top, left, bottom, right)));
+            },
+            _ => return Err(Error::InvalidParameter("query".to_string())),
+        }
+        if query.bbox.is_none() {
+            let radius = query.radius.unwrap_or(10000);
+            if !(10..=10000).contains(&radius) {
+                return Err(Error::InvalidParameter("radius".to_string()))
+            }
+            params.push(("gsradius".to_owned(), radius.to_string()));
+        }
+        if let Some(globe) = query.globe {
+            params.push(("gsglobe".to_owned(), globe));
+        }
+        if !query.namespaces.is_empty() {
+            params.push(("gsnamespace".to_owned(), join_namespaces(&query.namespaces)));
+        }
+        if query.details {
+            params.push(("gsprop".to_owned(), "type|dim|country|region".to_owned()));
+        }
+        params.push(("gsprimary".to_owned(), match query.primary {
+            GeoPrimary::Primary => "primary",
+            GeoPrimary::Secondary => "secondary",
+            GeoPrimary::All => "all",
+        }.to_owned()));
+        params.push(("gslimit".to_owned(), query.limit.unwrap_or(self.search_results).to_string()));
+        params.push(("format".to_owned(), "json".to_owned()));
+        params.push(("action".to_owned(), "query".to_owned()));
+        let data = self.query(params.iter().map(|x| (&*x.0, &*x.1))).await?;
+
+        Ok(data
+            .as_object()
+            .and_then(|x| x.get("query"))
+            .and_then(|x| x.get("geosearch"))
+            .and_then(|x| x.as_array())
+            .ok_or(Error::JSONPathError)?
+            .iter()
+            .filter_map(|x| {
+                let string = |key: &str| x.get(key).and_then(|x| x.as_str()).map(|x| x.to_owned());
+                Some(GeoHit {
+                    title: string("title")?,
+                    pageid: x.get("pageid").and_then(|x| x.as_u64()).unwrap_or(0),
+                    ns: x.get("ns").and_then(|x| x.as_i64()).unwrap_or(0) as i32,
+                    lat: x.get("lat")?.as_f64()?,
+                    lon: x.get("lon")?.as_f64()?,
+                    dist: x.get("dist").and_then(|x| x.as_f64()),
+                    primary: x.get("primary").is_some(),
+                    kind: string("type"),
+                    dim: x.get("dim").and_then(|x| x.as_u64().or_else(|| x.as_str().and_then(|x| x.parse().ok()))),
+                    country: string("country"),
+                    region: string("region"),
+                })
+            })
+            .collect())
+    }
+
+    /// Suggests up to `limit` articles whose title starts with `prefix`,
+    /// ordered by relevance. Meant for search-as-you-type boxes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wikipedia_wasm::{Wikipedia, http};
+    ///
+    /// #[tokio::main]
+    /// async fn main()
+    /// {
+    ///     let wiki = Wikipedia::<http::default::Client>::default();
+    ///     let results = wiki.autocomplete("Madison Squ", 5).await.unwrap();
+    ///     assert!(results.iter().any(|s| s.title == "Madison Square Garden"));
+    /// }
+    /// ```
+    pub async fn autocomplete(&self, prefix: &str, limit: u32) -> Result<Vec<Suggestion>> {
+        self.autocomplete_in(prefix, limit, &[0]).await
+    }
+
+    /// Like `autocomplete`, but only suggests pages in the given `namespaces`.
+    pub async fn autocomplete_in(&self, prefix: &str, limit: u32, namespaces: &[i32]) -> Result<Vec<Suggestion>> {
+        if !(1..=500).contains(&limit) {
+            return Err(Error::InvalidParameter("limit".to_string()))
+        }
+        if namespaces.is_empty() {
+            return Err(Error::InvalidParameter("namespaces".to_string()))
+        }
+        let namespaces = join_namespaces(namespaces);
+        let data = self.query(vec![
+            ("generator", "prefixsearch"),
+            ("gpssearch", prefix),
+            ("gpslimit", &*format!("{}", limit)),
+            ("gpsnamespace", &*namespaces),
+            ("prop", "description|info"),
+            ("inprop", "url"),
+            ("redirects", ""),
+            ("format", "json"),
+            ("action", "query"),
+        ].into_iter()).await?;
+
+        let pages = match data
+            .as_object()
+            .and_then(|x| x.get("query"))
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.get("pages"))
+            .and_then(|x| x.as_object()) {
+            Some(p) => p,
+            // No page matched the prefix
+            None => return Ok(Vec::new()),
+        };
+        let mut suggestions = pages
+            .values()
+            .filter_map(|x| x.as_object())
+            .map(|x| (
+                x.get("index").and_then(|x| x.as_u64()).unwrap_or(u64::MAX),
+                Suggestion {
+                    title: x.get("title").and_then(|x| x.as_str()).unwrap_or("").to_owned(),
+                    ns: x.get("ns").and_then(|x| x.as_i64()).unwrap_or(0) as i32,
+                    description: x.get("description").and_then(|x| x.as_str()).map(|x| x.to_owned()),
+                    url: x.get("fullurl").and_then(|x| x.as_str()).unwrap_or("").to_owned(),
+                },
+            ))
+            .collect::<Vec<_>>();
+        suggestions.sort_by_key(|x| x.0);
+        Ok(suggestions.into_iter().map(|x| x.1).collect())
+    }
+
+    /// Like `autocomplete`, but returns `None` if another request was started
+    /// with the same `session` while this one was in flight, so responses to
+    /// stale keystrokes can be dropped.
+    ///
+    /// Dropping the returned future also cancels the underlying request.
+    pub async fn autocomplete_latest(&self, session: &AutocompleteSession, prefix: &str, limit: u32)
+            -> Result<Option<Vec<Suggestion>>> {
+        let generation = session.begin();
+        let suggestions = self.autocomplete(prefix, limit).await?;
+        Ok(if session.is_current(generation) {
+            Some(suggestions)
+        } else {
+            None
+        })
+    }
+
+    /// Fetches `count` random articles' title.
+    pub async fn random_count(&self, count: u8) -> Result<Vec<String>> {
+        let data = self.query(vec![
+            ("list", "random"),
+            ("rnnamespace", "0"),
+            ("rnlimit", &*format!("{}", count)),
+            ("format", "json"),
+            ("action", "query"),
+        ].into_iter()).await?;
+        let r:Vec<String> = results!(data, "random");
+        Ok(r)
+    }
 
     /// Fetches a random article's title.
     pub async fn random(&self) -> Result<Option<String>> {
         Ok(self.random_count(1).await?.into_iter().next())
     }
 
+    /// Fetches up to `limit` pages linking to the urls matching `query`,
+    /// usually to find which articles cite a domain.
+    pub async fn external_url_usage(&self, query: iter::ReferenceQuery, limit: u32) -> Result<Vec<UrlUsage>> {
+        let mut usages = Vec::new();
+        let mut cont: Option<Vec<(String, String)>> = None;
+        while usages.len() < limit as usize {
+            let eulimit = format!("{}", std::cmp::min(limit as usize - usages.len(), 500));
+            let mut params = vec![
+                ("list", "exturlusage"),
+                ("euprop", "ids|title|url"),
+                ("eulimit", &*eulimit),
+                ("euexpandurl", ""),
+                ("format", "json"),
+                ("action", "query"),
+            ];
+            if let Some(ref protocol) = query.protocol {
+                params.push(("euprotocol", &**protocol));
+            }
+            if let Some(ref domain) = query.domain {
+                params.push(("euquery", &**domain));
+            }
+            match cont {
+                Some(ref v) => {
+                    for x in v.iter() { params.push((&*x.0, &*x.1)); }
+                },
+                None => params.push(("continue", "")),
+            }
+            let q = self.query(params.into_iter()).await?;
+
+            usages.extend(q
+                .as_object()
+                .and_then(|x| x.get("query"))
+                .and_then(|x| x.as_object())
+                .and_then(|x| x.get("exturlusage"))
+                .and_then(|x| x.as_array())
+                .ok_or(Error::JSONPathError)?
+                .iter()
+                .filter_map(|x| x.as_object())
+                .map(|x| UrlUsage {
+                    pageid: x.get("pageid").and_then(|x| x.as_u64()).unwrap_or(0),
+                    ns: x.get("ns").and_then(|x| x.as_i64()).unwrap_or(0) as i32,
+                    title: x.get("title").and_then(|x| x.as_str()).unwrap_or("").to_owned(),
+                    reference: iter::Reference::from_url(x.get("url").and_then(|x| x.as_str()).unwrap_or("")),
+                }));
+
+            cont = parse_cont(&q)?;
+            if cont.is_none() {
+                break;
+            }
+        }
+        usages.truncate(limit as usize);
+        Ok(usages)
+    }
+
+    /// Fetches the representative image of each of `titles`, as a thumbnail
+    /// of `width` pixels or the original image if `None`. The results are in
+    /// the same order as `titles`.
+    pub async fn get_page_images(&self, titles: &[String], width: Option<u32>) -> Result<Vec<(String, Option<PageImage>)>> {
+        let mut images = Vec::with_capacity(titles.len());
+        let size = width.map(|x| x.to_string());
+        // The API accepts up to 50 titles per request
+        for chunk in titles.chunks(50) {
+            let joined = chunk.join("|");
+            let mut params = vec![
+                ("prop", "pageimages"),
+                ("piprop", if size.is_some() { "thumbnail|name" } else { "original|name" }),
+                ("pilimit", "max"),
+            ];
+            if let Some(ref size) = size {
+                params.push(("pithumbsize", size));
+            }
+            params.extend(vec![
+                ("redirects", ""),
+                ("format", "json"),
+                ("action", "query"),
+                ("titles", &*joined),
+            ]);
+            let q = self.query(params.into_iter()).await?;
+
+            let query = q.as_object().and_then(|x| x.get("query")).ok_or(Error::JSONPathError)?;
+            let pages = query.get("pages").and_then(|x| x.as_object()).ok_or(Error::JSONPathError)?;
+            // Follows the normalizations and redirects applied to a title
+            let resolve = |title: &str, key: &str| query
+                .get(key)
+                .and_then(|x| x.as_array())
+                .and_then(|x| x.iter().find(|x| x.get("from").and_then(|x| x.as_str()) == Some(title)))
+                .and_then(|x| x.get("to"))
+                .and_then(|x| x.as_str())
+                .map(|x| x.to_owned());
+            for title in chunk {
+                let normalized = resolve(title, "normalized").unwrap_or_else(|| title.clone());
+                let target = resolve(&normalized, "redirects").unwrap_or(normalized);
+                let image = pages
+                    .values()
+                    .find(|x| x.get("title").and_then(|x| x.as_str()) == Some(&*target))
+                    .and_then(|x| PageImage::from_page(x, if width.is_some() { "thumbnail" } else { "original" }));
+                images.push((title.clone(), image));
+            }
+        }
+        Ok(images)
+    }
+
+    /// Creates an iterator to view the members of `category`, which must be
+    /// the full title of the category, for example "Category:Physics".
+    pub async fn category_members(&self, category: String, query: iter::CategoryMemberQuery)
+            -> Result<Iter<'_, A, iter::CategoryMember>> {
+        self.page_from_title(category).get_category_members(query).await
+    }
+
+    /// Fetches the members of `category` and of its subcategories, up to
+    /// `max_depth` levels deep. Each category is only visited once, even if
+    /// the category tree has cycles, and each member is only returned once.
+    pub async fn category_members_recursive(&self, category: String, query: iter::CategoryMemberQuery, max_depth: u32)
+            -> Result<Vec<iter::CategoryMember>> {
+        // Subcategories have to be listed to walk them, even if they are
+        // filtered out of the results.
+        let mut walk_query = query.clone();
+        if !walk_query.types.is_empty() && !walk_query.types.contains(&iter::CategoryMemberType::Subcat) {
+            walk_query.types.push(iter::CategoryMemberType::Subcat);
+        }
+        if !walk_query.namespaces.is_empty() && !walk_query.namespaces.contains(&CATEGORY_NAMESPACE) {
+            walk_query.namespaces.push(CATEGORY_NAMESPACE);
+        }
+
+        let mut visited = HashSet::new();
+        let mut seen = HashSet::new();
+        let mut members = Vec::new();
+        let mut pending = VecDeque::new();
+        visited.insert(category.clone());
+        pending.push_back((category, 0));
+        while let Some((category, depth)) = pending.pop_front() {
+            let mut iter = self.category_members(category, walk_query.clone()).await?;
+            while let Some(member) = iter.next().await {
+                if member.member_type == iter::CategoryMemberType::Subcat &&
+                        depth < max_depth && visited.insert(member.title.clone()) {
+                    pending.push_back((member.title.clone(), depth + 1));
+                }
+                if query.matches(&member) && seen.insert(member.title.clone()) {
+                    members.push(member);
+                }
+            }
+        }
+        Ok(members)
+    }
+
     /// Creates a new `Page` given a `title`.
     pub fn page_from_title<'a>(&'a self, title: String) -> Page<'a, A> {
         Page::from_title(self, title)
@@ -307,9 +1690,35 @@ impl<A: http::HttpClient> Wikipedia<A> {
     pub fn page_from_pageid<'a>(&'a self, pageid: String) -> Page<'a, A> {
         Page::from_pageid(self, pageid)
     }
+
+    /// Creates a `Wikidata` using the client of this object.
+    pub fn wikidata<'a>(&'a self) -> wikidata::Wikidata<'a, A> {
+        wikidata::Wikidata::new(self)
+    }
+
+    /// Creates a new `Page` from the url of an article in this wiki, such
+    /// as "https://en.wikipedia.org/wiki/World_War_II". Returns `None` if the
+    /// url points somewhere else.
+    pub fn page_from_url<'a>(&'a self, url: &str) -> Option<Page<'a, A>> {
+        let base_url = self.base_url();
+        let site = base_url
+            .strip_suffix("/w/api.php")?
+            .split_once("://")?
+            .1;
+        let title = url
+            .split_once("://")?
+            .1
+            .strip_prefix(site)?
+            .strip_prefix("/wiki/")?;
+        let title = percent_decode(title.split(['?', '#']).next().unwrap_or(title))?.replace('_', " ");
+        if title.is_empty() {
+            return None;
+        }
+        Some(self.page_from_title(title))
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum TitlePageId {
     Title(String),
     PageId(String),
@@ -322,6 +1731,23 @@ impl TitlePageId {
             TitlePageId::PageId(ref s) => ("pageids".to_owned(), s.clone()),
         }
     }
+
+    /// Like `query_param`, for `action=parse`.
+    fn parse_param(&self) -> (&'static str, String) {
+        match *self {
+            TitlePageId::Title(ref s) => ("page", s.clone()),
+            TitlePageId::PageId(ref s) => ("pageid", s.clone()),
+        }
+    }
+
+    /// Like `query_param`, for `list` modules whose parameters are named
+    /// `{prefix}title` and `{prefix}pageid`.
+    fn list_param(&self, prefix: &str) -> (String, String) {
+        match *self {
+            TitlePageId::Title(ref s) => (format!("{}title", prefix), s.clone()),
+            TitlePageId::PageId(ref s) => (format!("{}pageid", prefix), s.clone()),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -330,6 +1756,15 @@ pub struct Page<'a, A: 'a + http::HttpClient> {
     identifier: TitlePageId,
 }
 
+impl<'a, A: http::HttpClient> Clone for Page<'a, A> {
+    fn clone(&self) -> Self {
+        Page {
+            wikipedia: self.wikipedia,
+            identifier: self.identifier.clone(),
+        }
+    }
+}
+
 /// A wikipedia article.
 impl<'a, A: http::HttpClient> Page<'a, A> {
     /// Creates a new `Page` given a `title`.
@@ -448,7 +1883,7 @@ impl<'a, A: http::HttpClient> Page<'a, A> {
         pages.unwrap().get(pageid)
     }
 
-    /// Gets the markdown content of the article.
+    /// Gets the plain text content of the article.
     #[async_recursion::async_recursion(?Send)]
     pub async fn get_content(&self) -> Result<String> {
         let qp = self.identifier.query_param();
@@ -475,39 +1910,203 @@ impl<'a, A: http::HttpClient> Page<'a, A> {
             .to_owned())
     }
 
-    /// Gets the html content of the article.
+    /// Gets an extract of the article, limited and formatted as set in
+    /// `options`.
     #[async_recursion::async_recursion(?Send)]
+    pub async fn get_extract(&self, options: ExtractOptions) -> Result<String> {
+        let mut params = vec![("prop".to_owned(), "extracts".to_owned())];
+        match (options.sentences, options.chars) {
+            (Some(_), Some(_)) => return Err(Error::InvalidParameter("chars".to_string())),
+            (Some(sentences), None) => {
+                if !(1..=10).contains(&sentences) {
+                    return Err(Error::InvalidParameter("sentences".to_string()))
+                }
+                params.push(("exsentences".to_owned(), sentences.to_string()));
+            },
+            (None, Some(chars)) => {
+                if !(1..=1200).contains(&chars) {
+                    return Err(Error::InvalidParameter("chars".to_string()))
+                }
+                params.push(("exchars".to_owned(), chars.to_string()));
+            },
+            (None, None) => (),
+        }
+        if options.intro {
+            params.push(("exintro".to_owned(), "".to_owned()));
+        }
+        if !options.html {
+            params.push(("explaintext".to_owned(), "".to_owned()));
+            params.push(("exsectionformat".to_owned(), options.section_format.as_str().to_owned()));
+        }
+        let qp = self.identifier.query_param();
+        params.extend(vec![
+            ("redirects".to_owned(), "".to_owned()),
+            ("format".to_owned(), "json".to_owned()),
+            ("action".to_owned(), "query".to_owned()),
+            qp,
+        ]);
+        let q = self.wikipedia.query(params.iter().map(|(k, v)| (&**k, &**v))).await?;
+
+        if let Some(r) = self.redirect(&q) {
+            return Page::from_title(self.wikipedia, r).get_extract(options).await;
+        }
+
+        Ok(self.get_first_page(&q)
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.get("extract"))
+            .and_then(|x| x.as_str())
+            .ok_or(Error::JSONPathError)?
+            .to_owned())
+    }
+
+    /// Gets the html content of the article.
     pub async fn get_html_content(&self) -> Result<String> {
+        self.parse(ParseOptions::default())
+            .await?
+            .text
+            .ok_or(Error::JSONPathError)
+    }
+
+    /// Parses the article with `action=parse`, returning the parts chosen
+    /// in `options`.
+    pub async fn parse(&self, options: ParseOptions) -> Result<ParseResult> {
+        let pp = self.identifier.parse_param();
+        let props = if options.props.is_empty() {
+            "text".to_owned()
+        } else {
+            options.props.iter().map(|x| x.as_str()).collect::<Vec<_>>().join("|")
+        };
+        let mut params = vec![("prop", &*props)];
+        if let Some(ref section) = options.section {
+            params.push(("section", section));
+        }
+        if options.disable_edit_section {
+            params.push(("disableeditsection", ""));
+        }
+        if options.disable_toc {
+            params.push(("disabletoc", ""));
+        }
+        if options.mobile_format {
+            params.push(("mobileformat", ""));
+        }
+        if let Some(ref skin) = options.use_skin {
+            params.push(("useskin", skin));
+        }
+        params.extend(vec![
+            ("redirects", ""),
+            ("format", "json"),
+            ("action", "parse"),
+            (pp.0, &*pp.1),
+        ]);
+        let q = self.wikipedia.query(params.into_iter()).await?;
+
+        let parse = q.as_object().and_then(|x| x.get("parse")).ok_or(Error::JSONPathError)?;
+        Ok(ParseResult::from_value(parse))
+    }
+
+    /// Fetches the wikitext of the latest revision of the article.
+    pub async fn get_wikitext(&self) -> Result<Wikitext> {
+        self.request_wikitext(None).await
+    }
+
+    /// Fetches the wikitext of the section with the given `index`, as in
+    /// `Section::index`, including its heading. Sections coming from a
+    /// template, with an index like "T-1", are not part of the article's
+    /// wikitext and return `Error::InvalidParameter`.
+    pub async fn get_section_wikitext(&self, index: &str) -> Result<Wikitext> {
+        if index.starts_with("T-") {
+            return Err(Error::InvalidParameter("index".to_string()))
+        }
+        self.request_wikitext(Some(index)).await
+    }
+
+    #[async_recursion::async_recursion(?Send)]
+    async fn request_wikitext(&self, section: Option<&str>) -> Result<Wikitext> {
         let qp = self.identifier.query_param();
-        let q = self.wikipedia.query(vec![
+        let mut params = vec![
             ("prop", "revisions"),
-            ("rvprop", "content"),
-            ("rvlimit", "1"),
-            ("rvparse", ""),
+            ("rvprop", "ids|content"),
+            ("rvslots", "main"),
+        ];
+        if let Some(section) = section {
+            params.push(("rvsection", section));
+        }
+        params.extend(vec![
             ("redirects", ""),
             ("format", "json"),
             ("action", "query"),
             (&*qp.0, &*qp.1),
-        ].into_iter()).await?;
+        ]);
+        let q = self.wikipedia.query(params.into_iter()).await?;
 
-        match self.redirect(&q) {
-            Some(r) => return Page::from_title(&self.wikipedia, r).get_html_content().await,
-            None => (),
+        if let Some(r) = self.redirect(&q) {
+            return Page::from_title(self.wikipedia, r).request_wikitext(section).await;
         }
 
-        Ok(self.get_first_page(&q)
-            .and_then(|x| x.as_object())
+        let revision = self.get_first_page(&q)
             .and_then(|x| x.get("revisions"))
             .and_then(|x| x.as_array())
-            .and_then(|x| x.into_iter().next())
-            .and_then(|x| x.as_object())
-            .and_then(|x| x.get("*"))
-            .and_then(|x| x.as_str())
-            .ok_or(Error::JSONPathError)?
-            .to_owned())
+            .and_then(|x| x.iter().next())
+            .ok_or(Error::JSONPathError)?;
+        let main = revision
+            .get("slots")
+            .and_then(|x| x.get("main"))
+            .ok_or(Error::JSONPathError)?;
+        Ok(Wikitext {
+            content: main.get("*").and_then(|x| x.as_str()).ok_or(Error::JSONPathError)?.to_owned(),
+            revid: revision.get("revid").and_then(|x| x.as_u64()).ok_or(Error::JSONPathError)?,
+            content_model: main.get("contentmodel").and_then(|x| x.as_str()).unwrap_or("wikitext").to_owned(),
+        })
+    }
+
+    /// Gets the citations of the article, parsed from its `{{cite ...}}`
+    /// templates.
+    pub async fn get_citations(&self) -> Result<Vec<citation::Citation>> {
+        Ok(citation::parse_citations(&self.get_wikitext().await?.content))
+    }
+
+    /// Gets the tables written in the wikitext of the article, including
+    /// the ones nested in other tables or templates.
+    pub async fn get_tables(&self) -> Result<Vec<Table>> {
+        let nodes = self.get_wikitext().await?.parse();
+        Ok(wikitext::descendants(&nodes)
+            .into_iter()
+            .filter_map(|node| match *node {
+                wikitext::Node::Table(ref table) => Some(Table::from_wikitext(table)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Gets the tables with the `wikitable` class in the html of the
+    /// article, which unlike `get_tables` includes the tables made by
+    /// templates.
+    pub async fn get_html_tables(&self) -> Result<Vec<Table>> {
+        Ok(Table::from_html(&self.get_html_content().await?))
+    }
+
+    /// Gets the article as CommonMark, with GitHub flavored tables and
+    /// footnotes, converted from its wikitext.
+    pub async fn get_markdown(&self, options: MarkdownOptions) -> Result<String> {
+        let nodes = self.get_wikitext().await?.parse();
+        let base_url = self.wikipedia.base_url();
+        let wiki_url = format!("{}/wiki/", base_url.strip_suffix("/w/api.php").unwrap_or(&base_url));
+        Ok(markdown::from_wikitext(&nodes, &options, &wiki_url))
+    }
+
+    /// Gets the infobox of the article, from the first `{{Infobox ...}}`
+    /// template of its wikitext or, when there is none, from the first
+    /// `table.infobox` of its html.
+    pub async fn get_infobox(&self) -> Result<Option<Infobox>> {
+        let nodes = self.get_wikitext().await?.parse();
+        if let Some(infobox) = Infobox::from_wikitext(&nodes) {
+            return Ok(Some(infobox));
+        }
+        Ok(Infobox::from_html(&self.get_html_content().await?))
     }
 
-    /// Gets a summary of the article.
+    /// Gets a summary of the article. See `get_extract` to choose its
+    /// length and format.
     #[async_recursion::async_recursion(?Send)]
     pub async fn get_summary(&self) -> Result<String> {
         let qp = self.identifier.query_param();
@@ -534,38 +2133,43 @@ impl<'a, A: http::HttpClient> Page<'a, A> {
             .to_owned())
     }
 
-    /// Receive a json object and extracts any `continue` parameters to be
-    /// used when browsing following pages.
-    fn parse_cont(&self, q: &serde_json::Value) -> Result<Option<Vec<(String, String)>>> {
-        let cont = match q
-            .as_object()
-            .and_then(|x| x.get("continue"))
-            .and_then(|x| x.as_object()) {
-            Some(v) => v,
-            None => return Ok(None),
-        };
-        let mut cont_v = vec![];
-        for (k, v) in cont.into_iter() {
-            let value = match *v {
-                serde_json::Value::Null => "".to_owned(),
-                serde_json::Value::Bool(b) => if b { "1" } else { "0" }.to_owned(),
-                serde_json::Value::Number(ref f) => format!("{}", f),
-                serde_json::Value::String(ref s) => s.clone(),
-                _ => return Err(Error::JSONPathError),
-            };
-            cont_v.push((k.clone(), value));
-        }
-        Ok(Some(cont_v))
-    }
-
-    async fn request_images(&self, cont: &Option<Vec<(String, String)>>) ->
+    async fn request_images(&self, query: &iter::ImageQuery, cont: &Option<Vec<(String, String)>>) ->
             Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
-        cont!(self, cont,
+        let mut iiprop = "url".to_owned();
+        if query.details {
+            iiprop.push_str("|size|mime|sha1|timestamp|user");
+        }
+        if query.license {
+            iiprop.push_str("|extmetadata");
+        }
+        let width = query.thumb_width.map(|x| x.to_string());
+        let height = query.thumb_height.map(|x| x.to_string());
+        let mut params = vec![
             ("generator", "images"),
             ("gimlimit", &*self.wikipedia.images_results),
             ("prop", "imageinfo"),
-            ("iiprop", "url")
-        ).await
+            ("iiprop", &*iiprop),
+        ];
+        if query.license {
+            params.push(("iiextmetadatafilter", "LicenseShortName|Artist|Attribution|Credit"));
+        }
+        if let Some(ref width) = width {
+            params.push(("iiurlwidth", width));
+        }
+        if let Some(ref height) = height {
+            params.push(("iiurlheight", height));
+        }
+        let (q, cont) = self.request_page(params, cont).await?;
+
+        let pages = q
+            .as_object()
+            .and_then(|x| x.get("query"))
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.get("pages"))
+            .and_then(|x| x.as_object())
+            .ok_or(Error::JSONPathError)?;
+
+        Ok((pages.values().cloned().collect(), cont))
     }
 
     /// Creates an iterator to view all images in the `Page`.
@@ -573,24 +2177,30 @@ impl<'a, A: http::HttpClient> Page<'a, A> {
         Iter::new(&self).await
     }
 
-    async fn request_extlinks(&self, cont: &Option<Vec<(String, String)>>) ->
+    /// Like `get_images`, using `query` to choose which details to fetch.
+    pub async fn get_images_with(&self, query: iter::ImageQuery) -> Result<Iter<'a, A, iter::Image>> {
+        Iter::with_query(self, query).await
+    }
+
+    async fn request_extlinks(&self, query: &iter::ReferenceQuery, cont: &Option<Vec<(String, String)>>) ->
             Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
-        let a:Result<(Vec<serde_json::Value>, _)> = cont!(self, cont,
+        let mut params = vec![
             ("prop", "extlinks"),
-            ("ellimit", &*self.wikipedia.links_results)
-        ).await;
-        a.map(|(pages, cont)| {
-            let page = match pages.into_iter().next() {
-                Some(p) => p,
-                None => return (Vec::new(), None),
-            };
-            (page
-                .as_object()
-                .and_then(|x| x.get("extlinks"))
-                .and_then(|x| x.as_array())
-                .map(|x| x.into_iter().cloned().collect())
-                .unwrap_or(Vec::new()), cont)
-        })
+            ("ellimit", &*self.wikipedia.links_results),
+            ("elexpandurl", ""),
+        ];
+        if let Some(ref domain) = query.domain {
+            if let Some(ref protocol) = query.protocol {
+                params.push(("elprotocol", &**protocol));
+            }
+            params.push(("elquery", &**domain));
+        }
+        let (mut links, cont) = self.request_prop("extlinks", params, cont).await?;
+        if let (Some(ref protocol), None) = (&query.protocol, &query.domain) {
+            // elprotocol is ignored without elquery
+            links.retain(|x| iter::Reference::from_value(x).is_some_and(|x| x.protocol.eq_ignore_ascii_case(protocol)));
+        }
+        Ok((links, cont))
     }
 
     /// Creates an iterator to view all references (external links) in the `Page`.
@@ -598,25 +2208,47 @@ impl<'a, A: http::HttpClient> Page<'a, A> {
         Iter::new(&self).await
     }
 
-    async fn request_links(&self, cont: &Option<Vec<(String, String)>>) ->
+    /// Like `get_references`, using `query` to filter the references.
+    pub async fn get_references_with(&self, query: iter::ReferenceQuery) -> Result<Iter<'a, A, iter::Reference>> {
+        Iter::with_query(self, query).await
+    }
+
+    async fn request_links(&self, query: &iter::LinkQuery, cont: &Option<Vec<(String, String)>>) ->
             Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
-        let a:Result<(Vec<serde_json::Value>, _)> = cont!(self, cont,
-            ("prop", "links"),
-            ("plnamespace", "0"),
-            ("ellimit", &*self.wikipedia.links_results)
-        ).await;
-        a.map(|(pages, cont)| {
-            let page = match pages.into_iter().next() {
-                Some(p) => p,
-                None => return (Vec::new(), None),
-            };
-            (page
-                .as_object()
-                .and_then(|x| x.get("links"))
-                .and_then(|x| x.as_array())
-                .map(|x| x.into_iter().cloned().collect())
-                .unwrap_or(Vec::new()), cont)
-        })
+        let namespaces = join_namespaces(&query.namespaces);
+        let titles = query.titles.join("|");
+        // Using links as a generator tells missing pages apart
+        let mut params = vec![
+            ("generator", "links"),
+            ("gpllimit", &*self.wikipedia.links_results),
+            ("prop", "info"),
+        ];
+        if !query.namespaces.is_empty() {
+            params.push(("gplnamespace", &*namespaces));
+        }
+        if !query.titles.is_empty() {
+            params.push(("gpltitles", &*titles));
+        }
+        let (q, cont) = self.request_page(params, cont).await?;
+
+        let mut links = match q
+            .as_object()
+            .and_then(|x| x.get("query"))
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.get("pages"))
+            .and_then(|x| x.as_object()) {
+            Some(pages) => pages.values().cloned().collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+        // Pages are keyed by id, sort them the way `prop=links` would
+        links.sort_by(|a, b| {
+            let key = |x: &serde_json::Value| (
+                x.get("ns").and_then(|x| x.as_i64()).unwrap_or(0),
+                x.get("title").and_then(|x| x.as_str()).unwrap_or("").to_owned(),
+            );
+            key(a).cmp(&key(b))
+        });
+        Ok((links, cont))
     }
 
     /// Creates an iterator to view all internal links in the `Page`.
@@ -624,24 +2256,73 @@ impl<'a, A: http::HttpClient> Page<'a, A> {
         Iter::new(&self).await
     }
 
-    async fn request_categories(&self, cont: &Option<Vec<(String, String)>>) ->
-            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
-        let a:Result<(Vec<serde_json::Value>, _)> = cont!(self, cont,
+    /// Like `get_links`, using `query` to choose which links to view.
+    pub async fn get_links_with(&self, query: iter::LinkQuery) -> Result<Iter<'a, A, iter::Link>> {
+        Iter::with_query(self, query).await
+    }
+
+    async fn request_templates(&self, query: &iter::TemplateQuery, cont: &Option<Vec<(String, String)>>) ->
+            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
+        let namespaces = join_namespaces(&query.namespaces);
+        let mut params = vec![
+            ("prop", "templates"),
+            ("tllimit", &*self.wikipedia.links_results),
+        ];
+        if !query.namespaces.is_empty() {
+            params.push(("tlnamespace", &*namespaces));
+        }
+        self.request_prop("templates", params, cont).await
+    }
+
+    /// Creates an iterator to view all templates used in the `Page`.
+    pub async fn get_templates(&self) -> Result<Iter<'a, A, iter::Template>> {
+        Iter::new(self).await
+    }
+
+    /// Like `get_templates`, using `query` to filter the templates.
+    pub async fn get_templates_with(&self, query: iter::TemplateQuery) -> Result<Iter<'a, A, iter::Template>> {
+        Iter::with_query(self, query).await
+    }
+
+    async fn request_categories(&self, query: &iter::CategoryQuery, prefixes: &mut Option<Vec<String>>,
+                                cont: &Option<Vec<(String, String)>>) ->
+            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
+        let mut params = vec![
             ("prop", "categories"),
-            ("cllimit", &*self.wikipedia.categories_results)
-        ).await;
-        a.map(|(pages, cont)| {
-            let page = match pages.into_iter().next() {
-                Some(p) => p,
-                None => return (Vec::new(), None),
-            };
-            (page
-                .as_object()
-                .and_then(|x| x.get("categories"))
-                .and_then(|x| x.as_array())
-                .map(|x| x.into_iter().cloned().collect())
-                .unwrap_or(Vec::new()), cont)
-        })
+            ("clprop", "sortkey|timestamp|hidden"),
+            ("cllimit", &*self.wikipedia.categories_results),
+        ];
+        // The namespace names are needed to strip the localized prefix, and
+        // are only returned by the first request
+        if prefixes.is_none() {
+            params.push(("meta", "siteinfo"));
+            params.push(("siprop", "namespaces"));
+        }
+        if query.exclude_hidden {
+            params.push(("clshow", "!hidden"));
+        }
+        let (q, cont) = self.request_page(params, cont).await?;
+
+        let prefixes = prefixes.get_or_insert_with(|| namespace_names(&q, CATEGORY_NAMESPACE));
+        let page = match self.get_first_page(&q) {
+            Some(p) => p,
+            None => return Ok((Vec::new(), None)),
+        };
+        Ok((page
+            .as_object()
+            .and_then(|x| x.get("categories"))
+            .and_then(|x| x.as_array())
+            .map(|x| x.iter().cloned().map(|mut category| {
+                let name = category
+                    .get("title")
+                    .and_then(|x| x.as_str())
+                    .map(|x| strip_namespace(x, prefixes).to_owned());
+                if let (Some(name), Some(obj)) = (name, category.as_object_mut()) {
+                    obj.insert("title".to_owned(), serde_json::Value::String(name));
+                }
+                category
+            }).collect())
+            .unwrap_or_default(), cont))
     }
 
     /// Creates an iterator to view all categories of the `Page`.
@@ -649,6 +2330,195 @@ impl<'a, A: http::HttpClient> Page<'a, A> {
         Iter::new(&self).await
     }
 
+    /// Like `get_categories`, using `query` to filter the categories.
+    pub async fn get_categories_with(&self, query: iter::CategoryQuery) -> Result<Iter<'a, A, iter::Category>> {
+        Iter::with_query(self, query).await
+    }
+
+    /// Requests a `query` action with `params`, returning the response and
+    /// any `continue` parameters.
+    async fn request_query<'b>(&self, mut params: Vec<(&'b str, &'b str)>, cont: &'b Option<Vec<(String, String)>>) ->
+            Result<(serde_json::Value, Option<Vec<(String, String)>>)> {
+        params.push(("format", "json"));
+        params.push(("action", "query"));
+        self.request_cont(params, cont).await
+    }
+
+    /// Like `request_query`, for the `Page`.
+    async fn request_page<'b>(&self, mut params: Vec<(&'b str, &'b str)>, cont: &'b Option<Vec<(String, String)>>) ->
+            Result<(serde_json::Value, Option<Vec<(String, String)>>)> {
+        let qp = self.identifier.query_param();
+        params.push(("format", "json"));
+        params.push(("action", "query"));
+        params.push((&*qp.0, &*qp.1));
+        self.request_cont(params, cont).await
+    }
+
+    async fn request_cont<'b>(&self, mut params: Vec<(&'b str, &'b str)>, cont: &'b Option<Vec<(String, String)>>) ->
+            Result<(serde_json::Value, Option<Vec<(String, String)>>)> {
+        match *cont {
+            Some(ref v) => {
+                for x in v.iter() { params.push((&*x.0, &*x.1)); }
+            },
+            None => params.push(("continue", "")),
+        }
+        let q = self.wikipedia.query(params.into_iter()).await?;
+        let cont = parse_cont(&q)?;
+        Ok((q, cont))
+    }
+
+    /// Requests the `list` module with `params`, returning its items and
+    /// any `continue` parameters.
+    async fn request_list<'b>(&self, list: &'b str, mut params: Vec<(&'b str, &'b str)>, cont: &'b Option<Vec<(String, String)>>) ->
+            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
+        params.insert(0, ("list", list));
+        let (q, cont) = self.request_query(params, cont).await?;
+
+        let items = q
+            .as_object()
+            .and_then(|x| x.get("query"))
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.get(list))
+            .and_then(|x| x.as_array())
+            .ok_or(Error::JSONPathError)?;
+
+        Ok((items.clone(), cont))
+    }
+
+    async fn request_category_members(&self, query: &iter::CategoryMemberQuery, cont: &Option<Vec<(String, String)>>) ->
+            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
+        let lp = self.identifier.list_param("cm");
+        let types = query.types_param();
+        let namespaces = join_namespaces(&query.namespaces);
+        let mut params = vec![
+            (&*lp.0, &*lp.1),
+            ("cmprop", "ids|title|type|timestamp|sortkeyprefix"),
+            ("cmlimit", &*self.wikipedia.categories_results),
+            ("cmsort", match query.sort {
+                iter::CategoryMemberSort::Sortkey => "sortkey",
+                iter::CategoryMemberSort::Timestamp => "timestamp",
+            }),
+            ("cmdir", if query.descending { "desc" } else { "asc" }),
+        ];
+        if !query.types.is_empty() {
+            params.push(("cmtype", &*types));
+        }
+        if !query.namespaces.is_empty() {
+            params.push(("cmnamespace", &*namespaces));
+        }
+        let (mut members, cont) = self.request_list("categorymembers", params, cont).await?;
+        if query.sort == iter::CategoryMemberSort::Timestamp && !query.types.is_empty() {
+            // cmtype is ignored when sorting by timestamp
+            members.retain(|x| iter::CategoryMember::from_value(x).is_some_and(|x| query.matches(&x)));
+        }
+        Ok((members, cont))
+    }
+
+    /// Creates an iterator to view the members of the `Page`, which must be a
+    /// category.
+    pub async fn get_category_members(&self, query: iter::CategoryMemberQuery) -> Result<Iter<'a, A, iter::CategoryMember>> {
+        Iter::with_query(self, query).await
+    }
+
+    /// Requests the `prop` module with `params` for the `Page`, returning the
+    /// items under `key` and any `continue` parameters.
+    async fn request_prop<'b>(&self, key: &str, params: Vec<(&'b str, &'b str)>, cont: &'b Option<Vec<(String, String)>>) ->
+            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
+        let (q, cont) = self.request_page(params, cont).await?;
+
+        let page = match self.get_first_page(&q) {
+            Some(p) => p,
+            None => return Ok((Vec::new(), None)),
+        };
+        Ok((page
+            .as_object()
+            .and_then(|x| x.get(key))
+            .and_then(|x| x.as_array())
+            .cloned()
+            .unwrap_or_default(), cont))
+    }
+
+    /// Requests one of the modules listing pages that point to the `Page`.
+    async fn request_linking_pages<'b>(&self, prop: &'b str, prefix: &str, query: &iter::LinkingPageQuery, cont: &'b Option<Vec<(String, String)>>) ->
+            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
+        let prop_key = format!("{}prop", prefix);
+        let limit_key = format!("{}limit", prefix);
+        let namespace_key = format!("{}namespace", prefix);
+        let show_key = format!("{}show", prefix);
+        let namespaces = join_namespaces(&query.namespaces);
+        let mut params = vec![
+            ("prop", prop),
+            (&*prop_key, "pageid|title|redirect"),
+            (&*limit_key, &*self.wikipedia.links_results),
+        ];
+        if !query.namespaces.is_empty() {
+            params.push((&*namespace_key, &*namespaces));
+        }
+        match query.redirects {
+            iter::RedirectFilter::All => (),
+            iter::RedirectFilter::Redirects => params.push((&*show_key, "redirect")),
+            iter::RedirectFilter::NonRedirects => params.push((&*show_key, "!redirect")),
+        }
+        self.request_prop(prop, params, cont).await
+    }
+
+    async fn request_backlinks(&self, query: &iter::LinkingPageQuery, cont: &Option<Vec<(String, String)>>) ->
+            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
+        let lp = self.identifier.list_param("bl");
+        let namespaces = join_namespaces(&query.namespaces);
+        let mut params = vec![
+            (&*lp.0, &*lp.1),
+            ("bllimit", &*self.wikipedia.links_results),
+            ("blfilterredir", match query.redirects {
+                iter::RedirectFilter::All => "all",
+                iter::RedirectFilter::Redirects => "redirects",
+                iter::RedirectFilter::NonRedirects => "nonredirects",
+            }),
+        ];
+        if !query.namespaces.is_empty() {
+            params.push(("blnamespace", &*namespaces));
+        }
+        self.request_list("backlinks", params, cont).await
+    }
+
+    /// Creates an iterator to view all pages linking to the `Page`.
+    pub async fn get_backlinks(&self, query: iter::LinkingPageQuery) -> Result<Iter<'a, A, iter::Backlink>> {
+        Iter::with_query(self, query).await
+    }
+
+    async fn request_linkshere(&self, query: &iter::LinkingPageQuery, cont: &Option<Vec<(String, String)>>) ->
+            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
+        self.request_linking_pages("linkshere", "lh", query, cont).await
+    }
+
+    /// Creates an iterator to view all pages linking to the `Page`, including
+    /// redirects unless filtered out by `query`.
+    pub async fn get_linkshere(&self, query: iter::LinkingPageQuery) -> Result<Iter<'a, A, iter::LinkHere>> {
+        Iter::with_query(self, query).await
+    }
+
+    async fn request_transcludedin(&self, query: &iter::LinkingPageQuery, cont: &Option<Vec<(String, String)>>) ->
+            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
+        self.request_linking_pages("transcludedin", "ti", query, cont).await
+    }
+
+    /// Creates an iterator to view all pages transcluding the `Page`, which
+    /// is usually a template.
+    pub async fn get_transcluded_in(&self, query: iter::LinkingPageQuery) -> Result<Iter<'a, A, iter::TranscludedIn>> {
+        Iter::with_query(self, query).await
+    }
+
+    async fn request_fileusage(&self, query: &iter::LinkingPageQuery, cont: &Option<Vec<(String, String)>>) ->
+            Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
+        self.request_linking_pages("fileusage", "fu", query, cont).await
+    }
+
+    /// Creates an iterator to view all pages using the `Page`, which must be
+    /// a file.
+    pub async fn get_file_usage(&self, query: iter::LinkingPageQuery) -> Result<Iter<'a, A, iter::FileUsage>> {
+        Iter::with_query(self, query).await
+    }
+
     async fn request_langlinks(&self, cont: &Option<Vec<(String, String)>>) ->
             Result<(Vec<serde_json::Value>, Option<Vec<(String, String)>>)> {
         let a:Result<(Vec<serde_json::Value>, _)> = cont!(self, cont,
@@ -675,13 +2545,27 @@ impl<'a, A: http::HttpClient> Page<'a, A> {
         Iter::new(&self).await
     }
 
-    /// Returns the latitude and longitude associated to the `Page` if any.
-    #[async_recursion::async_recursion(?Send)]
+    /// Returns the latitude and longitude associated to the `Page` if any,
+    /// preferring the primary coordinates when there are several.
     pub async fn get_coordinates(&self) -> Result<Option<(f64, f64)>> {
+        let coordinates = self.get_all_coordinates().await?;
+        Ok(coordinates
+            .iter()
+            .find(|x| x.primary)
+            .or_else(|| coordinates.first())
+            .map(|x| (x.lat, x.lon)))
+    }
+
+    /// Returns all the coordinates associated to the `Page`, including the
+    /// secondary ones and the ones on other globes.
+    #[async_recursion::async_recursion(?Send)]
+    pub async fn get_all_coordinates(&self) -> Result<Vec<Coordinate>> {
         let qp = self.identifier.query_param();
         let params = vec![
             ("prop", "coordinates"),
             ("colimit", "max"),
+            ("coprop", "type|name|dim|country|region|globe"),
+            ("coprimary", "all"),
             ("redirects", ""),
             ("format", "json"),
             ("action", "query"),
@@ -689,28 +2573,97 @@ impl<'a, A: http::HttpClient> Page<'a, A> {
         ];
         let q = self.wikipedia.query(params.into_iter()).await?;
 
-        match self.redirect(&q) {
-            Some(r) => return Page::from_title(&self.wikipedia, r).get_coordinates().await,
-            None => (),
+        if let Some(r) = self.redirect(&q) {
+            return Page::from_title(self.wikipedia, r).get_all_coordinates().await;
         }
 
-        let coord = match self.get_first_page(&q)
-                .and_then(|x| x.as_object())
-                .and_then(|x| x.get("coordinates"))
-                .and_then(|x| x.as_array())
-                .and_then(|x| x.into_iter().next())
-                .and_then(|x| x.as_object()) {
-            Some(c) => c,
+        Ok(self.get_first_page(&q)
+            .and_then(|x| x.get("coordinates"))
+            .and_then(|x| x.as_array())
+            .map(|x| x.iter().filter_map(Coordinate::from_value).collect())
+            .unwrap_or_default())
+    }
+
+    /// Gets the short description of the article, either the local one or
+    /// the central one from Wikidata. `prefer` is used when both exist.
+    pub async fn get_description(&self, prefer: DescriptionSource) -> Result<Option<Description>> {
+        let qp = self.identifier.query_param();
+        let q = self.wikipedia.query(vec![
+            ("prop", "description"),
+            ("descprefersource", prefer.as_str()),
+            ("redirects", ""),
+            ("format", "json"),
+            ("action", "query"),
+            (&*qp.0, &*qp.1),
+        ].into_iter()).await?;
+
+        let page = match self.get_first_page(&q) {
+            Some(x) => x,
             None => return Ok(None),
         };
-        Ok(Some((
-            coord.get("lat").and_then(|x| x.as_f64()).ok_or(Error::JSONPathError)?,
-            coord.get("lon").and_then(|x| x.as_f64()).ok_or(Error::JSONPathError)?,
-        )))
+        Ok(page.get("description").and_then(|x| x.as_str()).map(|x| Description {
+            text: x.to_owned(),
+            source: match page.get("descriptionsource").and_then(|x| x.as_str()) {
+                Some("central") => DescriptionSource::Central,
+                _ => DescriptionSource::Local,
+            },
+        }))
+    }
+
+    /// Gets the id of the Wikidata item of the article, such as `Q42`.
+    pub async fn get_wikidata_id(&self) -> Result<Option<String>> {
+        let qp = self.identifier.query_param();
+        let q = self.wikipedia.query(vec![
+            ("prop", "pageprops"),
+            ("ppprop", "wikibase_item"),
+            ("redirects", ""),
+            ("format", "json"),
+            ("action", "query"),
+            (&*qp.0, &*qp.1),
+        ].into_iter()).await?;
+
+        Ok(self.get_first_page(&q)
+            .and_then(|x| x.get("pageprops"))
+            .and_then(|x| x.get("wikibase_item"))
+            .and_then(|x| x.as_str())
+            .map(|x| x.to_owned()))
+    }
+
+    /// Gets the representative image of the article as a thumbnail of
+    /// `width` pixels, if it has one.
+    pub async fn get_thumbnail(&self, width: u32) -> Result<Option<PageImage>> {
+        let qp = self.identifier.query_param();
+        let q = self.wikipedia.query(vec![
+            ("prop", "pageimages"),
+            ("piprop", "thumbnail|name"),
+            ("pithumbsize", &*width.to_string()),
+            ("redirects", ""),
+            ("format", "json"),
+            ("action", "query"),
+            (&*qp.0, &*qp.1),
+        ].into_iter()).await?;
+
+        Ok(self.get_first_page(&q).and_then(|x| PageImage::from_page(x, "thumbnail")))
+    }
+
+    /// Gets the representative image of the article in its original size,
+    /// if it has one.
+    pub async fn get_original_image(&self) -> Result<Option<PageImage>> {
+        let qp = self.identifier.query_param();
+        let q = self.wikipedia.query(vec![
+            ("prop", "pageimages"),
+            ("piprop", "original|name"),
+            ("redirects", ""),
+            ("format", "json"),
+            ("action", "query"),
+            (&*qp.0, &*qp.1),
+        ].into_iter()).await?;
+
+        Ok(self.get_first_page(&q).and_then(|x| PageImage::from_page(x, "original")))
     }
 
-    /// Fetches all sections of the article.
-    pub async fn get_sections(&self) -> Result<Vec<String>> {
+    /// Fetches all sections of the article, in order.
+    pub async fn get_sections(&self) -> Result<Vec<Section>> {
         let pageid = self.get_pageid().await?;
         let params = vec![
             ("prop", "sections"),
@@ -727,29 +2680,104 @@ impl<'a, A: http::HttpClient> Page<'a, A> {
             .and_then(|x| x.get("sections"))
             .and_then(|x| x.as_array())
             .ok_or(Error::JSONPathError)?
-            .into_iter()
-            .filter_map(|x| x.as_object()
-                    .and_then(|x| x.get("line"))
-                    .and_then(|x| x.as_str())
-                    .map(|x| x.to_owned())
-                    )
+            .iter()
+            .filter_map(Section::from_value)
             .collect())
     }
 
-    /// Fetches the content of a section.
+    /// Fetches all sections of the article, nested by their level in the
+    /// table of contents.
+    pub async fn get_section_tree(&self) -> Result<Vec<SectionNode>> {
+        Ok(SectionNode::tree(self.get_sections().await?))
+    }
+
+    /// Fetches the plain text content of the first section titled `title`,
+    /// including its subsections.
     pub async fn get_section_content(&self, title: &str) -> Result<Option<String>> {
-        let headr = format!("== {} ==", title);
-        let content = self.get_content().await?;
-        let index = match content.find(&*headr) {
-            Some(i) => headr.len() + i,
-            None => return Ok(None),
-        };
-        let end = match content[index..].find("==") {
-            Some(i) => index + i,
-            None => content.len(),
-        };
-        Ok(Some(content[index..end].to_owned()))
+        let sections = self.get_sections().await?;
+        match sections.iter().position(|x| strip_tags(&x.line) == title) {
+            Some(position) => self.section_content(&sections, position, SectionQuery {
+                include_subsections: true,
+                ..Default::default()
+            }).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetches the content of `section`, as returned by `get_sections`.
+    pub async fn get_section_content_with(&self, section: &Section, query: SectionQuery) -> Result<Option<String>> {
+        let sections = self.get_sections().await?;
+        match sections.iter().position(|x| x == section) {
+            Some(position) => self.section_content(&sections, position, query).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetches the content of the section with the given `index`.
+    pub async fn get_section_by_index(&self, index: &str, query: SectionQuery) -> Result<Option<String>> {
+        let sections = self.get_sections().await?;
+        match sections.iter().position(|x| x.index == index) {
+            Some(position) => self.section_content(&sections, position, query).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetches the content of the section with the given `anchor`, as in
+    /// the `#anchor` part of an url.
+    pub async fn get_section_by_anchor(&self, anchor: &str, query: SectionQuery) -> Result<Option<String>> {
+        let sections = self.get_sections().await?;
+        let anchor = anchor.replace(' ', "_");
+        match sections.iter().position(|x| x.anchor == anchor) {
+            Some(position) => self.section_content(&sections, position, query).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetches the content of `sections[position]`, without its heading.
+    async fn section_content(&self, sections: &[Section], position: usize, query: SectionQuery) -> Result<String> {
+        let section = &sections[position];
+        match query.format {
+            SectionFormat::PlainText => {
+                // The extract has the headings in wikitext style, so look
+                // for the heading with the same title and level, counting
+                // the previous ones to tell duplicate headings apart.
+                let title = strip_tags(&section.line);
+                let occurrence = sections[..position]
+                    .iter()
+                    .filter(|x| x.level == section.level && strip_tags(&x.line) == title)
+                    .count();
+                let content = self.get_content().await?;
+                let headings = headings(&content);
+                let found = headings
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, x)| x.level == section.level && x.title == title)
+                    .nth(occurrence)
+                    .map(|(i, _)| i);
+                match found {
+                    Some(i) => Ok(heading_body(&content, &headings, i, query.include_subsections)),
+                    None => Ok(String::new()),
+                }
+            },
+            SectionFormat::Wikitext => {
+                let wikitext = self.get_section_wikitext(&section.index).await?.content;
+                let headings = headings(&wikitext);
+                if headings.is_empty() {
+                    return Ok(wikitext.trim().to_owned());
+                }
+                Ok(heading_body(&wikitext, &headings, 0, query.include_subsections))
+            },
+            SectionFormat::Html => {
+                let html = self.parse(ParseOptions {
+                    section: Some(section.index.clone()),
+                    disable_edit_section: true,
+                    ..Default::default()
+                }).await?.text.ok_or(Error::JSONPathError)?;
+                Ok(html_section_body(&html, query.include_subsections))
+            },
+        }
     }
+
 }
 
 impl<'a, A: http::HttpClient> PartialEq<Page<'a, A>> for Page<'a, A> {
@@ -781,6 +2809,7 @@ mod test {
         pub user_agent: Option<String>,
         pub arguments: Mutex<Vec<Vec<(String, String)>>>,
         pub response: Mutex<Vec<String>>,
+        pub methods: Mutex<Vec<String>>,
     }
 
     impl Default for MockClient {
@@ -790,6 +2819,7 @@ mod test {
                 user_agent: None,
                 arguments: Mutex::new(Vec::new()),
                 response: Mutex::new(Vec::new()),
+                methods: Mutex::new(Vec::new()),
             }
         }
     }
@@ -801,6 +2831,15 @@ mod test {
 
         async fn get<'a, I>(&self, base_url: &str, args: I) -> Result<String, super::http::Error>
                 where I: Iterator<Item=(&'a str, &'a str)> {
+            self.methods.lock().unwrap().push("GET".to_owned());
+            self.url.lock().unwrap().push(base_url.to_owned());
+            self.arguments.lock().unwrap().push(args.map(|x| (x.0.to_owned(), x.1.to_owned())).collect());
+            Ok(self.response.lock().unwrap().remove(0))
+        }
+
+        async fn post<'a, I>(&self, base_url: &str, args: I) -> Result<String, super::http::Error>
+                where I: Iterator<Item=(&'a str, &'a str)> {
+            self.methods.lock().unwrap().push("POST".to_owned());
             self.url.lock().unwrap().push(base_url.to_owned());
             self.arguments.lock().unwrap().push(args.map(|x| (x.0.to_owned(), x.1.to_owned())).collect());
             Ok(self.response.lock().unwrap().remove(0))
@@ -951,7 +2990,7 @@ mod test {
     #[tokio::test]
     async fn page_html_content() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"revisions\":[{\"*\":\"hello\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"title\":\"Law of triviality\",\"pageid\":4138548,\"text\":{\"*\":\"hello\"}}}".to_owned());
         let page = wikipedia.page_from_pageid("4138548".to_owned());
         let html = page.get_html_content().await.unwrap();
         assert_eq!(
@@ -962,14 +3001,11 @@ mod test {
                 vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
                 vec![vec![
-                    ("prop".to_owned(), "revisions".to_owned()),
-                    ("rvprop".to_owned(), "content".to_owned()),
-                    ("rvlimit".to_owned(), "1".to_owned()),
-                    ("rvparse".to_owned(), "".to_owned()),
+                    ("prop".to_owned(), "text".to_owned()),
                     ("redirects".to_owned(), "".to_owned()),
                     ("format".to_owned(), "json".to_owned()),
-                    ("action".to_owned(), "query".to_owned()),
-                    ("pageids".to_owned(), "4138548".to_owned()),
+                    ("action".to_owned(), "parse".to_owned()),
+                    ("pageid".to_owned(), "4138548".to_owned()),
                     ]]);
     }
 
@@ -1051,11 +3087,13 @@ mod test {
                     url: "http://example.com/image1.jpg".to_owned(),
                     title: "Image 1".to_owned(),
                     description_url: "http://example.com/image1.jpg.html".to_owned(),
+                    ..Default::default()
                 },
                 iter::Image {
                     url: "http://example.com/image2.jpg".to_owned(),
                     title: "Image 2".to_owned(),
                     description_url: "http://example.com/image2.jpg.html".to_owned(),
+                    ..Default::default()
                 }
                 ]);
         assert_eq!(*wikipedia.client.url.lock().unwrap(),
@@ -1105,6 +3143,8 @@ mod test {
                 vec![vec![
                     ("prop".to_owned(), "coordinates".to_owned()),
                     ("colimit".to_owned(), "max".to_owned()),
+                    ("coprop".to_owned(), "type|name|dim|country|region|globe".to_owned()),
+                    ("coprimary".to_owned(), "all".to_owned()),
                     ("redirects".to_owned(), "".to_owned()),
                     ("format".to_owned(), "json".to_owned()),
                     ("action".to_owned(), "query".to_owned()),
@@ -1124,6 +3164,8 @@ mod test {
                 vec![vec![
                     ("prop".to_owned(), "coordinates".to_owned()),
                     ("colimit".to_owned(), "max".to_owned()),
+                    ("coprop".to_owned(), "type|name|dim|country|region|globe".to_owned()),
+                    ("coprimary".to_owned(), "all".to_owned()),
                     ("redirects".to_owned(), "".to_owned()),
                     ("format".to_owned(), "json".to_owned()),
                     ("action".to_owned(), "query".to_owned()),
@@ -1134,17 +3176,31 @@ mod test {
     #[tokio::test]
     async fn get_references() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"*\": \"//example.com/reference1.html\"}]}}}}".to_owned());
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"*\": \"//example.com/reference2.html\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"*\": \"https://Example.com:8080/reference1.html\"}, {\"*\": \"//example.com/reference2.html\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"*\": \"mailto:someone@example.org\"}, {\"*\": \"ftp://user@files.example.net/file\"}]}}}}".to_owned());
         let page = wikipedia.page_from_title("World".to_owned());
         assert_eq!(
                 page.get_references().await.unwrap().collect_vec::<Vec<_>>().await,
                 vec![
                 iter::Reference {
-                    url: "http://example.com/reference1.html".to_owned(),
+                    url: "https://Example.com:8080/reference1.html".to_owned(),
+                    protocol: "https".to_owned(),
+                    domain: "example.com".to_owned(),
+                },
+                iter::Reference {
+                    url: "https://example.com/reference2.html".to_owned(),
+                    protocol: "https".to_owned(),
+                    domain: "example.com".to_owned(),
+                },
+                iter::Reference {
+                    url: "mailto:someone@example.org".to_owned(),
+                    protocol: "mailto".to_owned(),
+                    domain: "example.org".to_owned(),
                 },
                 iter::Reference {
-                    url: "http://example.com/reference2.html".to_owned(),
+                    url: "ftp://user@files.example.net/file".to_owned(),
+                    protocol: "ftp".to_owned(),
+                    domain: "files.example.net".to_owned(),
                 }
                 ]);
         assert_eq!(*wikipedia.client.url.lock().unwrap(),
@@ -1156,6 +3212,7 @@ mod test {
                 vec![vec![
                     ("prop".to_owned(), "extlinks".to_owned()),
                     ("ellimit".to_owned(), "max".to_owned()),
+                    ("elexpandurl".to_owned(), "".to_owned()),
                     ("format".to_owned(), "json".to_owned()),
                     ("action".to_owned(), "query".to_owned()),
                     ("titles".to_owned(), "World".to_owned()),
@@ -1164,6 +3221,7 @@ mod test {
                 vec![
                     ("prop".to_owned(), "extlinks".to_owned()),
                     ("ellimit".to_owned(), "max".to_owned()),
+                    ("elexpandurl".to_owned(), "".to_owned()),
                     ("format".to_owned(), "json".to_owned()),
                     ("action".to_owned(), "query".to_owned()),
                     ("titles".to_owned(), "World".to_owned()),
@@ -1175,17 +3233,26 @@ mod test {
     #[tokio::test]
     async fn get_links() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"links\":[{\"title\": \"Hello\"}]}}}}".to_owned());
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"links\":[{\"title\": \"World\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"-1\":{\"ns\":0,\"title\":\"Hello\",\"missing\":\"\"},\"12\":{\"pageid\":12,\"ns\":0,\"title\":\"Goodbye\"}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"pageid\":13,\"ns\":0,\"title\":\"World\"}}}}".to_owned());
         let page = wikipedia.page_from_title("World".to_owned());
         assert_eq!(
                 page.get_links().await.unwrap().collect_vec::<Vec<_>>().await,
                 vec![
+                iter::Link {
+                    title: "Goodbye".to_owned(),
+                    ns: 0,
+                    exists: true,
+                },
                 iter::Link {
                     title: "Hello".to_owned(),
+                    ns: 0,
+                    exists: false,
                 },
                 iter::Link {
                     title: "World".to_owned(),
+                    ns: 0,
+                    exists: true,
                 }
                 ]);
         assert_eq!(*wikipedia.client.url.lock().unwrap(),
@@ -1196,18 +3263,20 @@ mod test {
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
                 vec![
                 vec![
-                    ("prop".to_owned(), "links".to_owned()),
-                    ("plnamespace".to_owned(), "0".to_owned()),
-                    ("ellimit".to_owned(), "max".to_owned()),
+                    ("generator".to_owned(), "links".to_owned()),
+                    ("gpllimit".to_owned(), "max".to_owned()),
+                    ("prop".to_owned(), "info".to_owned()),
+                    ("gplnamespace".to_owned(), "0".to_owned()),
                     ("format".to_owned(), "json".to_owned()),
                     ("action".to_owned(), "query".to_owned()),
                     ("titles".to_owned(), "World".to_owned()),
                     ("continue".to_owned(), "".to_owned()),
                 ],
                 vec![
-                    ("prop".to_owned(), "links".to_owned()),
-                    ("plnamespace".to_owned(), "0".to_owned()),
-                    ("ellimit".to_owned(), "max".to_owned()),
+                    ("generator".to_owned(), "links".to_owned()),
+                    ("gpllimit".to_owned(), "max".to_owned()),
+                    ("prop".to_owned(), "info".to_owned()),
+                    ("gplnamespace".to_owned(), "0".to_owned()),
                     ("format".to_owned(), "json".to_owned()),
                     ("action".to_owned(), "query".to_owned()),
                     ("titles".to_owned(), "World".to_owned()),
@@ -1219,17 +3288,25 @@ mod test {
     #[tokio::test]
     async fn get_categories() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"categories\":[{\"title\": \"Hello\"}]}}}}".to_owned());
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"categories\":[{\"title\": \"Category: World\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"namespaces\":{\"14\":{\"id\":14,\"*\":\"Kategorie\",\"canonical\":\"Category\"}},\"pages\":{\"a\":{\"categories\":[{\"ns\":14,\"title\":\"Kategorie:Hello\",\"sortkey\":\"48454c4c4f\",\"sortkeyprefix\":\"\",\"timestamp\":\"2020-01-01T00:00:00Z\",\"hidden\":\"\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"categories\":[{\"ns\":14,\"title\":\"Category:World\",\"sortkey\":\"574f524c44\",\"sortkeyprefix\":\"W\"}]}}}}".to_owned());
         let page = wikipedia.page_from_title("World".to_owned());
         assert_eq!(
                 page.get_categories().await.unwrap().collect_vec::<Vec<_>>().await,
                 vec![
                 iter::Category {
                     title: "Hello".to_owned(),
+                    sortkey: "48454c4c4f".to_owned(),
+                    sortkey_prefix: "".to_owned(),
+                    timestamp: Some("2020-01-01T00:00:00Z".to_owned()),
+                    hidden: true,
                 },
                 iter::Category {
                     title: "World".to_owned(),
+                    sortkey: "574f524c44".to_owned(),
+                    sortkey_prefix: "W".to_owned(),
+                    timestamp: None,
+                    hidden: false,
                 }
                 ]);
         assert_eq!(*wikipedia.client.url.lock().unwrap(),
@@ -1241,7 +3318,10 @@ mod test {
                 vec![
                 vec![
                     ("prop".to_owned(), "categories".to_owned()),
+                    ("clprop".to_owned(), "sortkey|timestamp|hidden".to_owned()),
                     ("cllimit".to_owned(), "max".to_owned()),
+                    ("meta".to_owned(), "siteinfo".to_owned()),
+                    ("siprop".to_owned(), "namespaces".to_owned()),
                     ("format".to_owned(), "json".to_owned()),
                     ("action".to_owned(), "query".to_owned()),
                     ("titles".to_owned(), "World".to_owned()),
@@ -1249,6 +3329,7 @@ mod test {
                 ],
                 vec![
                     ("prop".to_owned(), "categories".to_owned()),
+                    ("clprop".to_owned(), "sortkey|timestamp|hidden".to_owned()),
                     ("cllimit".to_owned(), "max".to_owned()),
                     ("format".to_owned(), "json".to_owned()),
                     ("action".to_owned(), "query".to_owned()),
@@ -1261,11 +3342,30 @@ mod test {
     #[tokio::test]
     async fn sections() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"sections\":[{\"line\":\"hello\"}, {\"line\":\"world\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"sections\":[{\"toclevel\":1,\"level\":\"2\",\"line\":\"hello\",\"number\":\"1\",\"index\":\"1\",\"fromtitle\":\"World\",\"byteoffset\":120,\"anchor\":\"hello\"}, {\"toclevel\":2,\"level\":\"3\",\"line\":\"world\",\"number\":\"1.1\",\"index\":\"T-1\",\"fromtitle\":\"Template:World\",\"byteoffset\":null,\"anchor\":\"world\"}]}}".to_owned());
         let page = wikipedia.page_from_pageid("123".to_owned());
         assert_eq!(
                 page.get_sections().await.unwrap(),
-                vec!["hello".to_owned(), "world".to_owned()]
+                vec![
+                    super::Section {
+                        line: "hello".to_owned(),
+                        level: 2,
+                        toclevel: 1,
+                        number: "1".to_owned(),
+                        anchor: "hello".to_owned(),
+                        index: "1".to_owned(),
+                        byteoffset: Some(120),
+                    },
+                    super::Section {
+                        line: "world".to_owned(),
+                        level: 3,
+                        toclevel: 2,
+                        number: "1.1".to_owned(),
+                        anchor: "world".to_owned(),
+                        index: "T-1".to_owned(),
+                        byteoffset: None,
+                    },
+                ]
                 );
         assert_eq!(*wikipedia.client.url.lock().unwrap(),
                 vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
@@ -1299,44 +3399,1264 @@ mod test {
                        ("action".to_owned(), "query".to_owned())
                    ]]);
     }
-}
-
-#[cfg(test)]
-mod wasm_tests {
-    use super::Wikipedia;
-    use super::http::HttpClient;
-    use super::iter;
-    use std::sync::Mutex;
-
-    use crate::iter::AsyncIterator;
-
-    use wasm_bindgen_test::wasm_bindgen_test;
+    #[tokio::test]
+    async fn autocomplete() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"2\":{\"pageid\":2,\"ns\":0,\"title\":\"world\",\"index\":2,\"fullurl\":\"https://en.wikipedia.org/wiki/World\"},\"1\":{\"pageid\":1,\"ns\":0,\"title\":\"hello\",\"index\":1,\"description\":\"greeting\",\"fullurl\":\"https://en.wikipedia.org/wiki/Hello\"}}}}".to_owned());
+        assert_eq!(
+                wikipedia.autocomplete_in("hel", 5, &[0, 14]).await.unwrap(),
+                vec![
+                super::Suggestion {
+                    title: "hello".to_owned(),
+                    ns: 0,
+                    description: Some("greeting".to_owned()),
+                    url: "https://en.wikipedia.org/wiki/Hello".to_owned(),
+                },
+                super::Suggestion {
+                    title: "world".to_owned(),
+                    ns: 0,
+                    description: None,
+                    url: "https://en.wikipedia.org/wiki/World".to_owned(),
+                },
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("generator".to_owned(), "prefixsearch".to_owned()),
+                    ("gpssearch".to_owned(), "hel".to_owned()),
+                    ("gpslimit".to_owned(), "5".to_owned()),
+                    ("gpsnamespace".to_owned(), "0|14".to_owned()),
+                    ("prop".to_owned(), "description|info".to_owned()),
+                    ("inprop".to_owned(), "url".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned())
+                    ]]);
+    }
 
-    struct MockClient {
-        pub url: Mutex<Vec<String>>,
-        pub user_agent: Option<String>,
-        pub arguments: Mutex<Vec<Vec<(String, String)>>>,
-        pub response: Mutex<Vec<String>>,
+    #[tokio::test]
+    async fn autocomplete_no_results() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"batchcomplete\":\"\"}".to_owned());
+        assert!(wikipedia.autocomplete("zzzz", 5).await.unwrap().is_empty());
+        assert!(wikipedia.autocomplete("zzzz", 0).await.is_err());
     }
 
-    impl Default for crate::wasm_tests::MockClient {
-        fn default() -> Self {
-            crate::wasm_tests::MockClient {
-                url: Mutex::new(Vec::new()),
-                user_agent: None,
-                arguments: Mutex::new(Vec::new()),
-                response: Mutex::new(Vec::new()),
-            }
-        }
+    #[tokio::test]
+    async fn autocomplete_latest() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let session = super::AutocompleteSession::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"title\":\"hello\",\"index\":1}}}}".to_owned());
+        assert_eq!(wikipedia.autocomplete_latest(&session, "hel", 5).await.unwrap().unwrap().len(), 1);
+
+        let stale = session.begin();
+        let latest = session.begin();
+        assert!(!session.is_current(stale));
+        assert!(session.is_current(latest));
     }
 
-    impl super::http::HttpClient for crate::wasm_tests::MockClient {
-        fn user_agent(&mut self, user_agent: String) {
-            self.user_agent = Some(user_agent)
-        }
+
+    #[tokio::test]
+    async fn category_members() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"cmcontinue\":\"page|1\"},\"query\":{\"categorymembers\":[{\"pageid\":1,\"ns\":0,\"title\":\"Hello\",\"type\":\"page\",\"sortkeyprefix\":\"\",\"timestamp\":\"2020-01-01T00:00:00Z\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"categorymembers\":[{\"pageid\":2,\"ns\":14,\"title\":\"Category:World\",\"type\":\"subcat\",\"sortkeyprefix\":\"W\"}]}}".to_owned());
+        let query = iter::CategoryMemberQuery {
+            types: vec![iter::CategoryMemberType::Page, iter::CategoryMemberType::Subcat],
+            sort: iter::CategoryMemberSort::Timestamp,
+            descending: true,
+            ..Default::default()
+        };
+        assert_eq!(
+                wikipedia.category_members("Category:Greetings".to_owned(), query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![
+                iter::CategoryMember {
+                    pageid: 1,
+                    ns: 0,
+                    title: "Hello".to_owned(),
+                    member_type: iter::CategoryMemberType::Page,
+                    sortkey_prefix: "".to_owned(),
+                    timestamp: Some("2020-01-01T00:00:00Z".to_owned()),
+                },
+                iter::CategoryMember {
+                    pageid: 2,
+                    ns: 14,
+                    title: "Category:World".to_owned(),
+                    member_type: iter::CategoryMemberType::Subcat,
+                    sortkey_prefix: "W".to_owned(),
+                    timestamp: None,
+                }
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![
+                vec![
+                    ("list".to_owned(), "categorymembers".to_owned()),
+                    ("cmtitle".to_owned(), "Category:Greetings".to_owned()),
+                    ("cmprop".to_owned(), "ids|title|type|timestamp|sortkeyprefix".to_owned()),
+                    ("cmlimit".to_owned(), "max".to_owned()),
+                    ("cmsort".to_owned(), "timestamp".to_owned()),
+                    ("cmdir".to_owned(), "desc".to_owned()),
+                    ("cmtype".to_owned(), "page|subcat".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                ],
+                vec![
+                    ("list".to_owned(), "categorymembers".to_owned()),
+                    ("cmtitle".to_owned(), "Category:Greetings".to_owned()),
+                    ("cmprop".to_owned(), "ids|title|type|timestamp|sortkeyprefix".to_owned()),
+                    ("cmlimit".to_owned(), "max".to_owned()),
+                    ("cmsort".to_owned(), "timestamp".to_owned()),
+                    ("cmdir".to_owned(), "desc".to_owned()),
+                    ("cmtype".to_owned(), "page|subcat".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("cmcontinue".to_owned(), "page|1".to_owned()),
+                ]
+                ]);
+    }
+
+    #[tokio::test]
+    async fn category_members_recursive() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"categorymembers\":[{\"ns\":0,\"title\":\"Hello\",\"type\":\"page\"},{\"ns\":14,\"title\":\"Category:B\",\"type\":\"subcat\"}]}}".to_owned());
+        // Category:B links back to Category:A, which must not be visited again
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"categorymembers\":[{\"ns\":0,\"title\":\"Hello\",\"type\":\"page\"},{\"ns\":0,\"title\":\"World\",\"type\":\"page\"},{\"ns\":14,\"title\":\"Category:A\",\"type\":\"subcat\"}]}}".to_owned());
+        let query = iter::CategoryMemberQuery {
+            types: vec![iter::CategoryMemberType::Page],
+            ..Default::default()
+        };
+        let members = wikipedia.category_members_recursive("Category:A".to_owned(), query, 5).await.unwrap();
+        assert_eq!(
+                members.into_iter().map(|x| x.title).collect::<Vec<_>>(),
+                vec!["Hello".to_owned(), "World".to_owned()]);
+        let arguments = wikipedia.client.arguments.lock().unwrap();
+        assert_eq!(arguments.len(), 2);
+        assert!(arguments[0].contains(&("cmtype".to_owned(), "page|subcat".to_owned())));
+        assert!(arguments[1].contains(&("cmtitle".to_owned(), "Category:B".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn get_categories_exclude_hidden() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"categories\":[{\"ns\":14,\"title\":\"Category:World\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::CategoryQuery { exclude_hidden: true };
+        let categories = page.get_categories_with(query).await.unwrap().collect_vec::<Vec<_>>().await;
+        assert_eq!(categories.len(), 1);
+        assert_eq!(categories[0].title, "World".to_owned());
+        assert!(wikipedia.client.arguments.lock().unwrap()[0].contains(&("clshow".to_owned(), "!hidden".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn get_backlinks() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"blcontinue\":\"0|2\"},\"query\":{\"backlinks\":[{\"pageid\":1,\"ns\":0,\"title\":\"Hello\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"backlinks\":[{\"pageid\":2,\"ns\":0,\"title\":\"Hi\",\"redirect\":\"\"}]}}".to_owned());
+        let page = wikipedia.page_from_pageid("4138548".to_owned());
+        let query = iter::LinkingPageQuery { namespaces: vec![0], ..Default::default() };
+        assert_eq!(
+                page.get_backlinks(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![
+                iter::Backlink { pageid: 1, ns: 0, title: "Hello".to_owned(), redirect: false },
+                iter::Backlink { pageid: 2, ns: 0, title: "Hi".to_owned(), redirect: true },
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![
+                vec![
+                    ("list".to_owned(), "backlinks".to_owned()),
+                    ("blpageid".to_owned(), "4138548".to_owned()),
+                    ("bllimit".to_owned(), "max".to_owned()),
+                    ("blfilterredir".to_owned(), "all".to_owned()),
+                    ("blnamespace".to_owned(), "0".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                ],
+                vec![
+                    ("list".to_owned(), "backlinks".to_owned()),
+                    ("blpageid".to_owned(), "4138548".to_owned()),
+                    ("bllimit".to_owned(), "max".to_owned()),
+                    ("blfilterredir".to_owned(), "all".to_owned()),
+                    ("blnamespace".to_owned(), "0".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("blcontinue".to_owned(), "0|2".to_owned()),
+                ]
+                ]);
+    }
+
+    #[tokio::test]
+    async fn get_linkshere() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"linkshere\":[{\"pageid\":2,\"ns\":0,\"title\":\"Hi\",\"redirect\":\"\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Hello".to_owned());
+        let query = iter::LinkingPageQuery { namespaces: vec![0, 4], redirects: iter::RedirectFilter::Redirects };
+        assert_eq!(
+                page.get_linkshere(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::LinkHere { pageid: 2, ns: 0, title: "Hi".to_owned(), redirect: true }]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "linkshere".to_owned()),
+                    ("lhprop".to_owned(), "pageid|title|redirect".to_owned()),
+                    ("lhlimit".to_owned(), "max".to_owned()),
+                    ("lhnamespace".to_owned(), "0|4".to_owned()),
+                    ("lhshow".to_owned(), "redirect".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "Hello".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                    ]]);
+    }
+
+    #[tokio::test]
+    async fn get_transcluded_in_and_file_usage() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"transcludedin\":[{\"pageid\":1,\"ns\":0,\"title\":\"Hello\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"fileusage\":[{\"pageid\":3,\"ns\":0,\"title\":\"World\"}]}}}}".to_owned());
+        let template = wikipedia.page_from_title("Template:Hello".to_owned());
+        let query = iter::LinkingPageQuery { redirects: iter::RedirectFilter::NonRedirects, ..Default::default() };
+        assert_eq!(
+                template.get_transcluded_in(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::TranscludedIn { pageid: 1, ns: 0, title: "Hello".to_owned(), redirect: false }]);
+        let file = wikipedia.page_from_title("File:World.png".to_owned());
+        assert_eq!(
+                file.get_file_usage(Default::default()).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::FileUsage { pageid: 3, ns: 0, title: "World".to_owned(), redirect: false }]);
+        let arguments = wikipedia.client.arguments.lock().unwrap();
+        assert!(arguments[0].contains(&("tishow".to_owned(), "!redirect".to_owned())));
+        assert!(arguments[1].contains(&("prop".to_owned(), "fileusage".to_owned())));
+        assert!(arguments[1].contains(&("fulimit".to_owned(), "max".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn get_templates() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"templates\":[{\"ns\":10,\"title\":\"Template:Infobox country\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"templates\":[{\"ns\":828,\"title\":\"Module:Infobox\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::TemplateQuery { namespaces: vec![10, 828] };
+        assert_eq!(
+                page.get_templates_with(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![
+                iter::Template {
+                    title: "Template:Infobox country".to_owned(),
+                    ns: 10,
+                },
+                iter::Template {
+                    title: "Module:Infobox".to_owned(),
+                    ns: 828,
+                }
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![
+                vec![
+                    ("prop".to_owned(), "templates".to_owned()),
+                    ("tllimit".to_owned(), "max".to_owned()),
+                    ("tlnamespace".to_owned(), "10|828".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                ],
+                vec![
+                    ("prop".to_owned(), "templates".to_owned()),
+                    ("tllimit".to_owned(), "max".to_owned()),
+                    ("tlnamespace".to_owned(), "10|828".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("lol".to_owned(), "1".to_owned()),
+                ]
+                ]);
+    }
+
+    #[tokio::test]
+    async fn get_links_with() {
+        let wikipedia = Wikipedia {
+            links_results: "20".to_owned(),
+            ..Wikipedia::<MockClient>::default()
+        };
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"ns\":4,\"title\":\"Wikipedia:Hello\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::LinkQuery {
+            namespaces: Vec::new(),
+            titles: vec!["Wikipedia:Hello".to_owned(), "Hello".to_owned()],
+        };
+        assert_eq!(
+                page.get_links_with(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::Link { title: "Wikipedia:Hello".to_owned(), ns: 4, exists: true }]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("generator".to_owned(), "links".to_owned()),
+                    ("gpllimit".to_owned(), "20".to_owned()),
+                    ("prop".to_owned(), "info".to_owned()),
+                    ("gpltitles".to_owned(), "Wikipedia:Hello|Hello".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                    ]]);
+    }
+
+    #[tokio::test]
+    async fn get_references_with() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"url\": \"https://www.example.com/\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::ReferenceQuery {
+            protocol: Some("https".to_owned()),
+            domain: Some("*.example.com".to_owned()),
+        };
+        assert_eq!(
+                page.get_references_with(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::Reference {
+                    url: "https://www.example.com/".to_owned(),
+                    protocol: "https".to_owned(),
+                    domain: "www.example.com".to_owned(),
+                }]);
+        let arguments = wikipedia.client.arguments.lock().unwrap();
+        assert!(arguments[0].contains(&("elprotocol".to_owned(), "https".to_owned())));
+        assert!(arguments[0].contains(&("elquery".to_owned(), "*.example.com".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn get_references_protocol() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"url\": \"https://www.example.com/\"},{\"url\": \"mailto:someone@example.com\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::ReferenceQuery {
+            protocol: Some("mailto".to_owned()),
+            domain: None,
+        };
+        assert_eq!(
+                page.get_references_with(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::Reference {
+                    url: "mailto:someone@example.com".to_owned(),
+                    protocol: "mailto".to_owned(),
+                    domain: "example.com".to_owned(),
+                }]);
+        let arguments = wikipedia.client.arguments.lock().unwrap();
+        assert!(!arguments[0].iter().any(|x| x.0 == "elprotocol"));
+    }
+
+    #[tokio::test]
+    async fn external_url_usage() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"euoffset\":1,\"continue\":\"-||\"},\"query\":{\"exturlusage\":[{\"pageid\":1,\"ns\":0,\"title\":\"Hello\",\"url\":\"https://example.com/a\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"exturlusage\":[{\"pageid\":2,\"ns\":0,\"title\":\"World\",\"url\":\"http://example.com/b\"}]}}".to_owned());
+        let query = iter::ReferenceQuery {
+            domain: Some("example.com".to_owned()),
+            ..Default::default()
+        };
+        let usages = wikipedia.external_url_usage(query, 10).await.unwrap();
+        assert_eq!(
+                usages.iter().map(|x| (&*x.title, &*x.reference.url)).collect::<Vec<_>>(),
+                vec![("Hello", "https://example.com/a"), ("World", "http://example.com/b")]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("list".to_owned(), "exturlusage".to_owned()),
+                    ("euprop".to_owned(), "ids|title|url".to_owned()),
+                    ("eulimit".to_owned(), "10".to_owned()),
+                    ("euexpandurl".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("euquery".to_owned(), "example.com".to_owned()),
+                    ("continue".to_owned(), "".to_owned())
+                ],
+                vec![
+                    ("list".to_owned(), "exturlusage".to_owned()),
+                    ("euprop".to_owned(), "ids|title|url".to_owned()),
+                    ("eulimit".to_owned(), "9".to_owned()),
+                    ("euexpandurl".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("euquery".to_owned(), "example.com".to_owned()),
+                    ("continue".to_owned(), "-||".to_owned()),
+                    ("euoffset".to_owned(), "1".to_owned())
+                ]
+                ]);
+    }
+
+    #[tokio::test]
+    async fn get_citations() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"revisions\":[{\"revid\":1,\"parentid\":0,\"slots\":{\"main\":{\"contentmodel\":\"wikitext\",\"*\":\"Hello.<ref>{{cite web|url=https://example.com|title=World}}</ref>\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let citations = page.get_citations().await.unwrap();
+        assert_eq!(citations.len(), 1);
+        assert_eq!(citations[0].title, Some("World".to_owned()));
+        assert_eq!(citations[0].url, Some("https://example.com".to_owned()));
+        assert_eq!(citations[0].anchor, Some("cite_note-1".to_owned()));
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "revisions".to_owned()),
+                    ("rvprop".to_owned(), "ids|content".to_owned()),
+                    ("rvslots".to_owned(), "main".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned())
+                    ]]);
+    }
+
+    #[tokio::test]
+    async fn page_images_with() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"title\":\"File:Hello.jpg\", \"imageinfo\":[{\"url\": \"http://example.com/hello.jpg\", \"descriptionurl\": \"http://example.com/hello.jpg.html\", \"width\": 800, \"height\": 600, \"size\": 12345, \"mime\": \"image/jpeg\", \"sha1\": \"abc\", \"timestamp\": \"2020-01-01T00:00:00Z\", \"user\": \"Someone\", \"thumburl\": \"http://example.com/200px-hello.jpg\", \"thumbwidth\": 200, \"thumbheight\": 150, \"extmetadata\": {\"LicenseShortName\": {\"value\": \"CC BY-SA 4.0\", \"source\": \"commons-desc-page\"}, \"Artist\": {\"value\": \"<a href=\\\"//example.com\\\">Someone</a>\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::ImageQuery {
+            details: true,
+            license: true,
+            thumb_width: Some(200),
+            thumb_height: None,
+        };
+        assert_eq!(
+                page.get_images_with(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::Image {
+                    url: "http://example.com/hello.jpg".to_owned(),
+                    title: "File:Hello.jpg".to_owned(),
+                    description_url: "http://example.com/hello.jpg.html".to_owned(),
+                    width: Some(800),
+                    height: Some(600),
+                    size: Some(12345),
+                    mime: Some("image/jpeg".to_owned()),
+                    sha1: Some("abc".to_owned()),
+                    timestamp: Some("2020-01-01T00:00:00Z".to_owned()),
+                    uploader: Some("Someone".to_owned()),
+                    license: Some(iter::ImageLicense {
+                        short_name: Some("CC BY-SA 4.0".to_owned()),
+                        artist: Some("<a href=\"//example.com\">Someone</a>".to_owned()),
+                        attribution: None,
+                        credit: None,
+                    }),
+                    thumbnail: Some(iter::Thumbnail {
+                        url: "http://example.com/200px-hello.jpg".to_owned(),
+                        width: 200,
+                        height: 150,
+                    }),
+                }]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("generator".to_owned(), "images".to_owned()),
+                    ("gimlimit".to_owned(), "max".to_owned()),
+                    ("prop".to_owned(), "imageinfo".to_owned()),
+                    ("iiprop".to_owned(), "url|size|mime|sha1|timestamp|user|extmetadata".to_owned()),
+                    ("iiextmetadatafilter".to_owned(), "LicenseShortName|Artist|Attribution|Credit".to_owned()),
+                    ("iiurlwidth".to_owned(), "200".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("continue".to_owned(), "".to_owned())
+                    ]]);
+    }
+
+
+    #[tokio::test]
+    async fn thumbnail() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\",\"thumbnail\":{\"source\":\"http://example.com/320px-Earth.jpg\",\"width\":320,\"height\":320},\"pageimage\":\"Earth.jpg\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(
+                page.get_thumbnail(320).await.unwrap(),
+                Some(super::PageImage {
+                    file: "Earth.jpg".to_owned(),
+                    url: "http://example.com/320px-Earth.jpg".to_owned(),
+                    width: 320,
+                    height: 320,
+                }));
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "pageimages".to_owned()),
+                    ("piprop".to_owned(), "thumbnail|name".to_owned()),
+                    ("pithumbsize".to_owned(), "320".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned())
+                    ]]);
+    }
+
+    #[tokio::test]
+    async fn original_image_missing() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(page.get_original_image().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn page_images_batch() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"normalized\":[{\"from\":\"earth\",\"to\":\"Earth\"}],\"redirects\":[{\"from\":\"Earth\",\"to\":\"Planet Earth\"}],\"pages\":{\"1\":{\"pageid\":1,\"title\":\"Planet Earth\",\"original\":{\"source\":\"http://example.com/Earth.jpg\",\"width\":1000,\"height\":800},\"pageimage\":\"Earth.jpg\"},\"2\":{\"pageid\":2,\"title\":\"Moon\"}}}}".to_owned());
+        let titles = vec!["earth".to_owned(), "Moon".to_owned()];
+        assert_eq!(
+                wikipedia.get_page_images(&titles, None).await.unwrap(),
+                vec![
+                    ("earth".to_owned(), Some(super::PageImage {
+                        file: "Earth.jpg".to_owned(),
+                        url: "http://example.com/Earth.jpg".to_owned(),
+                        width: 1000,
+                        height: 800,
+                    })),
+                    ("Moon".to_owned(), None),
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "pageimages".to_owned()),
+                    ("piprop".to_owned(), "original|name".to_owned()),
+                    ("pilimit".to_owned(), "max".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "earth|Moon".to_owned())
+                    ]]);
+    }
+
+
+    #[tokio::test]
+    async fn description() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\",\"description\":\"Everything that exists\",\"descriptionsource\":\"local\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(page.get_description(super::DescriptionSource::Local).await.unwrap(), Some(super::Description {
+            text: "Everything that exists".to_owned(),
+            source: super::DescriptionSource::Local,
+        }));
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "description".to_owned()),
+                    ("descprefersource".to_owned(), "local".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned())
+                    ]]);
+    }
+
+    #[tokio::test]
+    async fn description_central() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\",\"description\":\"all of the Earth\",\"descriptionsource\":\"central\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(page.get_description(super::DescriptionSource::Central).await.unwrap(), Some(super::Description {
+            text: "all of the Earth".to_owned(),
+            source: super::DescriptionSource::Central,
+        }));
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[0][1],
+                ("descprefersource".to_owned(), "central".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn wikidata_id() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\",\"pageprops\":{\"wikibase_item\":\"Q16502\"}}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(page.get_wikidata_id().await.unwrap(), Some("Q16502".to_owned()));
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "pageprops".to_owned()),
+                    ("ppprop".to_owned(), "wikibase_item".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned())
+                    ]]);
+    }
+
+
+    #[tokio::test]
+    async fn wikidata_entity() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let wikidata = wikipedia.wikidata();
+        wikipedia.client.response.lock().unwrap().push("{\"entities\":{\"Q42\":{\"id\":\"Q42\",\"labels\":{\"en\":{\"language\":\"en\",\"value\":\"Douglas Adams\"}}}}}".to_owned());
+        let entity = wikidata.get_entity("Q42").await.unwrap().unwrap();
+        assert_eq!(entity.id, "Q42");
+        assert_eq!(entity.label("en"), Some("Douglas Adams"));
+        assert_eq!(*wikipedia.client.url.lock().unwrap(), vec!["https://www.wikidata.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("action".to_owned(), "wbgetentities".to_owned()),
+                    ("ids".to_owned(), "Q42".to_owned()),
+                    ("props".to_owned(), "info|labels|descriptions|aliases|claims|datatype|sitelinks/urls".to_owned()),
+                    ("format".to_owned(), "json".to_owned())
+                    ]]);
+
+        wikipedia.client.response.lock().unwrap().push("{\"entities\":{\"Q0\":{\"id\":\"Q0\",\"missing\":\"\"}}}".to_owned());
+        assert_eq!(wikidata.get_entity("Q0").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn wikidata_search_entities() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let wikidata = wikipedia.wikidata();
+        wikipedia.client.response.lock().unwrap().push("{\"search\":[{\"id\":\"Q42\",\"label\":\"Douglas Adams\",\"description\":\"English writer\",\"concepturi\":\"http://www.wikidata.org/entity/Q42\"}]}".to_owned());
+        assert_eq!(
+                wikidata.search_entities("douglas adams").await.unwrap(),
+                vec![super::wikidata::EntitySearchResult {
+                    id: "Q42".to_owned(),
+                    label: Some("Douglas Adams".to_owned()),
+                    description: Some("English writer".to_owned()),
+                    url: Some("http://www.wikidata.org/entity/Q42".to_owned()),
+                }]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("action".to_owned(), "wbsearchentities".to_owned()),
+                    ("search".to_owned(), "douglas adams".to_owned()),
+                    ("language".to_owned(), "en".to_owned()),
+                    ("type".to_owned(), "item".to_owned()),
+                    ("limit".to_owned(), "10".to_owned()),
+                    ("format".to_owned(), "json".to_owned())
+                    ]]);
+    }
+
+    #[tokio::test]
+    async fn wikidata_page_entity() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"Douglas Adams\",\"pageprops\":{\"wikibase_item\":\"Q42\"}}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"entities\":{\"Q42\":{\"id\":\"Q42\"}}}".to_owned());
+        let wikidata = wikipedia.wikidata();
+        let page = wikipedia.page_from_title("Douglas Adams".to_owned());
+        assert_eq!(wikidata.get_page_entity(&page).await.unwrap().unwrap().id, "Q42");
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[1][1], ("ids".to_owned(), "Q42".to_owned()));
+    }
+
+
+    #[tokio::test]
+    async fn sparql_query() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let sparql = super::sparql::Sparql::<MockClient>::default();
+        sparql.client.response.lock().unwrap().push("{\"head\":{\"vars\":[\"article\"]},\"results\":{\"bindings\":[{\"article\":{\"type\":\"uri\",\"value\":\"https://en.wikipedia.org/wiki/Douglas_Adams\"}},{\"article\":{\"type\":\"uri\",\"value\":\"https://de.wikipedia.org/wiki/Douglas_Adams\"}}]}}".to_owned());
+        let query = "SELECT ?article WHERE { ?article schema:about wd:Q42 }";
+        let results = sparql.query(query).await.unwrap();
+        assert_eq!(*sparql.client.url.lock().unwrap(), vec!["https://query.wikidata.org/sparql".to_owned()]);
+        assert_eq!(*sparql.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("query".to_owned(), query.to_owned()),
+                    ("format".to_owned(), "json".to_owned())
+                    ]]);
+        let pages = results.bindings.iter().filter_map(|x| x["article"].to_page(&wikipedia)).collect::<Vec<_>>();
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0] == wikipedia.page_from_title("Douglas Adams".to_owned()));
+    }
+
+    #[test]
+    fn page_from_url() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        assert!(
+                wikipedia.page_from_url("https://en.wikipedia.org/wiki/Caf%C3%A9_au_lait#History").unwrap()
+                == wikipedia.page_from_title("Café au lait".to_owned()));
+        assert!(wikipedia.page_from_url("http://en.wikipedia.org/wiki/World").unwrap() == wikipedia.page_from_title("World".to_owned()));
+        assert!(wikipedia.page_from_url("https://en.wikipedia.org/wiki/World?oldid=1#History").unwrap() == wikipedia.page_from_title("World".to_owned()));
+        assert!(wikipedia.page_from_url("https://es.wikipedia.org/wiki/World").is_none());
+        assert!(wikipedia.page_from_url("http://www.wikidata.org/entity/Q42").is_none());
+    }
+
+
+    #[tokio::test]
+    async fn all_coordinates() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"Route 66\",\"coordinates\":[{\"lat\":41.87,\"lon\":-87.62,\"globe\":\"earth\",\"type\":\"landmark\",\"name\":\"Chicago end\",\"dim\":\"1000\",\"country\":\"US\",\"region\":\"IL\"},{\"lat\":34.01,\"lon\":-118.49,\"primary\":\"\",\"globe\":\"earth\",\"dim\":10000}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"Route 66\",\"coordinates\":[{\"lat\":41.87,\"lon\":-87.62,\"globe\":\"earth\"},{\"lat\":34.01,\"lon\":-118.49,\"primary\":\"\",\"globe\":\"earth\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Route 66".to_owned());
+        assert_eq!(
+                page.get_all_coordinates().await.unwrap(),
+                vec![
+                    super::Coordinate {
+                        lat: 41.87,
+                        lon: -87.62,
+                        primary: false,
+                        globe: "earth".to_owned(),
+                        dim: Some(1000),
+                        kind: Some("landmark".to_owned()),
+                        name: Some("Chicago end".to_owned()),
+                        country: Some("US".to_owned()),
+                        region: Some("IL".to_owned()),
+                    },
+                    super::Coordinate {
+                        lat: 34.01,
+                        lon: -118.49,
+                        primary: true,
+                        globe: "earth".to_owned(),
+                        dim: Some(10000),
+                        kind: None,
+                        name: None,
+                        country: None,
+                        region: None,
+                    },
+                ]);
+        assert_eq!(page.get_coordinates().await.unwrap(), Some((34.01, -118.49)));
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[0],
+                vec![
+                    ("prop".to_owned(), "coordinates".to_owned()),
+                    ("colimit".to_owned(), "max".to_owned()),
+                    ("coprop".to_owned(), "type|name|dim|country|region|globe".to_owned()),
+                    ("coprimary".to_owned(), "all".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "Route 66".to_owned())
+                    ]);
+    }
+
+
+    #[tokio::test]
+    async fn geosearch_with() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"geosearch\":[{\"pageid\":1,\"ns\":0,\"title\":\"Obelisk\",\"lat\":-34.6037,\"lon\":-58.3816,\"dist\":12.5,\"primary\":\"\",\"type\":\"landmark\",\"dim\":\"100\",\"country\":\"AR\",\"region\":\"C\"}]}}".to_owned());
+        let query = super::GeoQuery {
+            page: Some("Buenos Aires".to_owned()),
+            radius: Some(500),
+            namespaces: vec![0, 6],
+            details: true,
+            primary: super::GeoPrimary::All,
+            limit: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(
+                wikipedia.geosearch_with(query).await.unwrap(),
+                vec![super::GeoHit {
+                    title: "Obelisk".to_owned(),
+                    pageid: 1,
+                    ns: 0,
+                    lat: -34.6037,
+                    lon: -58.3816,
+                    dist: Some(12.5),
+                    primary: true,
+                    kind: Some("landmark".to_owned()),
+                    dim: Some(100),
+                    country: Some("AR".to_owned()),
+                    region: Some("C".to_owned()),
+                }]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("list".to_owned(), "geosearch".to_owned()),
+                    ("gspage".to_owned(), "Buenos Aires".to_owned()),
+                    ("gsradius".to_owned(), "500".to_owned()),
+                    ("gsnamespace".to_owned(), "0|6".to_owned()),
+                    ("gsprop".to_owned(), "type|dim|country|region".to_owned()),
+                    ("gsprimary".to_owned(), "all".to_owned()),
+                    ("gslimit".to_owned(), "5".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned())
+                    ]]);
+    }
+
+    #[tokio::test]
+    async fn geosearch_with_bbox() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"geosearch\":[]}}".to_owned());
+        let query = super::GeoQuery {
+            bbox: Some((10.0, 20.0, 0.0, 30.0)),
+            globe: Some("moon".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(wikipedia.geosearch_with(query).await.unwrap(), vec![]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("list".to_owned(), "geosearch".to_owned()),
+                    ("gsbbox".to_owned(), "10|20|0|30".to_owned()),
+                    ("gsglobe".to_owned(), "moon".to_owned()),
+                    ("gsprimary".to_owned(), "primary".to_owned()),
+                    ("gslimit".to_owned(), "10".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned())
+                    ]]);
+        assert!(wikipedia.geosearch_with(super::GeoQuery::default()).await.is_err());
+        let query = super::GeoQuery {
+            coordinates: Some((0.0, 0.0)),
+            radius: Some(20000),
+            ..Default::default()
+        };
+        assert!(wikipedia.geosearch_with(query).await.is_err());
+    }
+
+
+    #[tokio::test]
+    async fn section_tree() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"sections\":[{\"toclevel\":1,\"line\":\"A\",\"number\":\"1\"},{\"toclevel\":2,\"line\":\"A1\",\"number\":\"1.1\"},{\"toclevel\":3,\"line\":\"A1a\",\"number\":\"1.1.1\"},{\"toclevel\":2,\"line\":\"A2\",\"number\":\"1.2\"},{\"toclevel\":1,\"line\":\"B\",\"number\":\"2\"}]}}".to_owned());
+        let page = wikipedia.page_from_pageid("123".to_owned());
+        let tree = page.get_section_tree().await.unwrap();
+        let lines = |nodes: &Vec<super::SectionNode>| nodes.iter().map(|x| x.section.line.clone()).collect::<Vec<_>>();
+        assert_eq!(lines(&tree), vec!["A", "B"]);
+        assert_eq!(lines(&tree[0].children), vec!["A1", "A2"]);
+        assert_eq!(lines(&tree[0].children[0].children), vec!["A1a"]);
+        assert!(tree[0].children[1].children.is_empty());
+        assert!(tree[1].children.is_empty());
+    }
+
+
+    #[tokio::test]
+    async fn section_content_plaintext() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"sections\":[{\"toclevel\":1,\"level\":\"2\",\"line\":\"History\",\"index\":\"1\",\"anchor\":\"History\"},{\"toclevel\":2,\"level\":\"3\",\"line\":\"Early\",\"index\":\"2\",\"anchor\":\"Early\"},{\"toclevel\":1,\"level\":\"2\",\"line\":\"Places\",\"index\":\"3\",\"anchor\":\"Places\"},{\"toclevel\":2,\"level\":\"3\",\"line\":\"Early\",\"index\":\"4\",\"anchor\":\"Early_2\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"123\":{\"extract\":\"Intro.\\n\\n== History ==\\nOld times.\\n\\n=== Early ===\\nVery old.\\n\\n== Places ==\\nSome places.\\n\\n=== Early ===\\nFirst places.\\n\"}}}}".to_owned());
+        let page = wikipedia.page_from_pageid("123".to_owned());
+        assert_eq!(
+                page.get_section_content("History").await.unwrap(),
+                Some("Old times.\n\n=== Early ===\nVery old.".to_owned()));
+
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"sections\":[{\"toclevel\":1,\"level\":\"2\",\"line\":\"History\",\"index\":\"1\",\"anchor\":\"History\"},{\"toclevel\":2,\"level\":\"3\",\"line\":\"Early\",\"index\":\"2\",\"anchor\":\"Early\"},{\"toclevel\":1,\"level\":\"2\",\"line\":\"Places\",\"index\":\"3\",\"anchor\":\"Places\"},{\"toclevel\":2,\"level\":\"3\",\"line\":\"Early\",\"index\":\"4\",\"anchor\":\"Early_2\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"123\":{\"extract\":\"Intro.\\n\\n== History ==\\nOld times.\\n\\n=== Early ===\\nVery old.\\n\\n== Places ==\\nSome places.\\n\\n=== Early ===\\nFirst places.\\n\"}}}}".to_owned());
+        assert_eq!(
+                page.get_section_by_anchor("Early 2", super::SectionQuery::default()).await.unwrap(),
+                Some("First places.".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn section_content_wikitext_and_html() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let sections = "{\"parse\":{\"sections\":[{\"toclevel\":1,\"level\":\"2\",\"line\":\"History\",\"index\":\"1\",\"anchor\":\"History\"},{\"toclevel\":2,\"level\":\"3\",\"line\":\"Early\",\"index\":\"2\",\"anchor\":\"Early\"}]}}";
+        wikipedia.client.response.lock().unwrap().push(sections.to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"123\":{\"pageid\":123,\"revisions\":[{\"revid\":1,\"slots\":{\"main\":{\"contentmodel\":\"wikitext\",\"*\":\"== History ==\\nOld '''times'''.\\n\\n=== Early ===\\nVery old.\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_pageid("123".to_owned());
+        let query = super::SectionQuery {
+            format: super::SectionFormat::Wikitext,
+            include_subsections: false,
+        };
+        assert_eq!(
+                page.get_section_by_index("1", query).await.unwrap(),
+                Some("Old '''times'''.".to_owned()));
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[1],
+                vec![
+                    ("prop".to_owned(), "revisions".to_owned()),
+                    ("rvprop".to_owned(), "ids|content".to_owned()),
+                    ("rvslots".to_owned(), "main".to_owned()),
+                    ("rvsection".to_owned(), "1".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("pageids".to_owned(), "123".to_owned())
+                    ]);
+
+        wikipedia.client.response.lock().unwrap().push(sections.to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"text\":{\"*\":\"<div class=\\\"mw-parser-output\\\"><div class=\\\"mw-heading mw-heading2\\\"><h2 id=\\\"History\\\">History</h2></div>\\n<p>Old times.</p>\\n<div class=\\\"mw-heading mw-heading3\\\"><h3 id=\\\"Early\\\">Early</h3></div>\\n<p>Very old.</p></div>\"}}}".to_owned());
+        let query = super::SectionQuery {
+            format: super::SectionFormat::Html,
+            include_subsections: false,
+        };
+        assert_eq!(
+                page.get_section_by_anchor("History", query).await.unwrap(),
+                Some("<p>Old times.</p>".to_owned()));
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[3],
+                vec![
+                    ("prop".to_owned(), "text".to_owned()),
+                    ("section".to_owned(), "1".to_owned()),
+                    ("disableeditsection".to_owned(), "".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "parse".to_owned()),
+                    ("pageid".to_owned(), "123".to_owned())
+                    ]);
+
+        wikipedia.client.response.lock().unwrap().push(sections.to_owned());
+        assert_eq!(page.get_section_by_anchor("Missing", super::SectionQuery::default()).await.unwrap(), None);
+    }
+
+
+    #[tokio::test]
+    async fn wikitext() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\",\"revisions\":[{\"revid\":42,\"parentid\":41,\"slots\":{\"main\":{\"contentmodel\":\"wikitext\",\"contentformat\":\"text/x-wiki\",\"*\":\"== History ==\\nOld.\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(
+                page.get_section_wikitext("1").await.unwrap(),
+                super::Wikitext {
+                    content: "== History ==\nOld.".to_owned(),
+                    revid: 42,
+                    content_model: "wikitext".to_owned(),
+                });
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "revisions".to_owned()),
+                    ("rvprop".to_owned(), "ids|content".to_owned()),
+                    ("rvslots".to_owned(), "main".to_owned()),
+                    ("rvsection".to_owned(), "1".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned())
+                    ]]);
+    }
+
+
+    #[tokio::test]
+    async fn parse() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"title\":\"World\",\"pageid\":1,\"revid\":42,\"text\":{\"*\":\"<p>Hi</p>\"},\"links\":[{\"ns\":0,\"exists\":\"\",\"*\":\"Earth\"},{\"ns\":0,\"*\":\"Missing\"}],\"templates\":[{\"ns\":10,\"exists\":\"\",\"*\":\"Template:Infobox\"}],\"images\":[\"Earth_view.jpg\"],\"externallinks\":[\"https://example.com\"],\"categories\":[{\"sortkey\":\"\",\"hidden\":\"\",\"*\":\"Articles_with_short_description\"},{\"sortkey\":\"W\",\"*\":\"Worlds\"}],\"displaytitle\":\"<i>World</i>\"}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let options = super::ParseOptions {
+            props: vec![
+                super::ParseProp::Text,
+                super::ParseProp::Links,
+                super::ParseProp::Templates,
+                super::ParseProp::Images,
+                super::ParseProp::ExternalLinks,
+                super::ParseProp::Categories,
+                super::ParseProp::DisplayTitle,
+            ],
+            disable_edit_section: true,
+            disable_toc: true,
+            mobile_format: true,
+            use_skin: Some("vector".to_owned()),
+            section: Some("0".to_owned()),
+        };
+        assert_eq!(
+                page.parse(options).await.unwrap(),
+                super::ParseResult {
+                    title: "World".to_owned(),
+                    pageid: 1,
+                    revid: Some(42),
+                    text: Some("<p>Hi</p>".to_owned()),
+                    sections: vec![],
+                    links: vec![
+                        iter::Link { title: "Earth".to_owned(), ns: 0, exists: true },
+                        iter::Link { title: "Missing".to_owned(), ns: 0, exists: false },
+                    ],
+                    templates: vec![iter::Template { title: "Template:Infobox".to_owned(), ns: 10 }],
+                    images: vec!["Earth view.jpg".to_owned()],
+                    external_links: vec!["https://example.com".to_owned()],
+                    categories: vec![
+                        iter::Category {
+                            title: "Articles with short description".to_owned(),
+                            sortkey: "".to_owned(),
+                            sortkey_prefix: "".to_owned(),
+                            timestamp: None,
+                            hidden: true,
+                        },
+                        iter::Category {
+                            title: "Worlds".to_owned(),
+                            sortkey: "".to_owned(),
+                            sortkey_prefix: "W".to_owned(),
+                            timestamp: None,
+                            hidden: false,
+                        },
+                    ],
+                    display_title: Some("<i>World</i>".to_owned()),
+                    head_html: None,
+                });
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "text|links|templates|images|externallinks|categories|displaytitle".to_owned()),
+                    ("section".to_owned(), "0".to_owned()),
+                    ("disableeditsection".to_owned(), "".to_owned()),
+                    ("disabletoc".to_owned(), "".to_owned()),
+                    ("mobileformat".to_owned(), "".to_owned()),
+                    ("useskin".to_owned(), "vector".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "parse".to_owned()),
+                    ("page".to_owned(), "World".to_owned())
+                    ]]);
+    }
+
+
+    #[tokio::test]
+    async fn parse_wikitext() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"title\":\"World\",\"pageid\":0,\"text\":{\"*\":\"<div class=\\\"mw-parser-output\\\"><p><b>Hello</b>\\n</p></div>\"}}}".to_owned());
+        let text = "'''Hello'''".repeat(1000);
+        assert_eq!(
+                wikipedia.parse_wikitext(&text, Some("World")).await.unwrap(),
+                "<div class=\"mw-parser-output\"><p><b>Hello</b>\n</p></div>".to_owned());
+        assert_eq!(*wikipedia.client.methods.lock().unwrap(), vec!["POST".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "text".to_owned()),
+                    ("contentmodel".to_owned(), "wikitext".to_owned()),
+                    ("text".to_owned(), text.clone()),
+                    ("title".to_owned(), "World".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "parse".to_owned())
+                    ]]);
+    }
+
+    #[tokio::test]
+    async fn expand_templates() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"expandtemplates\":{\"wikitext\":\"4\"}}".to_owned());
+        assert_eq!(wikipedia.expand_templates("{{#expr: 2 + 2}}").await.unwrap(), "4".to_owned());
+        assert_eq!(*wikipedia.client.methods.lock().unwrap(), vec!["POST".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "wikitext".to_owned()),
+                    ("text".to_owned(), "{{#expr: 2 + 2}}".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "expandtemplates".to_owned())
+                    ]]);
+    }
+
+    #[tokio::test]
+    async fn get_infobox() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\": {\"pages\": {\"a\": {\"revisions\": [{\"revid\": 1, \"parentid\": 0, \"slots\": {\"main\": {\"contentmodel\": \"wikitext\", \"*\": \"{{Short description|Writer}}\\n{{Infobox person\\n| name = Douglas Adams\\n| birth_date = {{birth date|1952|3|11|df=y}}\\n| birth_place = [[Cambridge]], England\\n| spouse = <!-- none --> \\n| occupation = {{hlist|Writer|[[humorist]]}}<ref>x</ref>\\n}}\\n'''Douglas Adams''' was a writer.\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Douglas Adams".to_owned());
+        let infobox = page.get_infobox().await.unwrap().unwrap();
+        assert_eq!(infobox.kind, "person");
+        assert_eq!(infobox.params.iter().map(|x| &*x.0).collect::<Vec<_>>(),
+                vec!["name", "birth_date", "birth_place", "occupation"]);
+        assert_eq!(infobox.get("birth_date").unwrap().text, "11 March 1952");
+        assert_eq!(infobox.get("birth_place").unwrap(), &super::InfoboxValue {
+            text: "Cambridge, England".to_owned(),
+            links: vec!["Cambridge".to_owned()],
+        });
+        assert_eq!(infobox.get("occupation").unwrap(), &super::InfoboxValue {
+            text: "Writer\nhumorist".to_owned(),
+            links: vec!["Humorist".to_owned()],
+        });
+        assert_eq!(wikipedia.client.arguments.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn get_infobox_html() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\": {\"pages\": {\"a\": {\"revisions\": [{\"revid\": 1, \"parentid\": 0, \"slots\": {\"main\": {\"contentmodel\": \"wikitext\", \"*\": \"{{Speciesbox|genus=Felis}}The cat.\"}}}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"parse\": {\"title\": \"Cat\", \"pageid\": 1, \"text\": {\"*\": \"<table class=\\\"infobox biota\\\"><tbody><tr><th colspan=\\\"2\\\">Cat</th></tr><tr><th scope=\\\"row\\\">Kingdom:</th><td><a href=\\\"/wiki/Animal\\\" title=\\\"Animal\\\">Animalia</a><sup class=\\\"reference\\\"><a href=\\\"#cite_note-1\\\">[1]</a></sup></td></tr><tr><th>Names</th><td>Felis&#160;catus<br />Cat</td></tr></tbody></table><p>The cat.</p>\"}}}".to_owned());
+        let page = wikipedia.page_from_title("Cat".to_owned());
+        let infobox = page.get_infobox().await.unwrap().unwrap();
+        assert_eq!(infobox, super::Infobox {
+            kind: "".to_owned(),
+            params: vec![
+                ("Kingdom:".to_owned(), super::InfoboxValue {
+                    text: "Animalia".to_owned(),
+                    links: vec!["Animal".to_owned()],
+                }),
+                ("Names".to_owned(), super::InfoboxValue {
+                    text: "Felis catus\nCat".to_owned(),
+                    links: vec![],
+                }),
+            ],
+        });
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[1][3],
+                ("action".to_owned(), "parse".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn get_tables() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\": {\"pages\": {\"a\": {\"revisions\": [{\"revid\": 1, \"parentid\": 0, \"slots\": {\"main\": {\"contentmodel\": \"wikitext\", \"*\": \"Results.\\n{| class=\\\"wikitable\\\"\\n|+ Election <ref>x</ref>\\n|-\\n! rowspan=\\\"2\\\" | Party !! colspan=\\\"2\\\" | Votes\\n|-\\n! Number !! %\\n|-\\n| [[Red Party|Red]] || 1,000 || 60\\n|-\\n| rowspan=2 | Blue || 500 || 30\\n|-\\n| 200 || 10\\n|}\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Election".to_owned());
+        let tables = page.get_tables().await.unwrap();
+        assert_eq!(tables, vec![super::Table {
+            caption: Some("Election".to_owned()),
+            headers: vec!["Party".to_owned(), "Votes / Number".to_owned(), "Votes / %".to_owned()],
+            rows: vec![
+                vec!["Red".to_owned(), "1,000".to_owned(), "60".to_owned()],
+                vec!["Blue".to_owned(), "500".to_owned(), "30".to_owned()],
+                vec!["Blue".to_owned(), "200".to_owned(), "10".to_owned()],
+            ],
+        }]);
+    }
+
+    #[tokio::test]
+    async fn get_html_tables() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"parse\": {\"title\": \"Medals\", \"pageid\": 1, \"text\": {\"*\": \"<div class=\\\"navbox\\\"><table class=\\\"nowraplinks\\\"><tr><td>Nav</td></tr></table></div><table class=\\\"wikitable sortable\\\"><caption>Medals</caption><tbody><tr><th>Nation</th><th>Gold</th></tr><tr><td><a href=\\\"/wiki/Norway\\\" title=\\\"Norway\\\">Norway</a><sup class=\\\"reference\\\"><a href=\\\"#cite_note-1\\\">[1]</a></sup></td><td>16</td></tr><tr><td colspan=\\\"2\\\">Total&#160;<table><tr><td>nested</td></tr></table></td></tr></tbody></table>\"}}}".to_owned());
+        let page = wikipedia.page_from_title("Medals".to_owned());
+        let tables = page.get_html_tables().await.unwrap();
+        assert_eq!(tables, vec![super::Table {
+            caption: Some("Medals".to_owned()),
+            headers: vec!["Nation".to_owned(), "Gold".to_owned()],
+            rows: vec![
+                vec!["Norway".to_owned(), "16".to_owned()],
+                vec!["Total nested".to_owned(), "Total nested".to_owned()],
+            ],
+        }]);
+    }
+
+    #[test]
+    fn table_export() {
+        let table = super::Table {
+            caption: None,
+            headers: vec!["Name".to_owned(), "Note".to_owned()],
+            rows: vec![vec!["A".to_owned(), "says \"hi\", twice".to_owned()]],
+        };
+        assert_eq!(table.to_csv(), "Name,Note\nA,\"says \"\"hi\"\", twice\"\n");
+        assert_eq!(table.to_json(), serde_json::json!({
+            "caption": null,
+            "headers": ["Name", "Note"],
+            "rows": [["A", "says \"hi\", twice"]],
+        }));
+    }
+
+    #[tokio::test]
+    async fn get_markdown() {
+        let mut wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.set_base_url("https://es.wikipedia.org/w/api.php");
+        wikipedia.client.response.lock().unwrap().push("{\"query\": {\"pages\": {\"a\": {\"revisions\": [{\"revid\": 1, \"parentid\": 0, \"slots\": {\"main\": {\"contentmodel\": \"wikitext\", \"*\": \"'''Hola''' es [[Espa\\u00f1a]].\\n\\n== Historia ==\\nTexto.\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Hola".to_owned());
+        let markdown = page.get_markdown(super::MarkdownOptions::default()).await.unwrap();
+        assert_eq!(markdown, "**Hola** es [España](https://es.wikipedia.org/wiki/España).\n\n## Historia\n\nTexto.\n");
+    }
+
+    #[tokio::test]
+    async fn page_extract() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extract\":\"Hello. World.\"}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extract\":\"<p><b>Hello</b>…</p>\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("Hello".to_owned());
+        let extract = page.get_extract(super::ExtractOptions {
+            sentences: Some(2),
+            intro: true,
+            section_format: super::ExtractSectionFormat::Wiki,
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(extract, "Hello. World.");
+        let extract = page.get_extract(super::ExtractOptions {
+            chars: Some(100),
+            html: true,
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(extract, "<p><b>Hello</b>…</p>");
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "extracts".to_owned()),
+                    ("exsentences".to_owned(), "2".to_owned()),
+                    ("exintro".to_owned(), "".to_owned()),
+                    ("explaintext".to_owned(), "".to_owned()),
+                    ("exsectionformat".to_owned(), "wiki".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "Hello".to_owned())
+                    ], vec![
+                    ("prop".to_owned(), "extracts".to_owned()),
+                    ("exchars".to_owned(), "100".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "Hello".to_owned())
+                    ]]);
+    }
+
+    #[tokio::test]
+    async fn page_extract_invalid() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let page = wikipedia.page_from_title("Hello".to_owned());
+        let options = super::ExtractOptions {
+            sentences: Some(2),
+            chars: Some(100),
+            ..Default::default()
+        };
+        assert!(page.get_extract(options).await.is_err());
+        let options = super::ExtractOptions {
+            sentences: Some(11),
+            ..Default::default()
+        };
+        assert!(page.get_extract(options).await.is_err());
+        assert!(wikipedia.client.arguments.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn html_tables_truncated() {
+        assert_eq!(super::Table::from_html("<p>Text</p><table"), vec![]);
+        assert_eq!(super::Table::from_html("<table class=\"wikitable\"><tr><td>1</td></tr></table><table日本"), vec![super::Table {
+            caption: None,
+            headers: vec![],
+            rows: vec![vec!["1".to_owned()]],
+        }]);
+        assert_eq!(super::Infobox::from_html("<table"), None);
+    }
+
+    #[tokio::test]
+    async fn get_categories_localized_continuation() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"clcontinue\":\"1|B\",\"continue\":\"||\"},\"query\":{\"namespaces\":{\"14\":{\"id\":14,\"*\":\"Kategorie\",\"canonical\":\"Category\"}},\"pages\":{\"a\":{\"categories\":[{\"ns\":14,\"title\":\"Kategorie:A\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"categories\":[{\"ns\":14,\"title\":\"Kategorie:B\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Welt".to_owned());
+        let categories = page.get_categories().await.unwrap().collect_vec::<Vec<_>>().await;
+        assert_eq!(categories.iter().map(|x| &*x.title).collect::<Vec<_>>(), vec!["A", "B"]);
+        let arguments = wikipedia.client.arguments.lock().unwrap();
+        assert!(arguments[0].contains(&("meta".to_owned(), "siteinfo".to_owned())));
+        assert!(!arguments[1].contains(&("meta".to_owned(), "siteinfo".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn post_not_implemented() {
+        #[derive(Default)]
+        struct GetClient;
+        impl super::http::HttpClient for GetClient {
+            fn user_agent(&mut self, _user_agent: String) {}
+            async fn get<'a, I>(&self, _base_url: &str, _args: I) -> Result<String, super::http::Error>
+                    where I: Iterator<Item=(&'a str, &'a str)> {
+                panic!("POST requests must not fall back to GET");
+            }
+        }
+        let wikipedia = Wikipedia::<GetClient>::default();
+        assert!(wikipedia.parse_wikitext("''Hello''", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn category_members_timestamp_types() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"cmcontinue\":\"2020|1\"},\"query\":{\"categorymembers\":[{\"pageid\":1,\"ns\":6,\"title\":\"File:Hello.jpg\",\"type\":\"file\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"categorymembers\":[{\"pageid\":2,\"ns\":14,\"title\":\"Category:World\",\"type\":\"subcat\"},{\"pageid\":3,\"ns\":0,\"title\":\"Hi\",\"type\":\"page\"}]}}".to_owned());
+        let query = iter::CategoryMemberQuery {
+            types: vec![iter::CategoryMemberType::Page],
+            sort: iter::CategoryMemberSort::Timestamp,
+            ..Default::default()
+        };
+        let members = wikipedia.category_members("Category:Greetings".to_owned(), query).await.unwrap().collect_vec::<Vec<_>>().await;
+        assert_eq!(members.iter().map(|x| &*x.title).collect::<Vec<_>>(), vec!["Hi"]);
+        assert_eq!(wikipedia.client.arguments.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn transcluded_section_wikitext() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert!(page.get_section_wikitext("T-1").await.is_err());
+        assert!(wikipedia.client.arguments.lock().unwrap().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod wasm_tests {
+    use super::Wikipedia;
+    use super::http::HttpClient;
+    use super::iter;
+    use std::sync::Mutex;
+
+    use crate::iter::AsyncIterator;
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    struct MockClient {
+        pub url: Mutex<Vec<String>>,
+        pub user_agent: Option<String>,
+        pub arguments: Mutex<Vec<Vec<(String, String)>>>,
+        pub response: Mutex<Vec<String>>,
+        pub methods: Mutex<Vec<String>>,
+    }
+
+    impl Default for crate::wasm_tests::MockClient {
+        fn default() -> Self {
+            crate::wasm_tests::MockClient {
+                url: Mutex::new(Vec::new()),
+                user_agent: None,
+                arguments: Mutex::new(Vec::new()),
+                response: Mutex::new(Vec::new()),
+                methods: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl super::http::HttpClient for crate::wasm_tests::MockClient {
+        fn user_agent(&mut self, user_agent: String) {
+            self.user_agent = Some(user_agent)
+        }
 
         async fn get<'a, I>(&self, base_url: &str, args: I) -> Result<String, super::http::Error>
         where I: Iterator<Item=(&'a str, &'a str)> {
+            self.methods.lock().unwrap().push("GET".to_owned());
+            self.url.lock().unwrap().push(base_url.to_owned());
+            self.arguments.lock().unwrap().push(args.map(|x| (x.0.to_owned(), x.1.to_owned())).collect());
+            Ok(self.response.lock().unwrap().remove(0))
+        }
+
+        async fn post<'a, I>(&self, base_url: &str, args: I) -> Result<String, super::http::Error>
+        where I: Iterator<Item=(&'a str, &'a str)> {
+            self.methods.lock().unwrap().push("POST".to_owned());
             self.url.lock().unwrap().push(base_url.to_owned());
             self.arguments.lock().unwrap().push(args.map(|x| (x.0.to_owned(), x.1.to_owned())).collect());
             Ok(self.response.lock().unwrap().remove(0))
@@ -1344,495 +4664,1765 @@ mod wasm_tests {
     }
 
     #[wasm_bindgen_test]
-    fn base_url() {
-        let mut wikipedia = Wikipedia::<MockClient>::default();
-        assert_eq!(wikipedia.base_url(), "https://en.wikipedia.org/w/api.php");
-        wikipedia.language = "es".to_owned();
-        assert_eq!(wikipedia.base_url(), "https://es.wikipedia.org/w/api.php");
+    fn base_url() {
+        let mut wikipedia = Wikipedia::<MockClient>::default();
+        assert_eq!(wikipedia.base_url(), "https://en.wikipedia.org/w/api.php");
+        wikipedia.language = "es".to_owned();
+        assert_eq!(wikipedia.base_url(), "https://es.wikipedia.org/w/api.php");
+
+        wikipedia.set_base_url("https://hello.{language}.world/");
+        assert_eq!(wikipedia.base_url(), "https://hello.es.world/");
+
+        wikipedia.set_base_url("https://hello.world/");
+        assert_eq!(wikipedia.base_url(), "https://hello.world/");
+    }
+
+    #[wasm_bindgen_test]
+    async fn user_agent() {
+        let mut wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{}".to_owned());
+        wikipedia.search("hello world").await.unwrap_err();
+        assert_eq!(&*wikipedia.client.user_agent.unwrap(), "wikipedia (https://github.com/seppo0010/wikipedia-rs)");
+
+        let mut client = MockClient::default();
+        client.user_agent("hello world".to_owned());
+        client.response.lock().unwrap().push("{}".to_owned());
+        wikipedia.client = client;
+        wikipedia.search("hello world").await.unwrap_err();
+        assert_eq!(&*wikipedia.client.user_agent.unwrap(), "hello world");
+    }
+
+    #[wasm_bindgen_test]
+    async fn search() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"search\":[{\"title\":\"hello\"}, {\"title\":\"world\"}]}}".to_owned());
+        assert_eq!(
+            wikipedia.search("hello world").await.unwrap(),
+            vec![
+                "hello".to_owned(),
+                "world".to_owned(),
+            ]);
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![vec![
+                       ("list".to_owned(), "search".to_owned()),
+                       ("srprop".to_owned(), "".to_owned()),
+                       ("srlimit".to_owned(), "10".to_owned()),
+                       ("srsearch".to_owned(), "hello world".to_owned()),
+                       ("format".to_owned(), "json".to_owned()),
+                       ("action".to_owned(), "query".to_owned())
+                   ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn geosearch() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"geosearch\":[{\"title\":\"hello\"}, {\"title\":\"world\"}]}}".to_owned());
+        assert_eq!(
+            wikipedia.geosearch(-34.603333, -58.381667, 10).await.unwrap(),
+            vec![
+                "hello".to_owned(),
+                "world".to_owned(),
+            ]);
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![vec![
+                       ("list".to_owned(), "geosearch".to_owned()),
+                       ("gsradius".to_owned(), "10".to_owned()),
+                       ("gscoord".to_owned(), "-34.603333|-58.381667".to_owned()),
+                       ("gslimit".to_owned(), "10".to_owned()),
+                       ("format".to_owned(), "json".to_owned()),
+                       ("action".to_owned(), "query".to_owned())
+                   ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn random_count() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"random\":[{\"title\":\"hello\"}, {\"title\":\"world\"}]}}".to_owned());
+        assert_eq!(
+            wikipedia.random_count(10).await.unwrap(),
+            vec![
+                "hello".to_owned(),
+                "world".to_owned(),
+            ]);
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![vec![
+                       ("list".to_owned(), "random".to_owned()),
+                       ("rnnamespace".to_owned(), "0".to_owned()),
+                       ("rnlimit".to_owned(), "10".to_owned()),
+                       ("format".to_owned(), "json".to_owned()),
+                       ("action".to_owned(), "query".to_owned())
+                   ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn random() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"random\":[{\"title\":\"hello\"}, {\"title\":\"world\"}]}}".to_owned());
+        assert_eq!(
+            wikipedia.random().await.unwrap(),
+            Some("hello".to_owned())
+        );
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![vec![
+                       ("list".to_owned(), "random".to_owned()),
+                       ("rnnamespace".to_owned(), "0".to_owned()),
+                       ("rnlimit".to_owned(), "1".to_owned()),
+                       ("format".to_owned(), "json".to_owned()),
+                       ("action".to_owned(), "query".to_owned())
+                   ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_content() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extract\":\"hello\"}}}}".to_owned());
+        let page = wikipedia.page_from_pageid("4138548".to_owned());
+        let html = page.get_content().await.unwrap();
+        assert_eq!(
+            html,
+            "hello".to_owned()
+        );
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![vec![
+                       ("prop".to_owned(), "extracts|revisions".to_owned()),
+                       ("explaintext".to_owned(), "".to_owned()),
+                       ("rvprop".to_owned(), "ids".to_owned()),
+                       ("redirects".to_owned(), "".to_owned()),
+                       ("format".to_owned(), "json".to_owned()),
+                       ("action".to_owned(), "query".to_owned()),
+                       ("pageids".to_owned(), "4138548".to_owned()),
+                   ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_html_content() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"title\":\"Law of triviality\",\"pageid\":4138548,\"text\":{\"*\":\"hello\"}}}".to_owned());
+        let page = wikipedia.page_from_pageid("4138548".to_owned());
+        let html = page.get_html_content().await.unwrap();
+        assert_eq!(
+            html,
+            "hello".to_owned()
+        );
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![vec![
+                       ("prop".to_owned(), "text".to_owned()),
+                       ("redirects".to_owned(), "".to_owned()),
+                       ("format".to_owned(), "json".to_owned()),
+                       ("action".to_owned(), "parse".to_owned()),
+                       ("pageid".to_owned(), "4138548".to_owned()),
+                   ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_summary() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extract\":\"hello\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("Parkinson's law of triviality".to_owned());
+        let summary = page.get_summary().await.unwrap();
+        assert_eq!(
+            summary,
+            "hello".to_owned()
+        );
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![vec![
+                       ("prop".to_owned(), "extracts".to_owned()),
+                       ("explaintext".to_owned(), "".to_owned()),
+                       ("exintro".to_owned(), "".to_owned()),
+                       ("redirects".to_owned(), "".to_owned()),
+                       ("format".to_owned(), "json".to_owned()),
+                       ("action".to_owned(), "query".to_owned()),
+                       ("titles".to_owned(), "Parkinson\'s law of triviality".to_owned())
+                   ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_redirect_summary() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"redirects\":[{\"to\":\"hello world\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extract\":\"hello\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("Parkinson's law of triviality".to_owned());
+        let summary = page.get_summary().await.unwrap();
+        assert_eq!(
+            summary,
+            "hello".to_owned()
+        );
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec![
+                       "https://en.wikipedia.org/w/api.php".to_owned(),
+                       "https://en.wikipedia.org/w/api.php".to_owned(),
+                   ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![
+                       vec![
+                           ("prop".to_owned(), "extracts".to_owned()),
+                           ("explaintext".to_owned(), "".to_owned()),
+                           ("exintro".to_owned(), "".to_owned()),
+                           ("redirects".to_owned(), "".to_owned()),
+                           ("format".to_owned(), "json".to_owned()),
+                           ("action".to_owned(), "query".to_owned()),
+                           ("titles".to_owned(), "Parkinson\'s law of triviality".to_owned())
+                       ],
+                       vec![
+                           ("prop".to_owned(), "extracts".to_owned()),
+                           ("explaintext".to_owned(), "".to_owned()),
+                           ("exintro".to_owned(), "".to_owned()),
+                           ("redirects".to_owned(), "".to_owned()),
+                           ("format".to_owned(), "json".to_owned()),
+                           ("action".to_owned(), "query".to_owned()),
+                           ("titles".to_owned(), "hello world".to_owned())
+                       ]
+                   ]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_images() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"title\":\"Image 1\", \"imageinfo\":[{\"url\": \"http://example.com/image1.jpg\", \"descriptionurl\": \"http://example.com/image1.jpg.html\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"title\":\"Image 2\", \"imageinfo\":[{\"url\": \"http://example.com/image2.jpg\", \"descriptionurl\": \"http://example.com/image2.jpg.html\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Parkinson's law of triviality".to_owned());
+        let images= page.get_images().await.unwrap().collect_vec::<Vec<_>>().await;
+        assert_eq!(
+            images,
+            vec![
+                iter::Image {
+                    url: "http://example.com/image1.jpg".to_owned(),
+                    title: "Image 1".to_owned(),
+                    description_url: "http://example.com/image1.jpg.html".to_owned(),
+                    ..Default::default()
+                },
+                iter::Image {
+                    url: "http://example.com/image2.jpg".to_owned(),
+                    title: "Image 2".to_owned(),
+                    description_url: "http://example.com/image2.jpg.html".to_owned(),
+                    ..Default::default()
+                }
+            ]);
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec![
+                       "https://en.wikipedia.org/w/api.php".to_owned(),
+                       "https://en.wikipedia.org/w/api.php".to_owned(),
+                   ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![
+                       vec![
+                           ("generator".to_owned(), "images".to_owned()),
+                           ("gimlimit".to_owned(), "max".to_owned()),
+                           ("prop".to_owned(), "imageinfo".to_owned()),
+                           ("iiprop".to_owned(), "url".to_owned()),
+                           ("format".to_owned(), "json".to_owned()),
+                           ("action".to_owned(), "query".to_owned()),
+                           ("titles".to_owned(), "Parkinson\'s law of triviality".to_owned()),
+                           ("continue".to_owned(), "".to_owned())
+                       ],
+                       vec![
+                           ("generator".to_owned(), "images".to_owned()),
+                           ("gimlimit".to_owned(), "max".to_owned()),
+                           ("prop".to_owned(), "imageinfo".to_owned()),
+                           ("iiprop".to_owned(), "url".to_owned()),
+                           ("format".to_owned(), "json".to_owned()),
+                           ("action".to_owned(), "query".to_owned()),
+                           ("titles".to_owned(), "Parkinson\'s law of triviality".to_owned()),
+                           ("lol".to_owned(), "1".to_owned())
+                       ]
+                   ]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_coordinates() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"coordinates\":[{\"lat\": 2.1, \"lon\":-1.3}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let coordinates = page.get_coordinates().await.unwrap().unwrap();
+        assert_eq!(
+            coordinates,
+            (2.1, -1.3)
+        );
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![vec![
+                       ("prop".to_owned(), "coordinates".to_owned()),
+                       ("colimit".to_owned(), "max".to_owned()),
+                       ("coprop".to_owned(), "type|name|dim|country|region|globe".to_owned()),
+                       ("coprimary".to_owned(), "all".to_owned()),
+                       ("redirects".to_owned(), "".to_owned()),
+                       ("format".to_owned(), "json".to_owned()),
+                       ("action".to_owned(), "query".to_owned()),
+                       ("titles".to_owned(), "World".to_owned())
+                   ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_no_coordinates() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert!(page.get_coordinates().await.unwrap().is_none());
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![vec![
+                       ("prop".to_owned(), "coordinates".to_owned()),
+                       ("colimit".to_owned(), "max".to_owned()),
+                       ("coprop".to_owned(), "type|name|dim|country|region|globe".to_owned()),
+                       ("coprimary".to_owned(), "all".to_owned()),
+                       ("redirects".to_owned(), "".to_owned()),
+                       ("format".to_owned(), "json".to_owned()),
+                       ("action".to_owned(), "query".to_owned()),
+                       ("titles".to_owned(), "World".to_owned())
+                   ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_references() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"*\": \"https://Example.com:8080/reference1.html\"}, {\"*\": \"//example.com/reference2.html\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"*\": \"mailto:someone@example.org\"}, {\"*\": \"ftp://user@files.example.net/file\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(
+                page.get_references().await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![
+                iter::Reference {
+                    url: "https://Example.com:8080/reference1.html".to_owned(),
+                    protocol: "https".to_owned(),
+                    domain: "example.com".to_owned(),
+                },
+                iter::Reference {
+                    url: "https://example.com/reference2.html".to_owned(),
+                    protocol: "https".to_owned(),
+                    domain: "example.com".to_owned(),
+                },
+                iter::Reference {
+                    url: "mailto:someone@example.org".to_owned(),
+                    protocol: "mailto".to_owned(),
+                    domain: "example.org".to_owned(),
+                },
+                iter::Reference {
+                    url: "ftp://user@files.example.net/file".to_owned(),
+                    protocol: "ftp".to_owned(),
+                    domain: "files.example.net".to_owned(),
+                }
+                ]);
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                vec![
+                "https://en.wikipedia.org/w/api.php".to_owned(),
+                "https://en.wikipedia.org/w/api.php".to_owned(),
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "extlinks".to_owned()),
+                    ("ellimit".to_owned(), "max".to_owned()),
+                    ("elexpandurl".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("continue".to_owned(), "".to_owned())
+                ],
+                vec![
+                    ("prop".to_owned(), "extlinks".to_owned()),
+                    ("ellimit".to_owned(), "max".to_owned()),
+                    ("elexpandurl".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("lol".to_owned(), "1".to_owned())
+                ]
+                ]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_links() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"-1\":{\"ns\":0,\"title\":\"Hello\",\"missing\":\"\"},\"12\":{\"pageid\":12,\"ns\":0,\"title\":\"Goodbye\"}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"pageid\":13,\"ns\":0,\"title\":\"World\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(
+                page.get_links().await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![
+                iter::Link {
+                    title: "Goodbye".to_owned(),
+                    ns: 0,
+                    exists: true,
+                },
+                iter::Link {
+                    title: "Hello".to_owned(),
+                    ns: 0,
+                    exists: false,
+                },
+                iter::Link {
+                    title: "World".to_owned(),
+                    ns: 0,
+                    exists: true,
+                }
+                ]);
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                vec![
+                "https://en.wikipedia.org/w/api.php".to_owned(),
+                "https://en.wikipedia.org/w/api.php".to_owned(),
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![
+                vec![
+                    ("generator".to_owned(), "links".to_owned()),
+                    ("gpllimit".to_owned(), "max".to_owned()),
+                    ("prop".to_owned(), "info".to_owned()),
+                    ("gplnamespace".to_owned(), "0".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                ],
+                vec![
+                    ("generator".to_owned(), "links".to_owned()),
+                    ("gpllimit".to_owned(), "max".to_owned()),
+                    ("prop".to_owned(), "info".to_owned()),
+                    ("gplnamespace".to_owned(), "0".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("lol".to_owned(), "1".to_owned()),
+                ]
+                ]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_categories() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"namespaces\":{\"14\":{\"id\":14,\"*\":\"Kategorie\",\"canonical\":\"Category\"}},\"pages\":{\"a\":{\"categories\":[{\"ns\":14,\"title\":\"Kategorie:Hello\",\"sortkey\":\"48454c4c4f\",\"sortkeyprefix\":\"\",\"timestamp\":\"2020-01-01T00:00:00Z\",\"hidden\":\"\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"categories\":[{\"ns\":14,\"title\":\"Category:World\",\"sortkey\":\"574f524c44\",\"sortkeyprefix\":\"W\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(
+                page.get_categories().await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![
+                iter::Category {
+                    title: "Hello".to_owned(),
+                    sortkey: "48454c4c4f".to_owned(),
+                    sortkey_prefix: "".to_owned(),
+                    timestamp: Some("2020-01-01T00:00:00Z".to_owned()),
+                    hidden: true,
+                },
+                iter::Category {
+                    title: "World".to_owned(),
+                    sortkey: "574f524c44".to_owned(),
+                    sortkey_prefix: "W".to_owned(),
+                    timestamp: None,
+                    hidden: false,
+                }
+                ]);
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                vec![
+                "https://en.wikipedia.org/w/api.php".to_owned(),
+                "https://en.wikipedia.org/w/api.php".to_owned(),
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![
+                vec![
+                    ("prop".to_owned(), "categories".to_owned()),
+                    ("clprop".to_owned(), "sortkey|timestamp|hidden".to_owned()),
+                    ("cllimit".to_owned(), "max".to_owned()),
+                    ("meta".to_owned(), "siteinfo".to_owned()),
+                    ("siprop".to_owned(), "namespaces".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                ],
+                vec![
+                    ("prop".to_owned(), "categories".to_owned()),
+                    ("clprop".to_owned(), "sortkey|timestamp|hidden".to_owned()),
+                    ("cllimit".to_owned(), "max".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("lol".to_owned(), "1".to_owned()),
+                ]
+                ]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn sections() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"sections\":[{\"toclevel\":1,\"level\":\"2\",\"line\":\"hello\",\"number\":\"1\",\"index\":\"1\",\"fromtitle\":\"World\",\"byteoffset\":120,\"anchor\":\"hello\"}, {\"toclevel\":2,\"level\":\"3\",\"line\":\"world\",\"number\":\"1.1\",\"index\":\"T-1\",\"fromtitle\":\"Template:World\",\"byteoffset\":null,\"anchor\":\"world\"}]}}".to_owned());
+        let page = wikipedia.page_from_pageid("123".to_owned());
+        assert_eq!(
+                page.get_sections().await.unwrap(),
+                vec![
+                    super::Section {
+                        line: "hello".to_owned(),
+                        level: 2,
+                        toclevel: 1,
+                        number: "1".to_owned(),
+                        anchor: "hello".to_owned(),
+                        index: "1".to_owned(),
+                        byteoffset: Some(120),
+                    },
+                    super::Section {
+                        line: "world".to_owned(),
+                        level: 3,
+                        toclevel: 2,
+                        number: "1.1".to_owned(),
+                        anchor: "world".to_owned(),
+                        index: "T-1".to_owned(),
+                        byteoffset: None,
+                    },
+                ]
+                );
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "sections".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "parse".to_owned()),
+                    ("pageid".to_owned(), "123".to_owned())
+                    ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn languages() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"languages\":[{\"*\":\"hello\", \"code\":\"world\"}, {\"*\":\"foo\", \"code\":\"bar\"}]}}".to_owned());
+        assert_eq!(
+            wikipedia.get_languages().await.unwrap(),
+            vec![
+                ("world".to_owned(), "hello".to_owned()),
+                ("bar".to_owned(), "foo".to_owned()),
+            ]
+        );
+        assert_eq!(*wikipedia.client.url.lock().unwrap(),
+                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                   vec![vec![
+                       ("meta".to_owned(), "siteinfo".to_owned()),
+                       ("siprop".to_owned(), "languages".to_owned()),
+                       ("format".to_owned(), "json".to_owned()),
+                       ("action".to_owned(), "query".to_owned())
+                   ]]);
+    }
 
-        wikipedia.set_base_url("https://hello.{language}.world/");
-        assert_eq!(wikipedia.base_url(), "https://hello.es.world/");
+    #[wasm_bindgen_test]
+    async fn autocomplete() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"2\":{\"pageid\":2,\"ns\":0,\"title\":\"world\",\"index\":2,\"fullurl\":\"https://en.wikipedia.org/wiki/World\"},\"1\":{\"pageid\":1,\"ns\":0,\"title\":\"hello\",\"index\":1,\"description\":\"greeting\",\"fullurl\":\"https://en.wikipedia.org/wiki/Hello\"}}}}".to_owned());
+        assert_eq!(
+                wikipedia.autocomplete_in("hel", 5, &[0, 14]).await.unwrap(),
+                vec![
+                super::Suggestion {
+                    title: "hello".to_owned(),
+                    ns: 0,
+                    description: Some("greeting".to_owned()),
+                    url: "https://en.wikipedia.org/wiki/Hello".to_owned(),
+                },
+                super::Suggestion {
+                    title: "world".to_owned(),
+                    ns: 0,
+                    description: None,
+                    url: "https://en.wikipedia.org/wiki/World".to_owned(),
+                },
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("generator".to_owned(), "prefixsearch".to_owned()),
+                    ("gpssearch".to_owned(), "hel".to_owned()),
+                    ("gpslimit".to_owned(), "5".to_owned()),
+                    ("gpsnamespace".to_owned(), "0|14".to_owned()),
+                    ("prop".to_owned(), "description|info".to_owned()),
+                    ("inprop".to_owned(), "url".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned())
+                    ]]);
+    }
 
-        wikipedia.set_base_url("https://hello.world/");
-        assert_eq!(wikipedia.base_url(), "https://hello.world/");
+    #[wasm_bindgen_test]
+    async fn autocomplete_no_results() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"batchcomplete\":\"\"}".to_owned());
+        assert!(wikipedia.autocomplete("zzzz", 5).await.unwrap().is_empty());
+        assert!(wikipedia.autocomplete("zzzz", 0).await.is_err());
     }
 
     #[wasm_bindgen_test]
-    async fn user_agent() {
-        let mut wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{}".to_owned());
-        wikipedia.search("hello world").await.unwrap_err();
-        assert_eq!(&*wikipedia.client.user_agent.unwrap(), "wikipedia (https://github.com/seppo0010/wikipedia-rs)");
+    async fn autocomplete_latest() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let session = super::AutocompleteSession::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"title\":\"hello\",\"index\":1}}}}".to_owned());
+        assert_eq!(wikipedia.autocomplete_latest(&session, "hel", 5).await.unwrap().unwrap().len(), 1);
 
-        let mut client = MockClient::default();
-        client.user_agent("hello world".to_owned());
-        client.response.lock().unwrap().push("{}".to_owned());
-        wikipedia.client = client;
-        wikipedia.search("hello world").await.unwrap_err();
-        assert_eq!(&*wikipedia.client.user_agent.unwrap(), "hello world");
+        let stale = session.begin();
+        let latest = session.begin();
+        assert!(!session.is_current(stale));
+        assert!(session.is_current(latest));
+    }
+
+    #[wasm_bindgen_test]
+    async fn category_members() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"cmcontinue\":\"page|1\"},\"query\":{\"categorymembers\":[{\"pageid\":1,\"ns\":0,\"title\":\"Hello\",\"type\":\"page\",\"sortkeyprefix\":\"\",\"timestamp\":\"2020-01-01T00:00:00Z\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"categorymembers\":[{\"pageid\":2,\"ns\":14,\"title\":\"Category:World\",\"type\":\"subcat\",\"sortkeyprefix\":\"W\"}]}}".to_owned());
+        let query = iter::CategoryMemberQuery {
+            types: vec![iter::CategoryMemberType::Page, iter::CategoryMemberType::Subcat],
+            sort: iter::CategoryMemberSort::Timestamp,
+            descending: true,
+            ..Default::default()
+        };
+        assert_eq!(
+                wikipedia.category_members("Category:Greetings".to_owned(), query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![
+                iter::CategoryMember {
+                    pageid: 1,
+                    ns: 0,
+                    title: "Hello".to_owned(),
+                    member_type: iter::CategoryMemberType::Page,
+                    sortkey_prefix: "".to_owned(),
+                    timestamp: Some("2020-01-01T00:00:00Z".to_owned()),
+                },
+                iter::CategoryMember {
+                    pageid: 2,
+                    ns: 14,
+                    title: "Category:World".to_owned(),
+                    member_type: iter::CategoryMemberType::Subcat,
+                    sortkey_prefix: "W".to_owned(),
+                    timestamp: None,
+                }
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![
+                vec![
+                    ("list".to_owned(), "categorymembers".to_owned()),
+                    ("cmtitle".to_owned(), "Category:Greetings".to_owned()),
+                    ("cmprop".to_owned(), "ids|title|type|timestamp|sortkeyprefix".to_owned()),
+                    ("cmlimit".to_owned(), "max".to_owned()),
+                    ("cmsort".to_owned(), "timestamp".to_owned()),
+                    ("cmdir".to_owned(), "desc".to_owned()),
+                    ("cmtype".to_owned(), "page|subcat".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                ],
+                vec![
+                    ("list".to_owned(), "categorymembers".to_owned()),
+                    ("cmtitle".to_owned(), "Category:Greetings".to_owned()),
+                    ("cmprop".to_owned(), "ids|title|type|timestamp|sortkeyprefix".to_owned()),
+                    ("cmlimit".to_owned(), "max".to_owned()),
+                    ("cmsort".to_owned(), "timestamp".to_owned()),
+                    ("cmdir".to_owned(), "desc".to_owned()),
+                    ("cmtype".to_owned(), "page|subcat".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("cmcontinue".to_owned(), "page|1".to_owned()),
+                ]
+                ]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn category_members_recursive() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"categorymembers\":[{\"ns\":0,\"title\":\"Hello\",\"type\":\"page\"},{\"ns\":14,\"title\":\"Category:B\",\"type\":\"subcat\"}]}}".to_owned());
+        // Category:B links back to Category:A, which must not be visited again
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"categorymembers\":[{\"ns\":0,\"title\":\"Hello\",\"type\":\"page\"},{\"ns\":0,\"title\":\"World\",\"type\":\"page\"},{\"ns\":14,\"title\":\"Category:A\",\"type\":\"subcat\"}]}}".to_owned());
+        let query = iter::CategoryMemberQuery {
+            types: vec![iter::CategoryMemberType::Page],
+            ..Default::default()
+        };
+        let members = wikipedia.category_members_recursive("Category:A".to_owned(), query, 5).await.unwrap();
+        assert_eq!(
+                members.into_iter().map(|x| x.title).collect::<Vec<_>>(),
+                vec!["Hello".to_owned(), "World".to_owned()]);
+        let arguments = wikipedia.client.arguments.lock().unwrap();
+        assert_eq!(arguments.len(), 2);
+        assert!(arguments[0].contains(&("cmtype".to_owned(), "page|subcat".to_owned())));
+        assert!(arguments[1].contains(&("cmtitle".to_owned(), "Category:B".to_owned())));
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_categories_exclude_hidden() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"categories\":[{\"ns\":14,\"title\":\"Category:World\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::CategoryQuery { exclude_hidden: true };
+        let categories = page.get_categories_with(query).await.unwrap().collect_vec::<Vec<_>>().await;
+        assert_eq!(categories.len(), 1);
+        assert_eq!(categories[0].title, "World".to_owned());
+        assert!(wikipedia.client.arguments.lock().unwrap()[0].contains(&("clshow".to_owned(), "!hidden".to_owned())));
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_backlinks() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"blcontinue\":\"0|2\"},\"query\":{\"backlinks\":[{\"pageid\":1,\"ns\":0,\"title\":\"Hello\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"backlinks\":[{\"pageid\":2,\"ns\":0,\"title\":\"Hi\",\"redirect\":\"\"}]}}".to_owned());
+        let page = wikipedia.page_from_pageid("4138548".to_owned());
+        let query = iter::LinkingPageQuery { namespaces: vec![0], ..Default::default() };
+        assert_eq!(
+                page.get_backlinks(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![
+                iter::Backlink { pageid: 1, ns: 0, title: "Hello".to_owned(), redirect: false },
+                iter::Backlink { pageid: 2, ns: 0, title: "Hi".to_owned(), redirect: true },
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![
+                vec![
+                    ("list".to_owned(), "backlinks".to_owned()),
+                    ("blpageid".to_owned(), "4138548".to_owned()),
+                    ("bllimit".to_owned(), "max".to_owned()),
+                    ("blfilterredir".to_owned(), "all".to_owned()),
+                    ("blnamespace".to_owned(), "0".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                ],
+                vec![
+                    ("list".to_owned(), "backlinks".to_owned()),
+                    ("blpageid".to_owned(), "4138548".to_owned()),
+                    ("bllimit".to_owned(), "max".to_owned()),
+                    ("blfilterredir".to_owned(), "all".to_owned()),
+                    ("blnamespace".to_owned(), "0".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("blcontinue".to_owned(), "0|2".to_owned()),
+                ]
+                ]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_linkshere() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"linkshere\":[{\"pageid\":2,\"ns\":0,\"title\":\"Hi\",\"redirect\":\"\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Hello".to_owned());
+        let query = iter::LinkingPageQuery { namespaces: vec![0, 4], redirects: iter::RedirectFilter::Redirects };
+        assert_eq!(
+                page.get_linkshere(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::LinkHere { pageid: 2, ns: 0, title: "Hi".to_owned(), redirect: true }]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "linkshere".to_owned()),
+                    ("lhprop".to_owned(), "pageid|title|redirect".to_owned()),
+                    ("lhlimit".to_owned(), "max".to_owned()),
+                    ("lhnamespace".to_owned(), "0|4".to_owned()),
+                    ("lhshow".to_owned(), "redirect".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "Hello".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                    ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_transcluded_in_and_file_usage() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"transcludedin\":[{\"pageid\":1,\"ns\":0,\"title\":\"Hello\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"fileusage\":[{\"pageid\":3,\"ns\":0,\"title\":\"World\"}]}}}}".to_owned());
+        let template = wikipedia.page_from_title("Template:Hello".to_owned());
+        let query = iter::LinkingPageQuery { redirects: iter::RedirectFilter::NonRedirects, ..Default::default() };
+        assert_eq!(
+                template.get_transcluded_in(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::TranscludedIn { pageid: 1, ns: 0, title: "Hello".to_owned(), redirect: false }]);
+        let file = wikipedia.page_from_title("File:World.png".to_owned());
+        assert_eq!(
+                file.get_file_usage(Default::default()).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::FileUsage { pageid: 3, ns: 0, title: "World".to_owned(), redirect: false }]);
+        let arguments = wikipedia.client.arguments.lock().unwrap();
+        assert!(arguments[0].contains(&("tishow".to_owned(), "!redirect".to_owned())));
+        assert!(arguments[1].contains(&("prop".to_owned(), "fileusage".to_owned())));
+        assert!(arguments[1].contains(&("fulimit".to_owned(), "max".to_owned())));
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_templates() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"templates\":[{\"ns\":10,\"title\":\"Template:Infobox country\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"templates\":[{\"ns\":828,\"title\":\"Module:Infobox\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::TemplateQuery { namespaces: vec![10, 828] };
+        assert_eq!(
+                page.get_templates_with(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![
+                iter::Template {
+                    title: "Template:Infobox country".to_owned(),
+                    ns: 10,
+                },
+                iter::Template {
+                    title: "Module:Infobox".to_owned(),
+                    ns: 828,
+                }
+                ]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![
+                vec![
+                    ("prop".to_owned(), "templates".to_owned()),
+                    ("tllimit".to_owned(), "max".to_owned()),
+                    ("tlnamespace".to_owned(), "10|828".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                ],
+                vec![
+                    ("prop".to_owned(), "templates".to_owned()),
+                    ("tllimit".to_owned(), "max".to_owned()),
+                    ("tlnamespace".to_owned(), "10|828".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("lol".to_owned(), "1".to_owned()),
+                ]
+                ]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_links_with() {
+        let wikipedia = Wikipedia {
+            links_results: "20".to_owned(),
+            ..Wikipedia::<MockClient>::default()
+        };
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"ns\":4,\"title\":\"Wikipedia:Hello\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::LinkQuery {
+            namespaces: Vec::new(),
+            titles: vec!["Wikipedia:Hello".to_owned(), "Hello".to_owned()],
+        };
+        assert_eq!(
+                page.get_links_with(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::Link { title: "Wikipedia:Hello".to_owned(), ns: 4, exists: true }]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("generator".to_owned(), "links".to_owned()),
+                    ("gpllimit".to_owned(), "20".to_owned()),
+                    ("prop".to_owned(), "info".to_owned()),
+                    ("gpltitles".to_owned(), "Wikipedia:Hello|Hello".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("continue".to_owned(), "".to_owned()),
+                    ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_references_with() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"url\": \"https://www.example.com/\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::ReferenceQuery {
+            protocol: Some("https".to_owned()),
+            domain: Some("*.example.com".to_owned()),
+        };
+        assert_eq!(
+                page.get_references_with(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::Reference {
+                    url: "https://www.example.com/".to_owned(),
+                    protocol: "https".to_owned(),
+                    domain: "www.example.com".to_owned(),
+                }]);
+        let arguments = wikipedia.client.arguments.lock().unwrap();
+        assert!(arguments[0].contains(&("elprotocol".to_owned(), "https".to_owned())));
+        assert!(arguments[0].contains(&("elquery".to_owned(), "*.example.com".to_owned())));
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_references_protocol() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"url\": \"https://www.example.com/\"},{\"url\": \"mailto:someone@example.com\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::ReferenceQuery {
+            protocol: Some("mailto".to_owned()),
+            domain: None,
+        };
+        assert_eq!(
+                page.get_references_with(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::Reference {
+                    url: "mailto:someone@example.com".to_owned(),
+                    protocol: "mailto".to_owned(),
+                    domain: "example.com".to_owned(),
+                }]);
+        let arguments = wikipedia.client.arguments.lock().unwrap();
+        assert!(!arguments[0].iter().any(|x| x.0 == "elprotocol"));
+    }
+
+    #[wasm_bindgen_test]
+    async fn external_url_usage() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"euoffset\":1,\"continue\":\"-||\"},\"query\":{\"exturlusage\":[{\"pageid\":1,\"ns\":0,\"title\":\"Hello\",\"url\":\"https://example.com/a\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"exturlusage\":[{\"pageid\":2,\"ns\":0,\"title\":\"World\",\"url\":\"http://example.com/b\"}]}}".to_owned());
+        let query = iter::ReferenceQuery {
+            domain: Some("example.com".to_owned()),
+            ..Default::default()
+        };
+        let usages = wikipedia.external_url_usage(query, 10).await.unwrap();
+        assert_eq!(
+                usages.iter().map(|x| (&*x.title, &*x.reference.url)).collect::<Vec<_>>(),
+                vec![("Hello", "https://example.com/a"), ("World", "http://example.com/b")]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("list".to_owned(), "exturlusage".to_owned()),
+                    ("euprop".to_owned(), "ids|title|url".to_owned()),
+                    ("eulimit".to_owned(), "10".to_owned()),
+                    ("euexpandurl".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("euquery".to_owned(), "example.com".to_owned()),
+                    ("continue".to_owned(), "".to_owned())
+                ],
+                vec![
+                    ("list".to_owned(), "exturlusage".to_owned()),
+                    ("euprop".to_owned(), "ids|title|url".to_owned()),
+                    ("eulimit".to_owned(), "9".to_owned()),
+                    ("euexpandurl".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("euquery".to_owned(), "example.com".to_owned()),
+                    ("continue".to_owned(), "-||".to_owned()),
+                    ("euoffset".to_owned(), "1".to_owned())
+                ]
+                ]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_citations() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"revisions\":[{\"revid\":1,\"parentid\":0,\"slots\":{\"main\":{\"contentmodel\":\"wikitext\",\"*\":\"Hello.<ref>{{cite web|url=https://example.com|title=World}}</ref>\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let citations = page.get_citations().await.unwrap();
+        assert_eq!(citations.len(), 1);
+        assert_eq!(citations[0].title, Some("World".to_owned()));
+        assert_eq!(citations[0].url, Some("https://example.com".to_owned()));
+        assert_eq!(citations[0].anchor, Some("cite_note-1".to_owned()));
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "revisions".to_owned()),
+                    ("rvprop".to_owned(), "ids|content".to_owned()),
+                    ("rvslots".to_owned(), "main".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned())
+                    ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_images_with() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"title\":\"File:Hello.jpg\", \"imageinfo\":[{\"url\": \"http://example.com/hello.jpg\", \"descriptionurl\": \"http://example.com/hello.jpg.html\", \"width\": 800, \"height\": 600, \"size\": 12345, \"mime\": \"image/jpeg\", \"sha1\": \"abc\", \"timestamp\": \"2020-01-01T00:00:00Z\", \"user\": \"Someone\", \"thumburl\": \"http://example.com/200px-hello.jpg\", \"thumbwidth\": 200, \"thumbheight\": 150, \"extmetadata\": {\"LicenseShortName\": {\"value\": \"CC BY-SA 4.0\", \"source\": \"commons-desc-page\"}, \"Artist\": {\"value\": \"<a href=\\\"//example.com\\\">Someone</a>\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        let query = iter::ImageQuery {
+            details: true,
+            license: true,
+            thumb_width: Some(200),
+            thumb_height: None,
+        };
+        assert_eq!(
+                page.get_images_with(query).await.unwrap().collect_vec::<Vec<_>>().await,
+                vec![iter::Image {
+                    url: "http://example.com/hello.jpg".to_owned(),
+                    title: "File:Hello.jpg".to_owned(),
+                    description_url: "http://example.com/hello.jpg.html".to_owned(),
+                    width: Some(800),
+                    height: Some(600),
+                    size: Some(12345),
+                    mime: Some("image/jpeg".to_owned()),
+                    sha1: Some("abc".to_owned()),
+                    timestamp: Some("2020-01-01T00:00:00Z".to_owned()),
+                    uploader: Some("Someone".to_owned()),
+                    license: Some(iter::ImageLicense {
+                        short_name: Some("CC BY-SA 4.0".to_owned()),
+                        artist: Some("<a href=\"//example.com\">Someone</a>".to_owned()),
+                        attribution: None,
+                        credit: None,
+                    }),
+                    thumbnail: Some(iter::Thumbnail {
+                        url: "http://example.com/200px-hello.jpg".to_owned(),
+                        width: 200,
+                        height: 150,
+                    }),
+                }]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("generator".to_owned(), "images".to_owned()),
+                    ("gimlimit".to_owned(), "max".to_owned()),
+                    ("prop".to_owned(), "imageinfo".to_owned()),
+                    ("iiprop".to_owned(), "url|size|mime|sha1|timestamp|user|extmetadata".to_owned()),
+                    ("iiextmetadatafilter".to_owned(), "LicenseShortName|Artist|Attribution|Credit".to_owned()),
+                    ("iiurlwidth".to_owned(), "200".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned()),
+                    ("continue".to_owned(), "".to_owned())
+                    ]]);
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn thumbnail() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\",\"thumbnail\":{\"source\":\"http://example.com/320px-Earth.jpg\",\"width\":320,\"height\":320},\"pageimage\":\"Earth.jpg\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(
+                page.get_thumbnail(320).await.unwrap(),
+                Some(super::PageImage {
+                    file: "Earth.jpg".to_owned(),
+                    url: "http://example.com/320px-Earth.jpg".to_owned(),
+                    width: 320,
+                    height: 320,
+                }));
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "pageimages".to_owned()),
+                    ("piprop".to_owned(), "thumbnail|name".to_owned()),
+                    ("pithumbsize".to_owned(), "320".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned())
+                    ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn original_image_missing() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(page.get_original_image().await.unwrap(), None);
     }
 
     #[wasm_bindgen_test]
-    async fn search() {
+    async fn page_images_batch() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"search\":[{\"title\":\"hello\"}, {\"title\":\"world\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"normalized\":[{\"from\":\"earth\",\"to\":\"Earth\"}],\"redirects\":[{\"from\":\"Earth\",\"to\":\"Planet Earth\"}],\"pages\":{\"1\":{\"pageid\":1,\"title\":\"Planet Earth\",\"original\":{\"source\":\"http://example.com/Earth.jpg\",\"width\":1000,\"height\":800},\"pageimage\":\"Earth.jpg\"},\"2\":{\"pageid\":2,\"title\":\"Moon\"}}}}".to_owned());
+        let titles = vec!["earth".to_owned(), "Moon".to_owned()];
         assert_eq!(
-            wikipedia.search("hello world").await.unwrap(),
-            vec![
-                "hello".to_owned(),
-                "world".to_owned(),
-            ]);
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+                wikipedia.get_page_images(&titles, None).await.unwrap(),
+                vec![
+                    ("earth".to_owned(), Some(super::PageImage {
+                        file: "Earth.jpg".to_owned(),
+                        url: "http://example.com/Earth.jpg".to_owned(),
+                        width: 1000,
+                        height: 800,
+                    })),
+                    ("Moon".to_owned(), None),
+                ]);
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("list".to_owned(), "search".to_owned()),
-                       ("srprop".to_owned(), "".to_owned()),
-                       ("srlimit".to_owned(), "10".to_owned()),
-                       ("srsearch".to_owned(), "hello world".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned())
-                   ]]);
+                vec![vec![
+                    ("prop".to_owned(), "pageimages".to_owned()),
+                    ("piprop".to_owned(), "original|name".to_owned()),
+                    ("pilimit".to_owned(), "max".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "earth|Moon".to_owned())
+                    ]]);
     }
 
+
     #[wasm_bindgen_test]
-    async fn geosearch() {
+    async fn description() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"geosearch\":[{\"title\":\"hello\"}, {\"title\":\"world\"}]}}".to_owned());
-        assert_eq!(
-            wikipedia.geosearch(-34.603333, -58.381667, 10).await.unwrap(),
-            vec![
-                "hello".to_owned(),
-                "world".to_owned(),
-            ]);
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\",\"description\":\"Everything that exists\",\"descriptionsource\":\"local\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(page.get_description(super::DescriptionSource::Local).await.unwrap(), Some(super::Description {
+            text: "Everything that exists".to_owned(),
+            source: super::DescriptionSource::Local,
+        }));
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("list".to_owned(), "geosearch".to_owned()),
-                       ("gsradius".to_owned(), "10".to_owned()),
-                       ("gscoord".to_owned(), "-34.603333|-58.381667".to_owned()),
-                       ("gslimit".to_owned(), "10".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned())
-                   ]]);
+                vec![vec![
+                    ("prop".to_owned(), "description".to_owned()),
+                    ("descprefersource".to_owned(), "local".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned())
+                    ]]);
     }
 
     #[wasm_bindgen_test]
-    async fn random_count() {
+    async fn description_central() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"random\":[{\"title\":\"hello\"}, {\"title\":\"world\"}]}}".to_owned());
-        assert_eq!(
-            wikipedia.random_count(10).await.unwrap(),
-            vec![
-                "hello".to_owned(),
-                "world".to_owned(),
-            ]);
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
-        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("list".to_owned(), "random".to_owned()),
-                       ("rnnamespace".to_owned(), "0".to_owned()),
-                       ("rnlimit".to_owned(), "10".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned())
-                   ]]);
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\",\"description\":\"all of the Earth\",\"descriptionsource\":\"central\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(page.get_description(super::DescriptionSource::Central).await.unwrap(), Some(super::Description {
+            text: "all of the Earth".to_owned(),
+            source: super::DescriptionSource::Central,
+        }));
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[0][1],
+                ("descprefersource".to_owned(), "central".to_owned()));
     }
 
     #[wasm_bindgen_test]
-    async fn random() {
+    async fn wikidata_id() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"random\":[{\"title\":\"hello\"}, {\"title\":\"world\"}]}}".to_owned());
-        assert_eq!(
-            wikipedia.random().await.unwrap(),
-            Some("hello".to_owned())
-        );
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\",\"pageprops\":{\"wikibase_item\":\"Q16502\"}}}}}".to_owned());
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert_eq!(page.get_wikidata_id().await.unwrap(), Some("Q16502".to_owned()));
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("list".to_owned(), "random".to_owned()),
-                       ("rnnamespace".to_owned(), "0".to_owned()),
-                       ("rnlimit".to_owned(), "1".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned())
-                   ]]);
+                vec![vec![
+                    ("prop".to_owned(), "pageprops".to_owned()),
+                    ("ppprop".to_owned(), "wikibase_item".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned())
+                    ]]);
     }
 
+
     #[wasm_bindgen_test]
-    async fn page_content() {
+    async fn wikidata_entity() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extract\":\"hello\"}}}}".to_owned());
-        let page = wikipedia.page_from_pageid("4138548".to_owned());
-        let html = page.get_content().await.unwrap();
-        assert_eq!(
-            html,
-            "hello".to_owned()
-        );
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        let wikidata = wikipedia.wikidata();
+        wikipedia.client.response.lock().unwrap().push("{\"entities\":{\"Q42\":{\"id\":\"Q42\",\"labels\":{\"en\":{\"language\":\"en\",\"value\":\"Douglas Adams\"}}}}}".to_owned());
+        let entity = wikidata.get_entity("Q42").await.unwrap().unwrap();
+        assert_eq!(entity.id, "Q42");
+        assert_eq!(entity.label("en"), Some("Douglas Adams"));
+        assert_eq!(*wikipedia.client.url.lock().unwrap(), vec!["https://www.wikidata.org/w/api.php".to_owned()]);
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("prop".to_owned(), "extracts|revisions".to_owned()),
-                       ("explaintext".to_owned(), "".to_owned()),
-                       ("rvprop".to_owned(), "ids".to_owned()),
-                       ("redirects".to_owned(), "".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned()),
-                       ("pageids".to_owned(), "4138548".to_owned()),
-                   ]]);
+                vec![vec![
+                    ("action".to_owned(), "wbgetentities".to_owned()),
+                    ("ids".to_owned(), "Q42".to_owned()),
+                    ("props".to_owned(), "info|labels|descriptions|aliases|claims|datatype|sitelinks/urls".to_owned()),
+                    ("format".to_owned(), "json".to_owned())
+                    ]]);
+
+        wikipedia.client.response.lock().unwrap().push("{\"entities\":{\"Q0\":{\"id\":\"Q0\",\"missing\":\"\"}}}".to_owned());
+        assert_eq!(wikidata.get_entity("Q0").await.unwrap(), None);
     }
 
     #[wasm_bindgen_test]
-    async fn page_html_content() {
+    async fn wikidata_search_entities() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"revisions\":[{\"*\":\"hello\"}]}}}}".to_owned());
-        let page = wikipedia.page_from_pageid("4138548".to_owned());
-        let html = page.get_html_content().await.unwrap();
+        let wikidata = wikipedia.wikidata();
+        wikipedia.client.response.lock().unwrap().push("{\"search\":[{\"id\":\"Q42\",\"label\":\"Douglas Adams\",\"description\":\"English writer\",\"concepturi\":\"http://www.wikidata.org/entity/Q42\"}]}".to_owned());
         assert_eq!(
-            html,
-            "hello".to_owned()
-        );
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+                wikidata.search_entities("douglas adams").await.unwrap(),
+                vec![super::wikidata::EntitySearchResult {
+                    id: "Q42".to_owned(),
+                    label: Some("Douglas Adams".to_owned()),
+                    description: Some("English writer".to_owned()),
+                    url: Some("http://www.wikidata.org/entity/Q42".to_owned()),
+                }]);
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("prop".to_owned(), "revisions".to_owned()),
-                       ("rvprop".to_owned(), "content".to_owned()),
-                       ("rvlimit".to_owned(), "1".to_owned()),
-                       ("rvparse".to_owned(), "".to_owned()),
-                       ("redirects".to_owned(), "".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned()),
-                       ("pageids".to_owned(), "4138548".to_owned()),
-                   ]]);
+                vec![vec![
+                    ("action".to_owned(), "wbsearchentities".to_owned()),
+                    ("search".to_owned(), "douglas adams".to_owned()),
+                    ("language".to_owned(), "en".to_owned()),
+                    ("type".to_owned(), "item".to_owned()),
+                    ("limit".to_owned(), "10".to_owned()),
+                    ("format".to_owned(), "json".to_owned())
+                    ]]);
     }
 
     #[wasm_bindgen_test]
-    async fn page_summary() {
+    async fn wikidata_page_entity() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extract\":\"hello\"}}}}".to_owned());
-        let page = wikipedia.page_from_title("Parkinson's law of triviality".to_owned());
-        let summary = page.get_summary().await.unwrap();
-        assert_eq!(
-            summary,
-            "hello".to_owned()
-        );
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
-        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("prop".to_owned(), "extracts".to_owned()),
-                       ("explaintext".to_owned(), "".to_owned()),
-                       ("exintro".to_owned(), "".to_owned()),
-                       ("redirects".to_owned(), "".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned()),
-                       ("titles".to_owned(), "Parkinson\'s law of triviality".to_owned())
-                   ]]);
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"Douglas Adams\",\"pageprops\":{\"wikibase_item\":\"Q42\"}}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"entities\":{\"Q42\":{\"id\":\"Q42\"}}}".to_owned());
+        let wikidata = wikipedia.wikidata();
+        let page = wikipedia.page_from_title("Douglas Adams".to_owned());
+        assert_eq!(wikidata.get_page_entity(&page).await.unwrap().unwrap().id, "Q42");
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[1][1], ("ids".to_owned(), "Q42".to_owned()));
     }
 
+
     #[wasm_bindgen_test]
-    async fn page_redirect_summary() {
+    async fn sparql_query() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"redirects\":[{\"to\":\"hello world\"}]}}".to_owned());
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extract\":\"hello\"}}}}".to_owned());
-        let page = wikipedia.page_from_title("Parkinson's law of triviality".to_owned());
-        let summary = page.get_summary().await.unwrap();
+        let sparql = super::sparql::Sparql::<MockClient>::default();
+        sparql.client.response.lock().unwrap().push("{\"head\":{\"vars\":[\"article\"]},\"results\":{\"bindings\":[{\"article\":{\"type\":\"uri\",\"value\":\"https://en.wikipedia.org/wiki/Douglas_Adams\"}},{\"article\":{\"type\":\"uri\",\"value\":\"https://de.wikipedia.org/wiki/Douglas_Adams\"}}]}}".to_owned());
+        let query = "SELECT ?article WHERE { ?article schema:about wd:Q42 }";
+        let results = sparql.query(query).await.unwrap();
+        assert_eq!(*sparql.client.url.lock().unwrap(), vec!["https://query.wikidata.org/sparql".to_owned()]);
+        assert_eq!(*sparql.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("query".to_owned(), query.to_owned()),
+                    ("format".to_owned(), "json".to_owned())
+                    ]]);
+        let pages = results.bindings.iter().filter_map(|x| x["article"].to_page(&wikipedia)).collect::<Vec<_>>();
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0] == wikipedia.page_from_title("Douglas Adams".to_owned()));
+    }
+
+    #[wasm_bindgen_test]
+    fn page_from_url() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        assert!(
+                wikipedia.page_from_url("https://en.wikipedia.org/wiki/Caf%C3%A9_au_lait#History").unwrap()
+                == wikipedia.page_from_title("Café au lait".to_owned()));
+        assert!(wikipedia.page_from_url("http://en.wikipedia.org/wiki/World").unwrap() == wikipedia.page_from_title("World".to_owned()));
+        assert!(wikipedia.page_from_url("https://en.wikipedia.org/wiki/World?oldid=1#History").unwrap() == wikipedia.page_from_title("World".to_owned()));
+        assert!(wikipedia.page_from_url("https://es.wikipedia.org/wiki/World").is_none());
+        assert!(wikipedia.page_from_url("http://www.wikidata.org/entity/Q42").is_none());
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn all_coordinates() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"Route 66\",\"coordinates\":[{\"lat\":41.87,\"lon\":-87.62,\"globe\":\"earth\",\"type\":\"landmark\",\"name\":\"Chicago end\",\"dim\":\"1000\",\"country\":\"US\",\"region\":\"IL\"},{\"lat\":34.01,\"lon\":-118.49,\"primary\":\"\",\"globe\":\"earth\",\"dim\":10000}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"Route 66\",\"coordinates\":[{\"lat\":41.87,\"lon\":-87.62,\"globe\":\"earth\"},{\"lat\":34.01,\"lon\":-118.49,\"primary\":\"\",\"globe\":\"earth\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Route 66".to_owned());
         assert_eq!(
-            summary,
-            "hello".to_owned()
-        );
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec![
-                       "https://en.wikipedia.org/w/api.php".to_owned(),
-                       "https://en.wikipedia.org/w/api.php".to_owned(),
-                   ]);
-        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![
-                       vec![
-                           ("prop".to_owned(), "extracts".to_owned()),
-                           ("explaintext".to_owned(), "".to_owned()),
-                           ("exintro".to_owned(), "".to_owned()),
-                           ("redirects".to_owned(), "".to_owned()),
-                           ("format".to_owned(), "json".to_owned()),
-                           ("action".to_owned(), "query".to_owned()),
-                           ("titles".to_owned(), "Parkinson\'s law of triviality".to_owned())
-                       ],
-                       vec![
-                           ("prop".to_owned(), "extracts".to_owned()),
-                           ("explaintext".to_owned(), "".to_owned()),
-                           ("exintro".to_owned(), "".to_owned()),
-                           ("redirects".to_owned(), "".to_owned()),
-                           ("format".to_owned(), "json".to_owned()),
-                           ("action".to_owned(), "query".to_owned()),
-                           ("titles".to_owned(), "hello world".to_owned())
-                       ]
-                   ]
-        );
+                page.get_all_coordinates().await.unwrap(),
+                vec![
+                    super::Coordinate {
+                        lat: 41.87,
+                        lon: -87.62,
+                        primary: false,
+                        globe: "earth".to_owned(),
+                        dim: Some(1000),
+                        kind: Some("landmark".to_owned()),
+                        name: Some("Chicago end".to_owned()),
+                        country: Some("US".to_owned()),
+                        region: Some("IL".to_owned()),
+                    },
+                    super::Coordinate {
+                        lat: 34.01,
+                        lon: -118.49,
+                        primary: true,
+                        globe: "earth".to_owned(),
+                        dim: Some(10000),
+                        kind: None,
+                        name: None,
+                        country: None,
+                        region: None,
+                    },
+                ]);
+        assert_eq!(page.get_coordinates().await.unwrap(), Some((34.01, -118.49)));
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[0],
+                vec![
+                    ("prop".to_owned(), "coordinates".to_owned()),
+                    ("colimit".to_owned(), "max".to_owned()),
+                    ("coprop".to_owned(), "type|name|dim|country|region|globe".to_owned()),
+                    ("coprimary".to_owned(), "all".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "Route 66".to_owned())
+                    ]);
     }
 
+
     #[wasm_bindgen_test]
-    async fn page_images() {
+    async fn geosearch_with() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"title\":\"Image 1\", \"imageinfo\":[{\"url\": \"http://example.com/image1.jpg\", \"descriptionurl\": \"http://example.com/image1.jpg.html\"}]}}}}".to_owned());
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"title\":\"Image 2\", \"imageinfo\":[{\"url\": \"http://example.com/image2.jpg\", \"descriptionurl\": \"http://example.com/image2.jpg.html\"}]}}}}".to_owned());
-        let page = wikipedia.page_from_title("Parkinson's law of triviality".to_owned());
-        let images= page.get_images().await.unwrap().collect_vec::<Vec<_>>().await;
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"geosearch\":[{\"pageid\":1,\"ns\":0,\"title\":\"Obelisk\",\"lat\":-34.6037,\"lon\":-58.3816,\"dist\":12.5,\"primary\":\"\",\"type\":\"landmark\",\"dim\":\"100\",\"country\":\"AR\",\"region\":\"C\"}]}}".to_owned());
+        let query = super::GeoQuery {
+            page: Some("Buenos Aires".to_owned()),
+            radius: Some(500),
+            namespaces: vec![0, 6],
+            details: true,
+            primary: super::GeoPrimary::All,
+            limit: Some(5),
+            ..Default::default()
+        };
         assert_eq!(
-            images,
-            vec![
-                iter::Image {
-                    url: "http://example.com/image1.jpg".to_owned(),
-                    title: "Image 1".to_owned(),
-                    description_url: "http://example.com/image1.jpg.html".to_owned(),
-                },
-                iter::Image {
-                    url: "http://example.com/image2.jpg".to_owned(),
-                    title: "Image 2".to_owned(),
-                    description_url: "http://example.com/image2.jpg.html".to_owned(),
-                }
-            ]);
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec![
-                       "https://en.wikipedia.org/w/api.php".to_owned(),
-                       "https://en.wikipedia.org/w/api.php".to_owned(),
-                   ]);
-        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![
-                       vec![
-                           ("generator".to_owned(), "images".to_owned()),
-                           ("gimlimit".to_owned(), "max".to_owned()),
-                           ("prop".to_owned(), "imageinfo".to_owned()),
-                           ("iiprop".to_owned(), "url".to_owned()),
-                           ("format".to_owned(), "json".to_owned()),
-                           ("action".to_owned(), "query".to_owned()),
-                           ("titles".to_owned(), "Parkinson\'s law of triviality".to_owned()),
-                           ("continue".to_owned(), "".to_owned())
-                       ],
-                       vec![
-                           ("generator".to_owned(), "images".to_owned()),
-                           ("gimlimit".to_owned(), "max".to_owned()),
-                           ("prop".to_owned(), "imageinfo".to_owned()),
-                           ("iiprop".to_owned(), "url".to_owned()),
-                           ("format".to_owned(), "json".to_owned()),
-                           ("action".to_owned(), "query".to_owned()),
-                           ("titles".to_owned(), "Parkinson\'s law of triviality".to_owned()),
-                           ("lol".to_owned(), "1".to_owned())
-                       ]
-                   ]
-        );
+                wikipedia.geosearch_with(query).await.unwrap(),
+                vec![super::GeoHit {
+                    title: "Obelisk".to_owned(),
+                    pageid: 1,
+                    ns: 0,
+                    lat: -34.6037,
+                    lon: -58.3816,
+                    dist: Some(12.5),
+                    primary: true,
+                    kind: Some("landmark".to_owned()),
+                    dim: Some(100),
+                    country: Some("AR".to_owned()),
+                    region: Some("C".to_owned()),
+                }]);
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("list".to_owned(), "geosearch".to_owned()),
+                    ("gspage".to_owned(), "Buenos Aires".to_owned()),
+                    ("gsradius".to_owned(), "500".to_owned()),
+                    ("gsnamespace".to_owned(), "0|6".to_owned()),
+                    ("gsprop".to_owned(), "type|dim|country|region".to_owned()),
+                    ("gsprimary".to_owned(), "all".to_owned()),
+                    ("gslimit".to_owned(), "5".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned())
+                    ]]);
     }
 
     #[wasm_bindgen_test]
-    async fn page_coordinates() {
+    async fn geosearch_with_bbox() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"coordinates\":[{\"lat\": 2.1, \"lon\":-1.3}]}}}}".to_owned());
-        let page = wikipedia.page_from_title("World".to_owned());
-        let coordinates = page.get_coordinates().await.unwrap().unwrap();
-        assert_eq!(
-            coordinates,
-            (2.1, -1.3)
-        );
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"geosearch\":[]}}".to_owned());
+        let query = super::GeoQuery {
+            bbox: Some((10.0, 20.0, 0.0, 30.0)),
+            globe: Some("moon".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(wikipedia.geosearch_with(query).await.unwrap(), vec![]);
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("prop".to_owned(), "coordinates".to_owned()),
-                       ("colimit".to_owned(), "max".to_owned()),
-                       ("redirects".to_owned(), "".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned()),
-                       ("titles".to_owned(), "World".to_owned())
-                   ]]);
+                vec![vec![
+                    ("list".to_owned(), "geosearch".to_owned()),
+                    ("gsbbox".to_owned(), "10|20|0|30".to_owned()),
+                    ("gsglobe".to_owned(), "moon".to_owned()),
+                    ("gsprimary".to_owned(), "primary".to_owned()),
+                    ("gslimit".to_owned(), "10".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned())
+                    ]]);
+        assert!(wikipedia.geosearch_with(super::GeoQuery::default()).await.is_err());
+        let query = super::GeoQuery {
+            coordinates: Some((0.0, 0.0)),
+            radius: Some(20000),
+            ..Default::default()
+        };
+        assert!(wikipedia.geosearch_with(query).await.is_err());
     }
 
+
     #[wasm_bindgen_test]
-    async fn page_no_coordinates() {
+    async fn section_tree() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{}}}}".to_owned());
-        let page = wikipedia.page_from_title("World".to_owned());
-        assert!(page.get_coordinates().await.unwrap().is_none());
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
-        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("prop".to_owned(), "coordinates".to_owned()),
-                       ("colimit".to_owned(), "max".to_owned()),
-                       ("redirects".to_owned(), "".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned()),
-                       ("titles".to_owned(), "World".to_owned())
-                   ]]);
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"sections\":[{\"toclevel\":1,\"line\":\"A\",\"number\":\"1\"},{\"toclevel\":2,\"line\":\"A1\",\"number\":\"1.1\"},{\"toclevel\":3,\"line\":\"A1a\",\"number\":\"1.1.1\"},{\"toclevel\":2,\"line\":\"A2\",\"number\":\"1.2\"},{\"toclevel\":1,\"line\":\"B\",\"number\":\"2\"}]}}".to_owned());
+        let page = wikipedia.page_from_pageid("123".to_owned());
+        let tree = page.get_section_tree().await.unwrap();
+        let lines = |nodes: &Vec<super::SectionNode>| nodes.iter().map(|x| x.section.line.clone()).collect::<Vec<_>>();
+        assert_eq!(lines(&tree), vec!["A", "B"]);
+        assert_eq!(lines(&tree[0].children), vec!["A1", "A2"]);
+        assert_eq!(lines(&tree[0].children[0].children), vec!["A1a"]);
+        assert!(tree[0].children[1].children.is_empty());
+        assert!(tree[1].children.is_empty());
     }
 
+
     #[wasm_bindgen_test]
-    async fn get_references() {
+    async fn section_content_plaintext() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"*\": \"//example.com/reference1.html\"}]}}}}".to_owned());
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extlinks\":[{\"*\": \"//example.com/reference2.html\"}]}}}}".to_owned());
-        let page = wikipedia.page_from_title("World".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"sections\":[{\"toclevel\":1,\"level\":\"2\",\"line\":\"History\",\"index\":\"1\",\"anchor\":\"History\"},{\"toclevel\":2,\"level\":\"3\",\"line\":\"Early\",\"index\":\"2\",\"anchor\":\"Early\"},{\"toclevel\":1,\"level\":\"2\",\"line\":\"Places\",\"index\":\"3\",\"anchor\":\"Places\"},{\"toclevel\":2,\"level\":\"3\",\"line\":\"Early\",\"index\":\"4\",\"anchor\":\"Early_2\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"123\":{\"extract\":\"Intro.\\n\\n== History ==\\nOld times.\\n\\n=== Early ===\\nVery old.\\n\\n== Places ==\\nSome places.\\n\\n=== Early ===\\nFirst places.\\n\"}}}}".to_owned());
+        let page = wikipedia.page_from_pageid("123".to_owned());
         assert_eq!(
-            page.get_references().await.unwrap().collect_vec::<Vec<_>>().await,
-            vec![
-                iter::Reference {
-                    url: "http://example.com/reference1.html".to_owned(),
-                },
-                iter::Reference {
-                    url: "http://example.com/reference2.html".to_owned(),
-                }
-            ]);
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec![
-                       "https://en.wikipedia.org/w/api.php".to_owned(),
-                       "https://en.wikipedia.org/w/api.php".to_owned(),
-                   ]);
-        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("prop".to_owned(), "extlinks".to_owned()),
-                       ("ellimit".to_owned(), "max".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned()),
-                       ("titles".to_owned(), "World".to_owned()),
-                       ("continue".to_owned(), "".to_owned())
-                   ],
-                        vec![
-                            ("prop".to_owned(), "extlinks".to_owned()),
-                            ("ellimit".to_owned(), "max".to_owned()),
-                            ("format".to_owned(), "json".to_owned()),
-                            ("action".to_owned(), "query".to_owned()),
-                            ("titles".to_owned(), "World".to_owned()),
-                            ("lol".to_owned(), "1".to_owned())
-                        ]
-                   ]);
+                page.get_section_content("History").await.unwrap(),
+                Some("Old times.\n\n=== Early ===\nVery old.".to_owned()));
+
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"sections\":[{\"toclevel\":1,\"level\":\"2\",\"line\":\"History\",\"index\":\"1\",\"anchor\":\"History\"},{\"toclevel\":2,\"level\":\"3\",\"line\":\"Early\",\"index\":\"2\",\"anchor\":\"Early\"},{\"toclevel\":1,\"level\":\"2\",\"line\":\"Places\",\"index\":\"3\",\"anchor\":\"Places\"},{\"toclevel\":2,\"level\":\"3\",\"line\":\"Early\",\"index\":\"4\",\"anchor\":\"Early_2\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"123\":{\"extract\":\"Intro.\\n\\n== History ==\\nOld times.\\n\\n=== Early ===\\nVery old.\\n\\n== Places ==\\nSome places.\\n\\n=== Early ===\\nFirst places.\\n\"}}}}".to_owned());
+        assert_eq!(
+                page.get_section_by_anchor("Early 2", super::SectionQuery::default()).await.unwrap(),
+                Some("First places.".to_owned()));
     }
 
     #[wasm_bindgen_test]
-    async fn get_links() {
+    async fn section_content_wikitext_and_html() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let sections = "{\"parse\":{\"sections\":[{\"toclevel\":1,\"level\":\"2\",\"line\":\"History\",\"index\":\"1\",\"anchor\":\"History\"},{\"toclevel\":2,\"level\":\"3\",\"line\":\"Early\",\"index\":\"2\",\"anchor\":\"Early\"}]}}";
+        wikipedia.client.response.lock().unwrap().push(sections.to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"123\":{\"pageid\":123,\"revisions\":[{\"revid\":1,\"slots\":{\"main\":{\"contentmodel\":\"wikitext\",\"*\":\"== History ==\\nOld '''times'''.\\n\\n=== Early ===\\nVery old.\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_pageid("123".to_owned());
+        let query = super::SectionQuery {
+            format: super::SectionFormat::Wikitext,
+            include_subsections: false,
+        };
+        assert_eq!(
+                page.get_section_by_index("1", query).await.unwrap(),
+                Some("Old '''times'''.".to_owned()));
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[1],
+                vec![
+                    ("prop".to_owned(), "revisions".to_owned()),
+                    ("rvprop".to_owned(), "ids|content".to_owned()),
+                    ("rvslots".to_owned(), "main".to_owned()),
+                    ("rvsection".to_owned(), "1".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("pageids".to_owned(), "123".to_owned())
+                    ]);
+
+        wikipedia.client.response.lock().unwrap().push(sections.to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"text\":{\"*\":\"<div class=\\\"mw-parser-output\\\"><div class=\\\"mw-heading mw-heading2\\\"><h2 id=\\\"History\\\">History</h2></div>\\n<p>Old times.</p>\\n<div class=\\\"mw-heading mw-heading3\\\"><h3 id=\\\"Early\\\">Early</h3></div>\\n<p>Very old.</p></div>\"}}}".to_owned());
+        let query = super::SectionQuery {
+            format: super::SectionFormat::Html,
+            include_subsections: false,
+        };
+        assert_eq!(
+                page.get_section_by_anchor("History", query).await.unwrap(),
+                Some("<p>Old times.</p>".to_owned()));
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[3],
+                vec![
+                    ("prop".to_owned(), "text".to_owned()),
+                    ("section".to_owned(), "1".to_owned()),
+                    ("disableeditsection".to_owned(), "".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "parse".to_owned()),
+                    ("pageid".to_owned(), "123".to_owned())
+                    ]);
+
+        wikipedia.client.response.lock().unwrap().push(sections.to_owned());
+        assert_eq!(page.get_section_by_anchor("Missing", super::SectionQuery::default()).await.unwrap(), None);
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn wikitext() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"links\":[{\"title\": \"Hello\"}]}}}}".to_owned());
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"links\":[{\"title\": \"World\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"1\":{\"pageid\":1,\"title\":\"World\",\"revisions\":[{\"revid\":42,\"parentid\":41,\"slots\":{\"main\":{\"contentmodel\":\"wikitext\",\"contentformat\":\"text/x-wiki\",\"*\":\"== History ==\\nOld.\"}}}]}}}}".to_owned());
         let page = wikipedia.page_from_title("World".to_owned());
         assert_eq!(
-            page.get_links().await.unwrap().collect_vec::<Vec<_>>().await,
-            vec![
-                iter::Link {
-                    title: "Hello".to_owned(),
-                },
-                iter::Link {
-                    title: "World".to_owned(),
-                }
-            ]);
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec![
-                       "https://en.wikipedia.org/w/api.php".to_owned(),
-                       "https://en.wikipedia.org/w/api.php".to_owned(),
-                   ]);
+                page.get_section_wikitext("1").await.unwrap(),
+                super::Wikitext {
+                    content: "== History ==\nOld.".to_owned(),
+                    revid: 42,
+                    content_model: "wikitext".to_owned(),
+                });
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![
-                       vec![
-                           ("prop".to_owned(), "links".to_owned()),
-                           ("plnamespace".to_owned(), "0".to_owned()),
-                           ("ellimit".to_owned(), "max".to_owned()),
-                           ("format".to_owned(), "json".to_owned()),
-                           ("action".to_owned(), "query".to_owned()),
-                           ("titles".to_owned(), "World".to_owned()),
-                           ("continue".to_owned(), "".to_owned()),
-                       ],
-                       vec![
-                           ("prop".to_owned(), "links".to_owned()),
-                           ("plnamespace".to_owned(), "0".to_owned()),
-                           ("ellimit".to_owned(), "max".to_owned()),
-                           ("format".to_owned(), "json".to_owned()),
-                           ("action".to_owned(), "query".to_owned()),
-                           ("titles".to_owned(), "World".to_owned()),
-                           ("lol".to_owned(), "1".to_owned()),
-                       ]
-                   ]);
+                vec![vec![
+                    ("prop".to_owned(), "revisions".to_owned()),
+                    ("rvprop".to_owned(), "ids|content".to_owned()),
+                    ("rvslots".to_owned(), "main".to_owned()),
+                    ("rvsection".to_owned(), "1".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "World".to_owned())
+                    ]]);
     }
 
+
     #[wasm_bindgen_test]
-    async fn get_categories() {
+    async fn parse() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"lol\":\"1\"},\"query\":{\"pages\":{\"a\":{\"categories\":[{\"title\": \"Hello\"}]}}}}".to_owned());
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"categories\":[{\"title\": \"Category: World\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"title\":\"World\",\"pageid\":1,\"revid\":42,\"text\":{\"*\":\"<p>Hi</p>\"},\"links\":[{\"ns\":0,\"exists\":\"\",\"*\":\"Earth\"},{\"ns\":0,\"*\":\"Missing\"}],\"templates\":[{\"ns\":10,\"exists\":\"\",\"*\":\"Template:Infobox\"}],\"images\":[\"Earth_view.jpg\"],\"externallinks\":[\"https://example.com\"],\"categories\":[{\"sortkey\":\"\",\"hidden\":\"\",\"*\":\"Articles_with_short_description\"},{\"sortkey\":\"W\",\"*\":\"Worlds\"}],\"displaytitle\":\"<i>World</i>\"}}".to_owned());
         let page = wikipedia.page_from_title("World".to_owned());
+        let options = super::ParseOptions {
+            props: vec![
+                super::ParseProp::Text,
+                super::ParseProp::Links,
+                super::ParseProp::Templates,
+                super::ParseProp::Images,
+                super::ParseProp::ExternalLinks,
+                super::ParseProp::Categories,
+                super::ParseProp::DisplayTitle,
+            ],
+            disable_edit_section: true,
+            disable_toc: true,
+            mobile_format: true,
+            use_skin: Some("vector".to_owned()),
+            section: Some("0".to_owned()),
+        };
         assert_eq!(
-            page.get_categories().await.unwrap().collect_vec::<Vec<_>>().await,
-            vec![
-                iter::Category {
-                    title: "Hello".to_owned(),
-                },
-                iter::Category {
+                page.parse(options).await.unwrap(),
+                super::ParseResult {
                     title: "World".to_owned(),
-                }
-            ]);
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec![
-                       "https://en.wikipedia.org/w/api.php".to_owned(),
-                       "https://en.wikipedia.org/w/api.php".to_owned(),
-                   ]);
+                    pageid: 1,
+                    revid: Some(42),
+                    text: Some("<p>Hi</p>".to_owned()),
+                    sections: vec![],
+                    links: vec![
+                        iter::Link { title: "Earth".to_owned(), ns: 0, exists: true },
+                        iter::Link { title: "Missing".to_owned(), ns: 0, exists: false },
+                    ],
+                    templates: vec![iter::Template { title: "Template:Infobox".to_owned(), ns: 10 }],
+                    images: vec!["Earth view.jpg".to_owned()],
+                    external_links: vec!["https://example.com".to_owned()],
+                    categories: vec![
+                        iter::Category {
+                            title: "Articles with short description".to_owned(),
+                            sortkey: "".to_owned(),
+                            sortkey_prefix: "".to_owned(),
+                            timestamp: None,
+                            hidden: true,
+                        },
+                        iter::Category {
+                            title: "Worlds".to_owned(),
+                            sortkey: "".to_owned(),
+                            sortkey_prefix: "W".to_owned(),
+                            timestamp: None,
+                            hidden: false,
+                        },
+                    ],
+                    display_title: Some("<i>World</i>".to_owned()),
+                    head_html: None,
+                });
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![
-                       vec![
-                           ("prop".to_owned(), "categories".to_owned()),
-                           ("cllimit".to_owned(), "max".to_owned()),
-                           ("format".to_owned(), "json".to_owned()),
-                           ("action".to_owned(), "query".to_owned()),
-                           ("titles".to_owned(), "World".to_owned()),
-                           ("continue".to_owned(), "".to_owned()),
-                       ],
-                       vec![
-                           ("prop".to_owned(), "categories".to_owned()),
-                           ("cllimit".to_owned(), "max".to_owned()),
-                           ("format".to_owned(), "json".to_owned()),
-                           ("action".to_owned(), "query".to_owned()),
-                           ("titles".to_owned(), "World".to_owned()),
-                           ("lol".to_owned(), "1".to_owned()),
-                       ]
-                   ]);
+                vec![vec![
+                    ("prop".to_owned(), "text|links|templates|images|externallinks|categories|displaytitle".to_owned()),
+                    ("section".to_owned(), "0".to_owned()),
+                    ("disableeditsection".to_owned(), "".to_owned()),
+                    ("disabletoc".to_owned(), "".to_owned()),
+                    ("mobileformat".to_owned(), "".to_owned()),
+                    ("useskin".to_owned(), "vector".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "parse".to_owned()),
+                    ("page".to_owned(), "World".to_owned())
+                    ]]);
     }
 
+
     #[wasm_bindgen_test]
-    async fn sections() {
+    async fn parse_wikitext() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"sections\":[{\"line\":\"hello\"}, {\"line\":\"world\"}]}}".to_owned());
-        let page = wikipedia.page_from_pageid("123".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"parse\":{\"title\":\"World\",\"pageid\":0,\"text\":{\"*\":\"<div class=\\\"mw-parser-output\\\"><p><b>Hello</b>\\n</p></div>\"}}}".to_owned());
+        let text = "'''Hello'''".repeat(1000);
         assert_eq!(
-            page.get_sections().await.unwrap(),
-            vec!["hello".to_owned(), "world".to_owned()]
-        );
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+                wikipedia.parse_wikitext(&text, Some("World")).await.unwrap(),
+                "<div class=\"mw-parser-output\"><p><b>Hello</b>\n</p></div>".to_owned());
+        assert_eq!(*wikipedia.client.methods.lock().unwrap(), vec!["POST".to_owned()]);
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("prop".to_owned(), "sections".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "parse".to_owned()),
-                       ("pageid".to_owned(), "123".to_owned())
-                   ]]);
+                vec![vec![
+                    ("prop".to_owned(), "text".to_owned()),
+                    ("contentmodel".to_owned(), "wikitext".to_owned()),
+                    ("text".to_owned(), text.clone()),
+                    ("title".to_owned(), "World".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "parse".to_owned())
+                    ]]);
     }
 
     #[wasm_bindgen_test]
-    async fn languages() {
+    async fn expand_templates() {
         let wikipedia = Wikipedia::<MockClient>::default();
-        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"languages\":[{\"*\":\"hello\", \"code\":\"world\"}, {\"*\":\"foo\", \"code\":\"bar\"}]}}".to_owned());
-        assert_eq!(
-            wikipedia.get_languages().await.unwrap(),
-            vec![
-                ("world".to_owned(), "hello".to_owned()),
-                ("bar".to_owned(), "foo".to_owned()),
-            ]
-        );
-        assert_eq!(*wikipedia.client.url.lock().unwrap(),
-                   vec!["https://en.wikipedia.org/w/api.php".to_owned()]);
+        wikipedia.client.response.lock().unwrap().push("{\"expandtemplates\":{\"wikitext\":\"4\"}}".to_owned());
+        assert_eq!(wikipedia.expand_templates("{{#expr: 2 + 2}}").await.unwrap(), "4".to_owned());
+        assert_eq!(*wikipedia.client.methods.lock().unwrap(), vec!["POST".to_owned()]);
         assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
-                   vec![vec![
-                       ("meta".to_owned(), "siteinfo".to_owned()),
-                       ("siprop".to_owned(), "languages".to_owned()),
-                       ("format".to_owned(), "json".to_owned()),
-                       ("action".to_owned(), "query".to_owned())
-                   ]]);
+                vec![vec![
+                    ("prop".to_owned(), "wikitext".to_owned()),
+                    ("text".to_owned(), "{{#expr: 2 + 2}}".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "expandtemplates".to_owned())
+                    ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_infobox() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\": {\"pages\": {\"a\": {\"revisions\": [{\"revid\": 1, \"parentid\": 0, \"slots\": {\"main\": {\"contentmodel\": \"wikitext\", \"*\": \"{{Short description|Writer}}\\n{{Infobox person\\n| name = Douglas Adams\\n| birth_date = {{birth date|1952|3|11|df=y}}\\n| birth_place = [[Cambridge]], England\\n| spouse = <!-- none --> \\n| occupation = {{hlist|Writer|[[humorist]]}}<ref>x</ref>\\n}}\\n'''Douglas Adams''' was a writer.\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Douglas Adams".to_owned());
+        let infobox = page.get_infobox().await.unwrap().unwrap();
+        assert_eq!(infobox.kind, "person");
+        assert_eq!(infobox.params.iter().map(|x| &*x.0).collect::<Vec<_>>(),
+                vec!["name", "birth_date", "birth_place", "occupation"]);
+        assert_eq!(infobox.get("birth_date").unwrap().text, "11 March 1952");
+        assert_eq!(infobox.get("birth_place").unwrap(), &super::InfoboxValue {
+            text: "Cambridge, England".to_owned(),
+            links: vec!["Cambridge".to_owned()],
+        });
+        assert_eq!(infobox.get("occupation").unwrap(), &super::InfoboxValue {
+            text: "Writer\nhumorist".to_owned(),
+            links: vec!["Humorist".to_owned()],
+        });
+        assert_eq!(wikipedia.client.arguments.lock().unwrap().len(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_infobox_html() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\": {\"pages\": {\"a\": {\"revisions\": [{\"revid\": 1, \"parentid\": 0, \"slots\": {\"main\": {\"contentmodel\": \"wikitext\", \"*\": \"{{Speciesbox|genus=Felis}}The cat.\"}}}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"parse\": {\"title\": \"Cat\", \"pageid\": 1, \"text\": {\"*\": \"<table class=\\\"infobox biota\\\"><tbody><tr><th colspan=\\\"2\\\">Cat</th></tr><tr><th scope=\\\"row\\\">Kingdom:</th><td><a href=\\\"/wiki/Animal\\\" title=\\\"Animal\\\">Animalia</a><sup class=\\\"reference\\\"><a href=\\\"#cite_note-1\\\">[1]</a></sup></td></tr><tr><th>Names</th><td>Felis&#160;catus<br />Cat</td></tr></tbody></table><p>The cat.</p>\"}}}".to_owned());
+        let page = wikipedia.page_from_title("Cat".to_owned());
+        let infobox = page.get_infobox().await.unwrap().unwrap();
+        assert_eq!(infobox, super::Infobox {
+            kind: "".to_owned(),
+            params: vec![
+                ("Kingdom:".to_owned(), super::InfoboxValue {
+                    text: "Animalia".to_owned(),
+                    links: vec!["Animal".to_owned()],
+                }),
+                ("Names".to_owned(), super::InfoboxValue {
+                    text: "Felis catus\nCat".to_owned(),
+                    links: vec![],
+                }),
+            ],
+        });
+        assert_eq!(wikipedia.client.arguments.lock().unwrap()[1][3],
+                ("action".to_owned(), "parse".to_owned()));
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_tables() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\": {\"pages\": {\"a\": {\"revisions\": [{\"revid\": 1, \"parentid\": 0, \"slots\": {\"main\": {\"contentmodel\": \"wikitext\", \"*\": \"Results.\\n{| class=\\\"wikitable\\\"\\n|+ Election <ref>x</ref>\\n|-\\n! rowspan=\\\"2\\\" | Party !! colspan=\\\"2\\\" | Votes\\n|-\\n! Number !! %\\n|-\\n| [[Red Party|Red]] || 1,000 || 60\\n|-\\n| rowspan=2 | Blue || 500 || 30\\n|-\\n| 200 || 10\\n|}\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Election".to_owned());
+        let tables = page.get_tables().await.unwrap();
+        assert_eq!(tables, vec![super::Table {
+            caption: Some("Election".to_owned()),
+            headers: vec!["Party".to_owned(), "Votes / Number".to_owned(), "Votes / %".to_owned()],
+            rows: vec![
+                vec!["Red".to_owned(), "1,000".to_owned(), "60".to_owned()],
+                vec!["Blue".to_owned(), "500".to_owned(), "30".to_owned()],
+                vec!["Blue".to_owned(), "200".to_owned(), "10".to_owned()],
+            ],
+        }]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_html_tables() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"parse\": {\"title\": \"Medals\", \"pageid\": 1, \"text\": {\"*\": \"<div class=\\\"navbox\\\"><table class=\\\"nowraplinks\\\"><tr><td>Nav</td></tr></table></div><table class=\\\"wikitable sortable\\\"><caption>Medals</caption><tbody><tr><th>Nation</th><th>Gold</th></tr><tr><td><a href=\\\"/wiki/Norway\\\" title=\\\"Norway\\\">Norway</a><sup class=\\\"reference\\\"><a href=\\\"#cite_note-1\\\">[1]</a></sup></td><td>16</td></tr><tr><td colspan=\\\"2\\\">Total&#160;<table><tr><td>nested</td></tr></table></td></tr></tbody></table>\"}}}".to_owned());
+        let page = wikipedia.page_from_title("Medals".to_owned());
+        let tables = page.get_html_tables().await.unwrap();
+        assert_eq!(tables, vec![super::Table {
+            caption: Some("Medals".to_owned()),
+            headers: vec!["Nation".to_owned(), "Gold".to_owned()],
+            rows: vec![
+                vec!["Norway".to_owned(), "16".to_owned()],
+                vec!["Total nested".to_owned(), "Total nested".to_owned()],
+            ],
+        }]);
+    }
+
+    #[wasm_bindgen_test]
+    fn table_export() {
+        let table = super::Table {
+            caption: None,
+            headers: vec!["Name".to_owned(), "Note".to_owned()],
+            rows: vec![vec!["A".to_owned(), "says \"hi\", twice".to_owned()]],
+        };
+        assert_eq!(table.to_csv(), "Name,Note\nA,\"says \"\"hi\"\", twice\"\n");
+        assert_eq!(table.to_json(), serde_json::json!({
+            "caption": null,
+            "headers": ["Name", "Note"],
+            "rows": [["A", "says \"hi\", twice"]],
+        }));
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_markdown() {
+        let mut wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.set_base_url("https://es.wikipedia.org/w/api.php");
+        wikipedia.client.response.lock().unwrap().push("{\"query\": {\"pages\": {\"a\": {\"revisions\": [{\"revid\": 1, \"parentid\": 0, \"slots\": {\"main\": {\"contentmodel\": \"wikitext\", \"*\": \"'''Hola''' es [[Espa\\u00f1a]].\\n\\n== Historia ==\\nTexto.\"}}}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Hola".to_owned());
+        let markdown = page.get_markdown(super::MarkdownOptions::default()).await.unwrap();
+        assert_eq!(markdown, "**Hola** es [España](https://es.wikipedia.org/wiki/España).\n\n## Historia\n\nTexto.\n");
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_extract() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extract\":\"Hello. World.\"}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"extract\":\"<p><b>Hello</b>…</p>\"}}}}".to_owned());
+        let page = wikipedia.page_from_title("Hello".to_owned());
+        let extract = page.get_extract(super::ExtractOptions {
+            sentences: Some(2),
+            intro: true,
+            section_format: super::ExtractSectionFormat::Wiki,
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(extract, "Hello. World.");
+        let extract = page.get_extract(super::ExtractOptions {
+            chars: Some(100),
+            html: true,
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(extract, "<p><b>Hello</b>…</p>");
+        assert_eq!(*wikipedia.client.arguments.lock().unwrap(),
+                vec![vec![
+                    ("prop".to_owned(), "extracts".to_owned()),
+                    ("exsentences".to_owned(), "2".to_owned()),
+                    ("exintro".to_owned(), "".to_owned()),
+                    ("explaintext".to_owned(), "".to_owned()),
+                    ("exsectionformat".to_owned(), "wiki".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "Hello".to_owned())
+                    ], vec![
+                    ("prop".to_owned(), "extracts".to_owned()),
+                    ("exchars".to_owned(), "100".to_owned()),
+                    ("redirects".to_owned(), "".to_owned()),
+                    ("format".to_owned(), "json".to_owned()),
+                    ("action".to_owned(), "query".to_owned()),
+                    ("titles".to_owned(), "Hello".to_owned())
+                    ]]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_extract_invalid() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let page = wikipedia.page_from_title("Hello".to_owned());
+        let options = super::ExtractOptions {
+            sentences: Some(2),
+            chars: Some(100),
+            ..Default::default()
+        };
+        assert!(page.get_extract(options).await.is_err());
+        let options = super::ExtractOptions {
+            sentences: Some(11),
+            ..Default::default()
+        };
+        assert!(page.get_extract(options).await.is_err());
+        assert!(wikipedia.client.arguments.lock().unwrap().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn html_tables_truncated() {
+        assert_eq!(super::Table::from_html("<p>Text</p><table"), vec![]);
+        assert_eq!(super::Table::from_html("<table class=\"wikitable\"><tr><td>1</td></tr></table><table日本"), vec![super::Table {
+            caption: None,
+            headers: vec![],
+            rows: vec![vec!["1".to_owned()]],
+        }]);
+        assert_eq!(super::Infobox::from_html("<table"), None);
+    }
+
+    #[wasm_bindgen_test]
+    async fn get_categories_localized_continuation() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"clcontinue\":\"1|B\",\"continue\":\"||\"},\"query\":{\"namespaces\":{\"14\":{\"id\":14,\"*\":\"Kategorie\",\"canonical\":\"Category\"}},\"pages\":{\"a\":{\"categories\":[{\"ns\":14,\"title\":\"Kategorie:A\"}]}}}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"pages\":{\"a\":{\"categories\":[{\"ns\":14,\"title\":\"Kategorie:B\"}]}}}}".to_owned());
+        let page = wikipedia.page_from_title("Welt".to_owned());
+        let categories = page.get_categories().await.unwrap().collect_vec::<Vec<_>>().await;
+        assert_eq!(categories.iter().map(|x| &*x.title).collect::<Vec<_>>(), vec!["A", "B"]);
+        let arguments = wikipedia.client.arguments.lock().unwrap();
+        assert!(arguments[0].contains(&("meta".to_owned(), "siteinfo".to_owned())));
+        assert!(!arguments[1].contains(&("meta".to_owned(), "siteinfo".to_owned())));
+    }
+
+    #[wasm_bindgen_test]
+    async fn post_not_implemented() {
+        #[derive(Default)]
+        struct GetClient;
+        impl super::http::HttpClient for GetClient {
+            fn user_agent(&mut self, _user_agent: String) {}
+            async fn get<'a, I>(&self, _base_url: &str, _args: I) -> Result<String, super::http::Error>
+                    where I: Iterator<Item=(&'a str, &'a str)> {
+                panic!("POST requests must not fall back to GET");
+            }
+        }
+        let wikipedia = Wikipedia::<GetClient>::default();
+        assert!(wikipedia.parse_wikitext("''Hello''", None).await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn category_members_timestamp_types() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        wikipedia.client.response.lock().unwrap().push("{\"continue\": {\"cmcontinue\":\"2020|1\"},\"query\":{\"categorymembers\":[{\"pageid\":1,\"ns\":6,\"title\":\"File:Hello.jpg\",\"type\":\"file\"}]}}".to_owned());
+        wikipedia.client.response.lock().unwrap().push("{\"query\":{\"categorymembers\":[{\"pageid\":2,\"ns\":14,\"title\":\"Category:World\",\"type\":\"subcat\"},{\"pageid\":3,\"ns\":0,\"title\":\"Hi\",\"type\":\"page\"}]}}".to_owned());
+        let query = iter::CategoryMemberQuery {
+            types: vec![iter::CategoryMemberType::Page],
+            sort: iter::CategoryMemberSort::Timestamp,
+            ..Default::default()
+        };
+        let members = wikipedia.category_members("Category:Greetings".to_owned(), query).await.unwrap().collect_vec::<Vec<_>>().await;
+        assert_eq!(members.iter().map(|x| &*x.title).collect::<Vec<_>>(), vec!["Hi"]);
+        assert_eq!(wikipedia.client.arguments.lock().unwrap().len(), 2);
+    }
+
+    #[wasm_bindgen_test]
+    async fn transcluded_section_wikitext() {
+        let wikipedia = Wikipedia::<MockClient>::default();
+        let page = wikipedia.page_from_title("World".to_owned());
+        assert!(page.get_section_wikitext("T-1").await.is_err());
+        assert!(wikipedia.client.arguments.lock().unwrap().is_empty());
     }
 }
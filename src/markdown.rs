@@ -0,0 +1,497 @@
+//! Converts parsed wikitext into CommonMark, using the GitHub flavored
+//! markdown extensions for tables and footnotes.
+
+use std::collections::HashMap;
+
+use crate::wikitext::{self, InternalLink, Node, Template};
+use crate::{MarkdownLinks, MarkdownOptions, SpanCell, Table};
+
+/// Converts `nodes` into markdown. The links to articles point to
+/// `wiki_url` followed by the title, as in "https://en.wikipedia.org/wiki/".
+pub fn from_wikitext(nodes: &[Node], options: &MarkdownOptions, wiki_url: &str) -> String {
+    let mut writer = Writer {
+        options,
+        wiki_url,
+        notes: Vec::new(),
+        note_names: HashMap::new(),
+    };
+    let blocks: Vec<String> = nodes
+        .iter()
+        .map(|node| writer.block(node).trim().to_owned())
+        .filter(|x| !x.is_empty())
+        .collect();
+
+    // Leave out the headings with nothing under them, like the
+    // "References" heading once the references are footnotes
+    let mut kept = Vec::new();
+    let mut next_heading = Some(0);
+    for block in blocks.into_iter().rev() {
+        match heading_level(&block) {
+            Some(level) if next_heading.is_some_and(|x| x <= level) => continue,
+            Some(level) => next_heading = Some(level),
+            None => next_heading = None,
+        }
+        kept.push(block);
+    }
+    kept.reverse();
+
+    let notes: Vec<String> = writer.notes
+        .iter()
+        .enumerate()
+        .filter(|x| !x.1.is_empty())
+        .map(|(i, text)| format!("[^{}]: {}", i + 1, text))
+        .collect();
+    if !notes.is_empty() {
+        kept.push(notes.join("\n"));
+    }
+
+    let mut markdown = String::new();
+    let mut empty_lines = 0;
+    for line in kept.join("\n\n").lines() {
+        let line = line.trim_end();
+        empty_lines = if line.is_empty() { empty_lines + 1 } else { 0 };
+        if empty_lines < 2 {
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+    }
+    markdown
+}
+
+struct Writer<'o> {
+    options: &'o MarkdownOptions,
+    wiki_url: &'o str,
+    /// The text of each footnote, empty until its content is found.
+    notes: Vec<String>,
+    /// The footnote number of each named reference.
+    note_names: HashMap<String, usize>,
+}
+
+impl Writer<'_> {
+    fn block(&mut self, node: &Node) -> String {
+        match *node {
+            Node::Heading(ref heading) => {
+                format!("{} {}", "#".repeat(heading.level), self.inline(&heading.content).trim())
+            },
+            Node::Paragraph(ref content) => self.inline(content)
+                .lines()
+                .map(|x| x.trim())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Node::List(ref items) => {
+                let mut text = String::new();
+                let mut previous_plain = false;
+                for (i, item) in items.iter().enumerate() {
+                    let (parents, last) = item.marker.split_at(item.marker.len() - 1);
+                    let indent: String = parents
+                        .chars()
+                        .map(|c| match c {
+                            '*' => "  ",
+                            '#' => "   ",
+                            _ => "",
+                        })
+                        .collect();
+                    let content = self.inline(&item.content);
+                    let content = content.trim();
+                    let (line, plain) = match last {
+                        "*" => (format!("- {}", content), false),
+                        "#" => (format!("1. {}", content), false),
+                        ";" => (format!("**{}**", content), parents.is_empty()),
+                        _ => (content.to_owned(), parents.is_empty()),
+                    };
+                    if i > 0 {
+                        text.push_str(if plain || previous_plain { "\n\n" } else { "\n" });
+                    }
+                    text.push_str(&indent);
+                    text.push_str(&line);
+                    previous_plain = plain;
+                }
+                text
+            },
+            Node::Table(ref table) => self.table(table),
+            _ => self.inline(std::slice::from_ref(node)),
+        }
+    }
+
+    fn inline(&mut self, nodes: &[Node]) -> String {
+        let mut out = String::new();
+        for node in nodes {
+            self.write_inline(node, &mut out);
+        }
+        out
+    }
+
+    fn write_inline(&mut self, node: &Node, out: &mut String) {
+        match *node {
+            Node::Text(ref text) => out.push_str(&escape(&wikitext::decode_entities(&wikitext::strip_html(text)))),
+            Node::Paragraph(ref content) => out.push_str(&self.inline(content)),
+            Node::Heading(_) | Node::List(_) | Node::Table(_) => {
+                out.push_str("\n\n");
+                out.push_str(&self.block(node));
+                out.push_str("\n\n");
+            },
+            Node::Bold(ref content) => out.push_str(&wrap(&self.inline(content), "**")),
+            Node::Italic(ref content) => out.push_str(&wrap(&self.inline(content), "*")),
+            Node::InternalLink(ref link) => out.push_str(&self.link(link)),
+            Node::ExternalLink(ref link) => {
+                let url = match link.url.strip_prefix("//") {
+                    Some(rest) => format!("https://{}", rest),
+                    None => link.url.clone(),
+                };
+                match link.text {
+                    Some(ref text) if link.bracketed => {
+                        out.push_str(&format!("[{}]({})", self.inline(text).trim(), url));
+                    },
+                    _ => out.push_str(&format!("<{}>", url)),
+                }
+            },
+            Node::Template(ref template) => {
+                let name = template.name().to_lowercase();
+                let block = if name.starts_with("infobox") {
+                    if self.options.drop_infoboxes { String::new() } else { self.infobox(template) }
+                } else if name.starts_with("navbox") {
+                    if self.options.drop_navboxes { String::new() } else { self.navbox(template) }
+                } else {
+                    if let Some(refs) = template.get("refs").filter(|_| name == "reflist" && !self.options.drop_references) {
+                        // References defined in the list, used by name in the text
+                        for node in wikitext::descendants(&refs.value) {
+                            if let Node::Ref(ref r) = *node {
+                                self.note(r);
+                            }
+                        }
+                    }
+                    out.push_str(&escape(&node.plain_text()));
+                    return;
+                };
+                if !block.is_empty() {
+                    out.push_str("\n\n");
+                    out.push_str(&block);
+                    out.push_str("\n\n");
+                }
+            },
+            Node::Argument(ref argument) => if let Some(ref default) = argument.default {
+                out.push_str(&self.inline(default));
+            },
+            Node::Ref(ref r) => if !self.options.drop_references {
+                let number = self.note(r);
+                out.push_str(&format!("[^{}]", number));
+            },
+            Node::Tag(ref tag) => {
+                let content = match tag.content {
+                    Some(ref content) => content,
+                    None => return,
+                };
+                match &*tag.name.to_lowercase() {
+                    "pre" => out.push_str(&format!("\n\n```\n{}\n```\n\n", wikitext::decode_entities(content.trim_matches('\n')))),
+                    "syntaxhighlight" | "source" => {
+                        let lang = crate::citation::attribute(&tag.attributes, "lang").unwrap_or_default();
+                        out.push_str(&format!("\n\n```{}\n{}\n```\n\n", lang, content.trim_matches('\n')));
+                    },
+                    "math" | "chem" => out.push_str(&format!("${}$", content.trim())),
+                    "poem" => out.push_str(&escape(&wikitext::decode_entities(content))),
+                    _ => (),
+                }
+            },
+            Node::Nowiki(ref text) => out.push_str(&escape(&wikitext::decode_entities(text))),
+            Node::ParserFunction(_) | Node::Comment(_) => (),
+        }
+    }
+
+    fn link(&mut self, link: &InternalLink) -> String {
+        if link.is_media() || link.is_category() {
+            return String::new();
+        }
+        let target = link.target.trim().trim_start_matches(':').trim();
+        let fragment = target.split_once('#').map(|x| x.1.trim());
+        match self.options.links {
+            MarkdownLinks::Urls => {
+                let mut text = match link.text {
+                    Some(ref text) => self.inline(text).trim().to_owned(),
+                    None => escape(target),
+                };
+                text.push_str(&escape(&link.trail));
+                let mut url = match link.title() {
+                    Some(title) => format!("{}{}", self.wiki_url, encode(&title)),
+                    None => String::new(),
+                };
+                if let Some(fragment) = fragment {
+                    url.push('#');
+                    url.push_str(&encode(fragment));
+                }
+                format!("[{}]({})", text, url)
+            },
+            MarkdownLinks::WikiLinks => {
+                let text = match link.text {
+                    Some(ref text) => wikitext::plain_text(text),
+                    None => target.to_owned(),
+                } + &link.trail;
+                if text == target {
+                    format!("[[{}]]", target)
+                } else {
+                    format!("[[{}|{}]]", target, text)
+                }
+            },
+        }
+    }
+
+    /// Adds the reference to the footnotes, returning its number.
+    fn note(&mut self, r: &wikitext::Ref) -> usize {
+        let name = r.name();
+        let number = match name.as_ref().and_then(|x| self.note_names.get(x)) {
+            Some(&number) => number,
+            None => {
+                self.notes.push(String::new());
+                if let Some(name) = name {
+                    self.note_names.insert(name, self.notes.len());
+                }
+                self.notes.len()
+            },
+        };
+        if let Some(ref content) = r.content {
+            let text = self.inline(content).split_whitespace().collect::<Vec<_>>().join(" ");
+            if !text.is_empty() {
+                self.notes[number - 1] = text;
+            }
+        }
+        number
+    }
+
+    /// Writes the content of a table cell in a single line.
+    fn cell(&mut self, content: &[Node]) -> String {
+        let text: String = content.iter().map(|x| self.block(x)).collect();
+        lines(&text).join("<br>").replace('|', "\\|")
+    }
+
+    fn table(&mut self, table: &wikitext::Table) -> String {
+        let mut rows = Vec::new();
+        for row in &table.rows {
+            let mut cells = Vec::new();
+            for cell in &row.cells {
+                let text = self.cell(&cell.content);
+                cells.push(SpanCell::new(cell.header, text, cell.attributes.as_deref().unwrap_or("")));
+            }
+            rows.push(cells);
+        }
+        let caption = table.caption.as_ref().map(|caption| self.cell(&caption.content));
+        let table = Table::from_cells(caption, rows);
+        let (headers, rows) = if table.headers.is_empty() {
+            match table.rows.split_first() {
+                Some((first, rest)) => (first, rest),
+                None => return String::new(),
+            }
+        } else {
+            (&table.headers, &*table.rows)
+        };
+        let mut lines = Vec::new();
+        if let Some(ref caption) = table.caption {
+            lines.push(format!("**{}**\n", caption));
+        }
+        lines.push(table_row(headers));
+        lines.push(table_row(&vec!["---".to_owned(); headers.len()]));
+        lines.extend(rows.iter().map(|x| table_row(x)));
+        lines.join("\n")
+    }
+
+    /// Writes an infobox as a table of its parameters and values.
+    fn infobox(&mut self, template: &Template) -> String {
+        let mut rows = Vec::new();
+        for param in &template.params {
+            let name = match param.name() {
+                Some(name) => name,
+                None => continue,
+            };
+            let value = self.cell(&param.value);
+            if !value.is_empty() {
+                rows.push(table_row(&[escape(name), value]));
+            }
+        }
+        if rows.is_empty() {
+            return String::new();
+        }
+        let mut lines = vec![
+            table_row(&[escape(&template.name()), String::new()]),
+            table_row(&["---".to_owned(), "---".to_owned()]),
+        ];
+        lines.extend(rows);
+        lines.join("\n")
+    }
+
+    /// Writes a navbox as its title and a list of its groups.
+    fn navbox(&mut self, template: &Template) -> String {
+        let mut text = String::new();
+        if let Some(title) = template.get("title") {
+            let title = lines(&self.inline(&title.value)).join(" ");
+            if !title.is_empty() {
+                text.push_str(&format!("**{}**\n\n", title));
+            }
+        }
+        for param in &template.params {
+            let number = match param.name().and_then(|x| x.strip_prefix("list")) {
+                Some(number) => number,
+                None => continue,
+            };
+            let list = lines(&self.inline(&param.value)).join(" · ");
+            if list.is_empty() {
+                continue;
+            }
+            let group = match template.get(&format!("group{}", number)) {
+                Some(group) => lines(&self.inline(&group.value)).join(" "),
+                None => String::new(),
+            };
+            if group.is_empty() {
+                text.push_str(&format!("- {}\n", list));
+            } else {
+                text.push_str(&format!("- **{}**: {}\n", group, list));
+            }
+        }
+        text
+    }
+}
+
+/// The level of a markdown heading.
+fn heading_level(block: &str) -> Option<usize> {
+    let level = block.len() - block.trim_start_matches('#').len();
+    if level > 0 && block[level..].starts_with(' ') { Some(level) } else { None }
+}
+
+/// Splits markdown into its non-empty lines, without list markers.
+fn lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("- ").or_else(|| line.strip_prefix("1. ")).unwrap_or(line);
+            let mut line = line.trim_start();
+            while let Some(rest) = line.strip_prefix("\\*").or_else(|| line.strip_prefix(['#', ':', ';'])) {
+                line = rest.trim_start();
+            }
+            line.to_owned()
+        })
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+fn table_row(cells: &[String]) -> String {
+    format!("| {} |", cells.join(" | "))
+}
+
+/// Puts `marks` around `text`, leaving its surrounding whitespace outside
+/// as markdown requires.
+fn wrap(text: &str, marks: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_owned();
+    }
+    let start = text.len() - text.trim_start().len();
+    let end = start + trimmed.len();
+    format!("{}{}{}{}{}", &text[..start], marks, trimmed, marks, &text[end..])
+}
+
+/// Escapes the characters of `text` that markdown would take as markup.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Writes a title as part of a url.
+fn encode(title: &str) -> String {
+    let mut encoded = String::with_capacity(title.len());
+    for c in title.chars() {
+        match c {
+            ' ' => encoded.push('_'),
+            '%' | '(' | ')' | '?' | '"' | '<' | '>' | '\\' => encoded.push_str(&format!("%{:02X}", c as u32)),
+            c => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use super::from_wikitext;
+    use crate::{wikitext, MarkdownLinks, MarkdownOptions};
+
+    const SOURCE: &str = "{{Short description|Capital of France}}
+{{Infobox settlement
+| name = Paris
+| country = [[France]]
+}}
+'''Paris''' is the [[capital city|capital]]s of [[France]].<ref name=\"a\">[https://paris.fr Paris site]</ref> It has ''many'' [[Paris (disambiguation)|parks]].<ref name=\"a\" />
+
+== Districts ==
+* [[Louvre]]
+*# First
+; Note
+: Text
+
+{| class=\"wikitable\"
+|+ Population
+! Year !! People
+|-
+| rowspan=\"2\" | 2020 || 2,000,000
+|-
+| 2,100,000
+|}
+
+== References ==
+{{Reflist}}
+{{Navbox
+| title = [[France]]
+| group1 = Cities
+| list1 = * [[Paris]]
+* [[Lyon]]
+}}
+[[Category:Cities]]";
+
+    #[test]
+    fn markdown() {
+        let nodes = wikitext::parse(SOURCE);
+        let markdown = from_wikitext(&nodes, &MarkdownOptions::default(), "https://en.wikipedia.org/wiki/");
+        assert_eq!(markdown, "| Infobox settlement |  |
+| --- | --- |
+| name | Paris |
+| country | [France](https://en.wikipedia.org/wiki/France) |
+
+**Paris** is the [capitals](https://en.wikipedia.org/wiki/Capital_city) of [France](https://en.wikipedia.org/wiki/France).[^1] It has *many* [parks](https://en.wikipedia.org/wiki/Paris_%28disambiguation%29).[^1]
+
+## Districts
+
+- [Louvre](https://en.wikipedia.org/wiki/Louvre)
+  1. First
+
+**Note**
+
+Text
+
+**Population**
+
+| Year | People |
+| --- | --- |
+| 2020 | 2,000,000 |
+| 2020 | 2,100,000 |
+
+## References
+
+**[France](https://en.wikipedia.org/wiki/France)**
+
+- **Cities**: [Paris](https://en.wikipedia.org/wiki/Paris) · [Lyon](https://en.wikipedia.org/wiki/Lyon)
+
+[^1]: [Paris site](https://paris.fr)
+");
+
+        let options = MarkdownOptions {
+            links: MarkdownLinks::WikiLinks,
+            drop_references: true,
+            drop_infoboxes: true,
+            drop_navboxes: true,
+        };
+        let markdown = from_wikitext(&nodes, &options, "https://en.wikipedia.org/wiki/");
+        assert!(markdown.starts_with("**Paris** is the [[capital city|capitals]] of [[France]]. It has *many* [[Paris (disambiguation)|parks]].\n\n## Districts\n\n- [[Louvre]]\n"));
+        assert!(markdown.ends_with("| 2020 | 2,100,000 |\n"));
+    }
+}
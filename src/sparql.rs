@@ -0,0 +1,186 @@
+//! Run SPARQL queries against a Wikidata Query Service style endpoint.
+
+use std::collections::BTreeMap;
+
+use crate::{http, Error, Page, Result, Wikipedia};
+
+/// A value bound to a variable in a query result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Uri(String),
+    Literal {
+        value: String,
+        /// The language tag, like "en".
+        lang: Option<String>,
+        /// The datatype uri, like "http://www.w3.org/2001/XMLSchema#dateTime".
+        datatype: Option<String>,
+    },
+    /// A blank node, identified by its label.
+    BlankNode(String),
+}
+
+impl Term {
+    /// The uri, literal value or blank node label.
+    pub fn value(&self) -> &str {
+        match *self {
+            Term::Uri(ref v) => v,
+            Term::Literal { ref value, .. } => value,
+            Term::BlankNode(ref v) => v,
+        }
+    }
+
+    /// Creates a `Page` if the term is the uri of an article in `wikipedia`.
+    pub fn to_page<'a, A: http::HttpClient>(&self, wikipedia: &'a Wikipedia<A>) -> Option<Page<'a, A>> {
+        match *self {
+            Term::Uri(ref uri) => wikipedia.page_from_url(uri),
+            _ => None,
+        }
+    }
+
+    fn from_value(value: &serde_json::Value) -> Option<Term> {
+        let string = |key: &str| value.get(key).and_then(|x| x.as_str()).map(|x| x.to_owned());
+        let v = string("value")?;
+        match value.get("type").and_then(|x| x.as_str())? {
+            "uri" => Some(Term::Uri(v)),
+            "literal" | "typed-literal" => Some(Term::Literal {
+                value: v,
+                lang: string("xml:lang"),
+                datatype: string("datatype"),
+            }),
+            "bnode" => Some(Term::BlankNode(v)),
+            _ => None,
+        }
+    }
+}
+
+/// The results of a query, in the SPARQL JSON results format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResults {
+    /// The variables selected by the query, without the `?`.
+    pub vars: Vec<String>,
+    /// One map of variable to value for each solution. Unbound variables
+    /// are missing from the map.
+    pub bindings: Vec<BTreeMap<String, Term>>,
+    /// The answer to an `ASK` query.
+    pub boolean: Option<bool>,
+}
+
+impl QueryResults {
+    /// Parses a response in the SPARQL JSON results format.
+    pub fn from_json(json: &str) -> Result<QueryResults> {
+        let q: serde_json::Value = serde_json::from_str(json).map_err(Error::JSONError)?;
+        let o = q.as_object().ok_or(Error::JSONPathError)?;
+        let vars = o
+            .get("head")
+            .and_then(|x| x.get("vars"))
+            .and_then(|x| x.as_array())
+            .map(|x| x.iter().filter_map(|x| x.as_str()).map(|x| x.to_owned()).collect())
+            .unwrap_or_default();
+        let boolean = o.get("boolean").and_then(|x| x.as_bool());
+        let bindings = match o.get("results") {
+            Some(results) => results
+                .get("bindings")
+                .and_then(|x| x.as_array())
+                .ok_or(Error::JSONPathError)?
+                .iter()
+                .filter_map(|x| x.as_object())
+                .map(|x| x.iter()
+                    .filter_map(|(k, v)| Some((k.clone(), Term::from_value(v)?)))
+                    .collect())
+                .collect(),
+            None if boolean.is_some() => Vec::new(),
+            None => return Err(Error::JSONPathError),
+        };
+        Ok(QueryResults { vars, bindings, boolean })
+    }
+}
+
+#[derive(Debug)]
+pub struct Sparql<A: http::HttpClient> {
+    /// HttpClient struct.
+    pub client: A,
+    /// The url of the SPARQL endpoint.
+    pub endpoint: String,
+}
+
+impl<A: http::HttpClient + Default> Default for Sparql<A> {
+    fn default() -> Self {
+        Sparql::new(A::default())
+    }
+}
+
+impl<A: http::HttpClient> Sparql<A> {
+    /// Creates a new object using the provided client and the Wikidata
+    /// Query Service endpoint.
+    pub fn new(mut client: A) -> Self {
+        client.user_agent("wikipedia (https://github.com/seppo0010/wikipedia-rs)".to_owned());
+        Sparql {
+            client,
+            endpoint: "https://query.wikidata.org/sparql".to_owned(),
+        }
+    }
+
+    /// Updates the url of the SPARQL endpoint, for example to use a mirror.
+    pub fn set_endpoint(&mut self, endpoint: &str) {
+        self.endpoint = endpoint.to_owned();
+    }
+
+    /// Runs `query` and returns its results.
+    pub async fn query(&self, query: &str) -> Result<QueryResults> {
+        let args = vec![
+            ("query", query),
+            ("format", "json"),
+        ];
+        let response_str = self.client.get(&self.endpoint, args.into_iter()).await.map_err(|_| Error::HTTPError)?;
+        QueryResults::from_json(&response_str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{QueryResults, Term};
+
+    #[test]
+    fn select_results() {
+        let results = QueryResults::from_json(r#"{
+            "head": {"vars": ["item", "itemLabel", "born", "article", "node"]},
+            "results": {"bindings": [
+                {
+                    "item": {"type": "uri", "value": "http://www.wikidata.org/entity/Q42"},
+                    "itemLabel": {"xml:lang": "en", "type": "literal", "value": "Douglas Adams"},
+                    "born": {"datatype": "http://www.w3.org/2001/XMLSchema#dateTime", "type": "literal", "value": "1952-03-11T00:00:00Z"},
+                    "article": {"type": "uri", "value": "https://en.wikipedia.org/wiki/Douglas_Adams"},
+                    "node": {"type": "bnode", "value": "b0"}
+                },
+                {
+                    "item": {"type": "uri", "value": "http://www.wikidata.org/entity/Q1"}
+                }
+            ]}
+        }"#).unwrap();
+        assert_eq!(results.vars, vec!["item", "itemLabel", "born", "article", "node"]);
+        assert_eq!(results.boolean, None);
+        assert_eq!(results.bindings.len(), 2);
+        let first = &results.bindings[0];
+        assert_eq!(first["item"], Term::Uri("http://www.wikidata.org/entity/Q42".to_owned()));
+        assert_eq!(first["itemLabel"], Term::Literal {
+            value: "Douglas Adams".to_owned(),
+            lang: Some("en".to_owned()),
+            datatype: None,
+        });
+        assert_eq!(first["born"], Term::Literal {
+            value: "1952-03-11T00:00:00Z".to_owned(),
+            lang: None,
+            datatype: Some("http://www.w3.org/2001/XMLSchema#dateTime".to_owned()),
+        });
+        assert_eq!(first["node"], Term::BlankNode("b0".to_owned()));
+        assert_eq!(first["article"].value(), "https://en.wikipedia.org/wiki/Douglas_Adams");
+        assert!(!results.bindings[1].contains_key("itemLabel"));
+    }
+
+    #[test]
+    fn ask_results() {
+        let results = QueryResults::from_json(r#"{"head": {}, "boolean": true}"#).unwrap();
+        assert_eq!(results.boolean, Some(true));
+        assert!(results.bindings.is_empty());
+    }
+}
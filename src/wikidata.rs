@@ -0,0 +1,414 @@
+//! Access Wikidata entities through the Wikibase api.
+
+use std::collections::BTreeMap;
+
+use crate::{http, Error, Page, Result, Wikipedia};
+
+/// A Wikidata entity, usually an item like `Q42` or a property like `P31`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entity {
+    pub id: String,
+    /// Labels by language code.
+    pub labels: BTreeMap<String, String>,
+    /// Descriptions by language code.
+    pub descriptions: BTreeMap<String, String>,
+    /// Aliases by language code.
+    pub aliases: BTreeMap<String, Vec<String>>,
+    /// Sitelinks by site id, for example "enwiki".
+    pub sitelinks: BTreeMap<String, Sitelink>,
+    /// Statements by property id.
+    pub claims: BTreeMap<String, Vec<Claim>>,
+}
+
+impl Entity {
+    /// The label in `language`, if there is one.
+    pub fn label(&self, language: &str) -> Option<&str> {
+        self.labels.get(language).map(|x| &**x)
+    }
+
+    /// The description in `language`, if there is one.
+    pub fn description(&self, language: &str) -> Option<&str> {
+        self.descriptions.get(language).map(|x| &**x)
+    }
+
+    /// The values of the statements about `property`, skipping the ones
+    /// with no value or an unknown value.
+    pub fn values(&self, property: &str) -> Vec<&DataValue> {
+        self.claims
+            .get(property)
+            .map(|claims| claims.iter().filter_map(|x| x.mainsnak.value.value()).collect())
+            .unwrap_or_default()
+    }
+
+    fn from_value(value: &serde_json::Value) -> Option<Entity> {
+        let o = value.as_object()?;
+        let terms = |key: &str| o
+            .get(key)
+            .and_then(|x| x.as_object())
+            .map(|x| x.iter()
+                .filter_map(|(k, v)| Some((k.clone(), v.get("value")?.as_str()?.to_owned())))
+                .collect())
+            .unwrap_or_default();
+        Some(Entity {
+            id: o.get("id")?.as_str()?.to_owned(),
+            labels: terms("labels"),
+            descriptions: terms("descriptions"),
+            aliases: o
+                .get("aliases")
+                .and_then(|x| x.as_object())
+                .map(|x| x.iter()
+                    .map(|(k, v)| (k.clone(), v
+                        .as_array()
+                        .map(|x| x.iter()
+                            .filter_map(|x| x.get("value").and_then(|x| x.as_str()).map(|x| x.to_owned()))
+                            .collect())
+                        .unwrap_or_default()))
+                    .collect())
+                .unwrap_or_default(),
+            sitelinks: o
+                .get("sitelinks")
+                .and_then(|x| x.as_object())
+                .map(|x| x.iter()
+                    .filter_map(|(k, v)| Some((k.clone(), Sitelink {
+                        site: v.get("site")?.as_str()?.to_owned(),
+                        title: v.get("title")?.as_str()?.to_owned(),
+                        url: v.get("url").and_then(|x| x.as_str()).map(|x| x.to_owned()),
+                    })))
+                    .collect())
+                .unwrap_or_default(),
+            claims: o
+                .get("claims")
+                .and_then(|x| x.as_object())
+                .map(|x| x.iter()
+                    .map(|(k, v)| (k.clone(), v
+                        .as_array()
+                        .map(|x| x.iter().filter_map(Claim::from_value).collect())
+                        .unwrap_or_default()))
+                    .collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// A link from an entity to a page in another wiki.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sitelink {
+    pub site: String,
+    pub title: String,
+    /// The url of the page, like "https://en.wikipedia.org/wiki/Douglas_Adams".
+    pub url: Option<String>,
+}
+
+/// A statement about an entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Claim {
+    pub id: String,
+    /// "preferred", "normal" or "deprecated".
+    pub rank: String,
+    pub mainsnak: Snak,
+    /// Qualifiers by property id.
+    pub qualifiers: BTreeMap<String, Vec<Snak>>,
+}
+
+impl Claim {
+    fn from_value(value: &serde_json::Value) -> Option<Claim> {
+        Some(Claim {
+            id: value.get("id").and_then(|x| x.as_str()).unwrap_or("").to_owned(),
+            rank: value.get("rank").and_then(|x| x.as_str()).unwrap_or("normal").to_owned(),
+            mainsnak: Snak::from_value(value.get("mainsnak")?)?,
+            qualifiers: value
+                .get("qualifiers")
+                .and_then(|x| x.as_object())
+                .map(|x| x.iter()
+                    .map(|(k, v)| (k.clone(), v
+                        .as_array()
+                        .map(|x| x.iter().filter_map(Snak::from_value).collect())
+                        .unwrap_or_default()))
+                    .collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// A property and its value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snak {
+    pub property: String,
+    /// The datatype of the property, for example "wikibase-item" or "time".
+    pub datatype: Option<String>,
+    pub value: SnakValue,
+}
+
+impl Snak {
+    fn from_value(value: &serde_json::Value) -> Option<Snak> {
+        Some(Snak {
+            property: value.get("property")?.as_str()?.to_owned(),
+            datatype: value.get("datatype").and_then(|x| x.as_str()).map(|x| x.to_owned()),
+            value: match value.get("snaktype").and_then(|x| x.as_str()) {
+                Some("somevalue") => SnakValue::SomeValue,
+                Some("novalue") => SnakValue::NoValue,
+                _ => SnakValue::Value(DataValue::from_value(value.get("datavalue")?)),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnakValue {
+    Value(DataValue),
+    /// The property has a value, but it is unknown.
+    SomeValue,
+    /// The property has no value.
+    NoValue,
+}
+
+impl SnakValue {
+    /// The value, if it is known.
+    pub fn value(&self) -> Option<&DataValue> {
+        match *self {
+            SnakValue::Value(ref v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a snak, by its `datavalue` type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataValue {
+    String(String),
+    MonolingualText {
+        text: String,
+        language: String,
+    },
+    /// A reference to another entity, for example "Q5".
+    EntityId(String),
+    Time {
+        /// In the form "+2001-12-31T00:00:00Z".
+        time: String,
+        timezone: i64,
+        /// From 0 (billion years) to 14 (seconds), 11 being a day.
+        precision: u8,
+        calendarmodel: String,
+    },
+    Quantity {
+        /// A decimal with its sign, like "+1.5".
+        amount: String,
+        /// The url of the unit entity, or "1" if there is none.
+        unit: String,
+        upper_bound: Option<String>,
+        lower_bound: Option<String>,
+    },
+    GlobeCoordinate {
+        latitude: f64,
+        longitude: f64,
+        precision: Option<f64>,
+        /// The url of the globe entity.
+        globe: String,
+    },
+    /// Any other type, as returned by the api.
+    Other(serde_json::Value),
+}
+
+impl DataValue {
+    fn from_value(datavalue: &serde_json::Value) -> DataValue {
+        let value = datavalue.get("value").cloned().unwrap_or(serde_json::Value::Null);
+        let string = |key: &str| value.get(key).and_then(|x| x.as_str()).map(|x| x.to_owned());
+        let parsed = match datavalue.get("type").and_then(|x| x.as_str()) {
+            Some("string") => value.as_str().map(|x| DataValue::String(x.to_owned())),
+            Some("monolingualtext") => string("text").map(|text| DataValue::MonolingualText {
+                text,
+                language: string("language").unwrap_or_default(),
+            }),
+            Some("wikibase-entityid") => string("id").map(DataValue::EntityId),
+            Some("time") => string("time").map(|time| DataValue::Time {
+                time,
+                timezone: value.get("timezone").and_then(|x| x.as_i64()).unwrap_or(0),
+                precision: value.get("precision").and_then(|x| x.as_u64()).unwrap_or(0) as u8,
+                calendarmodel: string("calendarmodel").unwrap_or_default(),
+            }),
+            Some("quantity") => string("amount").map(|amount| DataValue::Quantity {
+                amount,
+                unit: string("unit").unwrap_or_else(|| "1".to_owned()),
+                upper_bound: string("upperBound"),
+                lower_bound: string("lowerBound"),
+            }),
+            Some("globecoordinate") => value.get("latitude").and_then(|x| x.as_f64()).and_then(|latitude| Some(DataValue::GlobeCoordinate {
+                latitude,
+                longitude: value.get("longitude")?.as_f64()?,
+                precision: value.get("precision").and_then(|x| x.as_f64()),
+                globe: string("globe").unwrap_or_default(),
+            })),
+            _ => None,
+        };
+        parsed.unwrap_or(DataValue::Other(value))
+    }
+}
+
+/// An entity found by `Wikidata::search_entities`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySearchResult {
+    pub id: String,
+    pub label: Option<String>,
+    pub description: Option<String>,
+    /// The concept url, like "http://www.wikidata.org/entity/Q42".
+    pub url: Option<String>,
+}
+
+/// Access to Wikidata, sharing the client of a `Wikipedia`. Created with
+/// `Wikipedia::wikidata`.
+#[derive(Debug)]
+pub struct Wikidata<'a, A: 'a + http::HttpClient> {
+    wikipedia: &'a Wikipedia<A>,
+    /// The api url.
+    pub base_url: String,
+    /// The language used when searching.
+    pub language: String,
+    /// Number of results to fetch when searching.
+    pub search_results: u32,
+}
+
+impl<'a, A: http::HttpClient> Wikidata<'a, A> {
+    /// Creates a new object using the client of `wikipedia` and default
+    /// values.
+    pub fn new(wikipedia: &'a Wikipedia<A>) -> Self {
+        Wikidata {
+            wikipedia,
+            base_url: "https://www.wikidata.org/w/api.php".to_owned(),
+            language: "en".to_owned(),
+            search_results: 10,
+        }
+    }
+
+    /// Updates the api url, for example to use a mirror.
+    pub fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = base_url.to_owned();
+    }
+
+    async fn query<'b, I>(&self, args: I) -> Result<serde_json::Value>
+            where I: Iterator<Item=(&'b str, &'b str)> {
+        self.wikipedia.query_url(&self.base_url, args).await
+    }
+
+    /// Fetches the entity with id `id`, for example "Q42". Returns `None` if
+    /// it does not exist.
+    pub async fn get_entity(&self, id: &str) -> Result<Option<Entity>> {
+        let q = self.query(vec![
+            ("action", "wbgetentities"),
+            ("ids", id),
+            ("props", "info|labels|descriptions|aliases|claims|datatype|sitelinks/urls"),
+            ("format", "json"),
+        ].into_iter()).await?;
+
+        let entity = q
+            .as_object()
+            .and_then(|x| x.get("entities"))
+            .and_then(|x| x.as_object())
+            .and_then(|x| x.values().next())
+            .ok_or(Error::JSONPathError)?;
+        if entity.get("missing").is_some() {
+            return Ok(None);
+        }
+        Entity::from_value(entity).ok_or(Error::JSONPathError).map(Some)
+    }
+
+    /// Searches for items whose label or alias match `search`.
+    pub async fn search_entities(&self, search: &str) -> Result<Vec<EntitySearchResult>> {
+        let limit = self.search_results.to_string();
+        let q = self.query(vec![
+            ("action", "wbsearchentities"),
+            ("search", search),
+            ("language", &*self.language),
+            ("type", "item"),
+            ("limit", &*limit),
+            ("format", "json"),
+        ].into_iter()).await?;
+
+        Ok(q
+            .as_object()
+            .and_then(|x| x.get("search"))
+            .and_then(|x| x.as_array())
+            .ok_or(Error::JSONPathError)?
+            .iter()
+            .filter_map(|x| {
+                let string = |key: &str| x.get(key).and_then(|x| x.as_str()).map(|x| x.to_owned());
+                Some(EntitySearchResult {
+                    id: string("id")?,
+                    label: string("label"),
+                    description: string("description"),
+                    url: string("concepturi"),
+                })
+            })
+            .collect())
+    }
+
+    /// Fetches the entity `page` is about, if it has one.
+    pub async fn get_page_entity<B: http::HttpClient>(&self, page: &Page<'_, B>) -> Result<Option<Entity>> {
+        match page.get_wikidata_id().await? {
+            Some(id) => self.get_entity(&id).await,
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Claim, DataValue, Entity, Snak, SnakValue};
+
+    #[test]
+    fn entity_from_value() {
+        let value: serde_json::Value = serde_json::from_str(r#"{
+            "id": "Q42",
+            "labels": {"en": {"language": "en", "value": "Douglas Adams"}},
+            "descriptions": {"en": {"language": "en", "value": "English writer"}},
+            "aliases": {"en": [{"language": "en", "value": "Douglas Noel Adams"}]},
+            "sitelinks": {"enwiki": {"site": "enwiki", "title": "Douglas Adams", "badges": [], "url": "https://en.wikipedia.org/wiki/Douglas_Adams"}},
+            "claims": {
+                "P31": [{"id": "a", "rank": "normal", "mainsnak": {"snaktype": "value", "property": "P31", "datatype": "wikibase-item", "datavalue": {"type": "wikibase-entityid", "value": {"entity-type": "item", "numeric-id": 5, "id": "Q5"}}}}],
+                "P569": [{"id": "b", "rank": "normal", "mainsnak": {"snaktype": "value", "property": "P569", "datatype": "time", "datavalue": {"type": "time", "value": {"time": "+1952-03-11T00:00:00Z", "timezone": 0, "before": 0, "after": 0, "precision": 11, "calendarmodel": "http://www.wikidata.org/entity/Q1985727"}}}}],
+                "P2048": [{"id": "c", "rank": "preferred", "mainsnak": {"snaktype": "value", "property": "P2048", "datatype": "quantity", "datavalue": {"type": "quantity", "value": {"amount": "+1.96", "unit": "http://www.wikidata.org/entity/Q11573"}}}, "qualifiers": {"P585": [{"snaktype": "novalue", "property": "P585"}]}}],
+                "P625": [{"id": "d", "rank": "normal", "mainsnak": {"snaktype": "value", "property": "P625", "datatype": "globe-coordinate", "datavalue": {"type": "globecoordinate", "value": {"latitude": 51.5, "longitude": -0.1, "precision": 0.01, "globe": "http://www.wikidata.org/entity/Q2"}}}}],
+                "P1": [{"id": "e", "rank": "normal", "mainsnak": {"snaktype": "somevalue", "property": "P1"}}]
+            }
+        }"#).unwrap();
+        let entity = Entity::from_value(&value).unwrap();
+        assert_eq!(entity.label("en"), Some("Douglas Adams"));
+        assert_eq!(entity.description("en"), Some("English writer"));
+        assert_eq!(entity.aliases["en"], vec!["Douglas Noel Adams".to_owned()]);
+        assert_eq!(entity.sitelinks["enwiki"].title, "Douglas Adams");
+        assert_eq!(entity.sitelinks["enwiki"].url.as_deref(), Some("https://en.wikipedia.org/wiki/Douglas_Adams"));
+        assert_eq!(entity.values("P31"), vec![&DataValue::EntityId("Q5".to_owned())]);
+        assert_eq!(entity.values("P569"), vec![&DataValue::Time {
+            time: "+1952-03-11T00:00:00Z".to_owned(),
+            timezone: 0,
+            precision: 11,
+            calendarmodel: "http://www.wikidata.org/entity/Q1985727".to_owned(),
+        }]);
+        assert_eq!(entity.claims["P2048"], vec![Claim {
+            id: "c".to_owned(),
+            rank: "preferred".to_owned(),
+            mainsnak: Snak {
+                property: "P2048".to_owned(),
+                datatype: Some("quantity".to_owned()),
+                value: SnakValue::Value(DataValue::Quantity {
+                    amount: "+1.96".to_owned(),
+                    unit: "http://www.wikidata.org/entity/Q11573".to_owned(),
+                    upper_bound: None,
+                    lower_bound: None,
+                }),
+            },
+            qualifiers: vec![("P585".to_owned(), vec![Snak {
+                property: "P585".to_owned(),
+                datatype: None,
+                value: SnakValue::NoValue,
+            }])].into_iter().collect(),
+        }]);
+        assert_eq!(entity.values("P625"), vec![&DataValue::GlobeCoordinate {
+            latitude: 51.5,
+            longitude: -0.1,
+            precision: Some(0.01),
+            globe: "http://www.wikidata.org/entity/Q2".to_owned(),
+        }]);
+        assert!(entity.values("P1").is_empty());
+        assert_eq!(entity.claims["P1"][0].mainsnak.value, SnakValue::SomeValue);
+    }
+}
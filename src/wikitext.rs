@@ -0,0 +1,1513 @@
+//! An offline wikitext parser.
+//!
+//! `parse` turns wikitext into a tree of `Node`s, and `to_wikitext` turns the
+//! tree back into the exact source, so the tree can be inspected or changed
+//! and written back. Anything the parser does not understand is kept as
+//! `Node::Text`.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use crate::citation::{attribute, find_ignore_case};
+
+/// A piece of wikitext.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Text(String),
+    Heading(Heading),
+    /// Consecutive lines of text, with the line breaks between them.
+    Paragraph(Vec<Node>),
+    /// Consecutive list lines.
+    List(Vec<ListItem>),
+    Table(Table),
+    /// Text between `'''`.
+    Bold(Vec<Node>),
+    /// Text between `''`.
+    Italic(Vec<Node>),
+    InternalLink(InternalLink),
+    ExternalLink(ExternalLink),
+    Template(Template),
+    ParserFunction(ParserFunction),
+    /// A template parameter like `{{{1|default}}}`, found in template source.
+    Argument(Argument),
+    Ref(Ref),
+    /// An extension tag whose content is not wikitext, like `<math>` or
+    /// `<syntaxhighlight>`.
+    Tag(Tag),
+    /// The text of an html comment, without `<!--` and `-->`.
+    Comment(String),
+    /// The text of a `<nowiki>` tag.
+    Nowiki(String),
+}
+
+/// A `== Heading ==` line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    /// From 1 to 6, the number of `=` on each side.
+    pub level: usize,
+    /// The text between the `=`, including the spaces around it.
+    pub content: Vec<Node>,
+    /// Whitespace after the closing `=`.
+    pub trailing: String,
+}
+
+/// A line of a list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListItem {
+    /// The `*`, `#`, `:` and `;` at the start of the line, one per level.
+    pub marker: String,
+    pub content: Vec<Node>,
+}
+
+impl ListItem {
+    /// The nesting level of the item, starting at 1.
+    pub fn depth(&self) -> usize {
+        self.marker.len()
+    }
+
+    /// Whether the item belongs to a numbered list.
+    pub fn ordered(&self) -> bool {
+        self.marker.ends_with('#')
+    }
+}
+
+/// A `{| ... |}` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    /// The html attributes after `{|`.
+    pub attributes: String,
+    pub caption: Option<TableCaption>,
+    pub rows: Vec<TableRow>,
+}
+
+/// The `|+` line of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableCaption {
+    pub attributes: Option<String>,
+    pub content: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRow {
+    /// The html attributes after `|-`, `None` for a first row with no `|-`.
+    pub attributes: Option<String>,
+    pub cells: Vec<TableCell>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableCell {
+    /// Whether it is a `!` header cell.
+    pub header: bool,
+    /// The `|` or `!` starting the cell on a new line, or the `||` or `!!`
+    /// separating it from the previous cell on the same line.
+    pub marker: String,
+    /// The html attributes before a single `|`, like `rowspan="2"`.
+    pub attributes: Option<String>,
+    pub content: Vec<Node>,
+}
+
+/// A `[[target|text]]trail` link.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InternalLink {
+    /// The linked page, as written.
+    pub target: String,
+    /// Everything after the first `|`, which for files includes the options
+    /// and the caption.
+    pub text: Option<Vec<Node>>,
+    /// The letters right after `]]`, shown as part of the link.
+    pub trail: String,
+}
+
+/// A `[url text]` link or a bare url.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalLink {
+    pub url: String,
+    /// The text after the first space.
+    pub text: Option<Vec<Node>>,
+    /// Whether the link is between brackets.
+    pub bracketed: bool,
+}
+
+impl InternalLink {
+    /// The linked page, without the `#` section, the leading `:` and
+    /// surrounding whitespace, and with its first letter in uppercase as
+    /// in titles. `None` for links to a section of the same page.
+    pub fn title(&self) -> Option<String> {
+        let target = self.target.trim().trim_start_matches(':').trim_start();
+        let target = target.split('#').next().unwrap_or("").trim().replace('_', " ");
+        let mut chars = target.chars();
+        let first = chars.next()?;
+        Some(first.to_uppercase().chain(chars).collect())
+    }
+
+    /// Whether the link shows a file, like `[[File:Example.jpg|thumb]]`.
+    pub fn is_media(&self) -> bool {
+        let target = self.target.trim_start().to_lowercase();
+        target.starts_with("file:") || target.starts_with("image:")
+    }
+
+    /// Whether the link adds the page to a category instead of being shown.
+    pub fn is_category(&self) -> bool {
+        self.target.trim_start().to_lowercase().starts_with("category:")
+    }
+}
+
+/// A `{{name|param|key=value}}` template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    /// The name as written, including spaces and line breaks.
+    pub name: String,
+    pub params: Vec<TemplateParam>,
+}
+
+impl Template {
+    /// The name without surrounding whitespace, with underscores as spaces.
+    pub fn name(&self) -> String {
+        self.name.trim().replace('_', " ")
+    }
+
+    /// Finds a parameter by name, or by position for a number like "1",
+    /// counting only the positional parameters. The last one wins when
+    /// there are duplicates, as in MediaWiki.
+    pub fn get(&self, key: &str) -> Option<&TemplateParam> {
+        let mut position = 0;
+        let mut found = None;
+        for param in &self.params {
+            let name = match param.name() {
+                Some(name) => name.to_owned(),
+                None => {
+                    position += 1;
+                    position.to_string()
+                },
+            };
+            if name == key {
+                found = Some(param);
+            }
+        }
+        found
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateParam {
+    /// The name as written before `=`, `None` for positional parameters.
+    pub name: Option<String>,
+    pub value: Vec<Node>,
+}
+
+impl TemplateParam {
+    /// The name without surrounding whitespace.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(|x| x.trim())
+    }
+}
+
+/// A `{{#name: arg | arg}}` parser function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserFunction {
+    /// The name as written before `:`, like "#if".
+    pub name: String,
+    /// The arguments, the first one being the text after `:`.
+    pub args: Vec<Vec<Node>>,
+}
+
+impl ParserFunction {
+    /// The name without surrounding whitespace.
+    pub fn name(&self) -> &str {
+        self.name.trim()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument {
+    pub name: String,
+    pub default: Option<Vec<Node>>,
+}
+
+/// A `<ref>` tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ref {
+    /// The tag name as written, usually "ref".
+    pub tag: String,
+    /// The attributes as written, including the leading space.
+    pub attributes: String,
+    /// The content, `None` for `<ref name="x" />`.
+    pub content: Option<Vec<Node>>,
+}
+
+impl Ref {
+    /// The value of the `name` attribute.
+    pub fn name(&self) -> Option<String> {
+        attribute(&self.attributes, "name")
+    }
+}
+
+/// An extension tag like `<math>`, with its content unparsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    /// The tag name as written.
+    pub name: String,
+    /// The attributes as written, including the leading space.
+    pub attributes: String,
+    /// The content, `None` for self-closing tags.
+    pub content: Option<String>,
+}
+
+/// Tags whose content is kept as it is.
+const RAW_TAGS: &[&str] = &[
+    "nowiki", "pre", "math", "chem", "ce", "syntaxhighlight", "source", "score", "timeline",
+    "graph", "mapframe", "maplink", "templatedata", "hiero", "gallery", "references", "poem",
+    "imagemap", "inputbox", "categorytree", "includeonly", "noinclude", "onlyinclude", "section",
+];
+
+/// Parser functions that do not start with `#`.
+const PARSER_FUNCTIONS: &[&str] = &[
+    "lc", "uc", "lcfirst", "ucfirst", "urlencode", "anchorencode", "formatnum", "padleft",
+    "padright", "plural", "grammar", "gender", "int", "ns", "nse", "fullurl", "localurl",
+    "canonicalurl", "filepath", "displaytitle", "defaultsort", "msgnw",
+];
+
+/// Protocols of bracketed external links.
+const PROTOCOLS: &[&str] = &[
+    "http://", "https://", "ftp://", "ftps://", "sftp://", "git://", "irc://", "ircs://",
+    "news:", "mailto:", "//",
+];
+
+/// Protocols of bare urls in the text.
+const BARE_PROTOCOLS: &[&str] = &["http://", "https://", "ftp://"];
+
+/// Parses `text` into a tree of nodes.
+pub fn parse(text: &str) -> Vec<Node> {
+    UNCLOSED.with(|x| x.borrow_mut().clear());
+    let nodes = parse_blocks(text);
+    UNCLOSED.with(|x| x.borrow_mut().clear());
+    nodes
+}
+
+/// Writes `nodes` back as wikitext.
+pub fn to_wikitext(nodes: &[Node]) -> String {
+    let mut text = String::new();
+    for node in nodes {
+        node.write(&mut text);
+    }
+    text
+}
+
+/// Returns `nodes` and all the nodes inside them, in the order they are
+/// written.
+pub fn descendants(nodes: &[Node]) -> Vec<&Node> {
+    let mut found = Vec::new();
+    let mut stack: Vec<&Node> = nodes.iter().rev().collect();
+    while let Some(node) = stack.pop() {
+        found.push(node);
+        for children in node.children().into_iter().rev() {
+            stack.extend(children.iter().rev());
+        }
+    }
+    found
+}
+
+/// Returns the text of `nodes` as a reader would see it, without markup,
+/// references, comments or templates, except for the templates commonly
+/// used for dates, lists, units and formatting.
+pub fn plain_text(nodes: &[Node]) -> String {
+    let mut text = String::new();
+    for node in nodes {
+        node.write_plain(&mut text);
+    }
+    clean_whitespace(&text)
+}
+
+impl Node {
+    /// Returns the text of the node as a reader would see it, see
+    /// `plain_text`.
+    pub fn plain_text(&self) -> String {
+        plain_text(std::slice::from_ref(self))
+    }
+
+    /// The lists of nodes directly inside this node.
+    pub fn children(&self) -> Vec<&[Node]> {
+        match *self {
+            Node::Heading(ref heading) => vec![&heading.content],
+            Node::Paragraph(ref content) | Node::Bold(ref content) | Node::Italic(ref content) => vec![content],
+            Node::List(ref items) => items.iter().map(|x| &*x.content).collect(),
+            Node::Table(ref table) => table.caption
+                .iter()
+                .map(|x| &*x.content)
+                .chain(table.rows.iter().flat_map(|x| x.cells.iter().map(|x| &*x.content)))
+                .collect(),
+            Node::InternalLink(InternalLink { text: Some(ref text), .. }) |
+                Node::ExternalLink(ExternalLink { text: Some(ref text), .. }) |
+                Node::Argument(Argument { default: Some(ref text), .. }) |
+                Node::Ref(Ref { content: Some(ref text), .. }) => vec![text],
+            Node::Template(ref template) => template.params.iter().map(|x| &*x.value).collect(),
+            Node::ParserFunction(ref function) => function.args.iter().map(|x| &**x).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn write_plain(&self, out: &mut String) {
+        let nodes = |out: &mut String, nodes: &[Node]| for node in nodes {
+            node.write_plain(out);
+        };
+        match *self {
+            Node::Text(ref text) => out.push_str(&decode_entities(&strip_html(text))),
+            Node::Heading(ref heading) => nodes(out, &heading.content),
+            Node::Paragraph(ref content) | Node::Bold(ref content) | Node::Italic(ref content) => nodes(out, content),
+            Node::List(ref items) => for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push('\n');
+                }
+                nodes(out, &item.content);
+            },
+            Node::Table(ref table) => {
+                if let Some(ref caption) = table.caption {
+                    nodes(out, &caption.content);
+                }
+                for row in &table.rows {
+                    out.push('\n');
+                    for (i, cell) in row.cells.iter().enumerate() {
+                        if i > 0 {
+                            out.push('\t');
+                        }
+                        out.push_str(&plain_text(&cell.content).replace('\n', " "));
+                    }
+                }
+            },
+            Node::InternalLink(ref link) => {
+                if link.is_media() || link.is_category() {
+                    return;
+                }
+                match link.text {
+                    Some(ref text) => nodes(out, text),
+                    None => out.push_str(link.target.trim().trim_start_matches(':')),
+                }
+                out.push_str(&link.trail);
+            },
+            Node::ExternalLink(ref link) => match link.text {
+                Some(ref text) => nodes(out, text),
+                None if !link.bracketed => out.push_str(&link.url),
+                None => (),
+            },
+            Node::Template(ref template) => template.write_plain(out),
+            Node::Argument(Argument { default: Some(ref default), .. }) => nodes(out, default),
+            Node::Tag(Tag { ref name, content: Some(ref content), .. }) if ["pre", "poem"].contains(&&*name.to_lowercase()) => {
+                out.push_str(&decode_entities(content));
+            },
+            Node::Nowiki(ref text) => out.push_str(&decode_entities(text)),
+            _ => (),
+        }
+    }
+
+    /// Writes the node back as wikitext.
+    pub fn to_wikitext(&self) -> String {
+        let mut text = String::new();
+        self.write(&mut text);
+        text
+    }
+
+    fn write(&self, out: &mut String) {
+        let nodes = |out: &mut String, nodes: &[Node]| for node in nodes {
+            node.write(out);
+        };
+        match *self {
+            Node::Text(ref text) => out.push_str(text),
+            Node::Heading(ref heading) => {
+                let marks = "=".repeat(heading.level);
+                out.push_str(&marks);
+                nodes(out, &heading.content);
+                out.push_str(&marks);
+                out.push_str(&heading.trailing);
+            },
+            Node::Paragraph(ref content) => nodes(out, content),
+            Node::List(ref items) => for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push('\n');
+                }
+                out.push_str(&item.marker);
+                nodes(out, &item.content);
+            },
+            Node::Table(ref table) => {
+                out.push_str("{|");
+                out.push_str(&table.attributes);
+                if let Some(ref caption) = table.caption {
+                    out.push_str("\n|+");
+                    if let Some(ref attributes) = caption.attributes {
+                        out.push_str(attributes);
+                        out.push('|');
+                    }
+                    nodes(out, &caption.content);
+                }
+                for row in &table.rows {
+                    if let Some(ref attributes) = row.attributes {
+                        out.push_str("\n|-");
+                        out.push_str(attributes);
+                    }
+                    for cell in &row.cells {
+                        if cell.marker.len() == 1 {
+                            out.push('\n');
+                        }
+                        out.push_str(&cell.marker);
+                        if let Some(ref attributes) = cell.attributes {
+                            out.push_str(attributes);
+                            out.push('|');
+                        }
+                        nodes(out, &cell.content);
+                    }
+                }
+                out.push_str("\n|}");
+            },
+            Node::Bold(ref content) => {
+                out.push_str("'''");
+                nodes(out, content);
+                out.push_str("'''");
+            },
+            Node::Italic(ref content) => {
+                out.push_str("''");
+                nodes(out, content);
+                out.push_str("''");
+            },
+            Node::InternalLink(ref link) => {
+                out.push_str("[[");
+                out.push_str(&link.target);
+                if let Some(ref text) = link.text {
+                    out.push('|');
+                    nodes(out, text);
+                }
+                out.push_str("]]");
+                out.push_str(&link.trail);
+            },
+            Node::ExternalLink(ref link) => {
+                if link.bracketed {
+                    out.push('[');
+                }
+                out.push_str(&link.url);
+                if let Some(ref text) = link.text {
+                    out.push(' ');
+                    nodes(out, text);
+                }
+                if link.bracketed {
+                    out.push(']');
+                }
+            },
+            Node::Template(ref template) => {
+                out.push_str("{{");
+                out.push_str(&template.name);
+                for param in &template.params {
+                    out.push('|');
+                    if let Some(ref name) = param.name {
+                        out.push_str(name);
+                        out.push('=');
+                    }
+                    nodes(out, &param.value);
+                }
+                out.push_str("}}");
+            },
+            Node::ParserFunction(ref function) => {
+                out.push_str("{{");
+                out.push_str(&function.name);
+                out.push(':');
+                for (i, arg) in function.args.iter().enumerate() {
+                    if i > 0 {
+                        out.push('|');
+                    }
+                    nodes(out, arg);
+                }
+                out.push_str("}}");
+            },
+            Node::Argument(ref argument) => {
+                out.push_str("{{{");
+                out.push_str(&argument.name);
+                if let Some(ref default) = argument.default {
+                    out.push('|');
+                    nodes(out, default);
+                }
+                out.push_str("}}}");
+            },
+            Node::Ref(ref r) => {
+                out.push('<');
+                out.push_str(&r.tag);
+                out.push_str(&r.attributes);
+                match r.content {
+                    Some(ref content) => {
+                        out.push('>');
+                        nodes(out, content);
+                        out.push_str("</");
+                        out.push_str(&r.tag);
+                        out.push('>');
+                    },
+                    None => out.push_str("/>"),
+                }
+            },
+            Node::Tag(ref tag) => {
+                out.push('<');
+                out.push_str(&tag.name);
+                out.push_str(&tag.attributes);
+                match tag.content {
+                    Some(ref content) => {
+                        out.push('>');
+                        out.push_str(content);
+                        out.push_str("</");
+                        out.push_str(&tag.name);
+                        out.push('>');
+                    },
+                    None => out.push_str("/>"),
+                }
+            },
+            Node::Comment(ref text) => {
+                out.push_str("<!--");
+                out.push_str(text);
+                out.push_str("-->");
+            },
+            Node::Nowiki(ref text) => {
+                out.push_str("<nowiki>");
+                out.push_str(text);
+                out.push_str("</nowiki>");
+            },
+        }
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July", "August", "September",
+    "October", "November", "December",
+];
+
+impl Template {
+    fn write_plain(&self, out: &mut String) {
+        let positional: Vec<String> = self.params
+            .iter()
+            .filter(|x| x.name.is_none())
+            .map(|x| plain_text(&x.value))
+            .collect();
+        let arg = |n: usize| positional.get(n).map_or("", |x| &**x);
+        let name = self.name().to_lowercase();
+        match &*name {
+            "nowrap" | "nobr" | "small" | "smaller" | "big" | "larger" | "nobold" | "noitalic" | "abbr" |
+                "plainlist" | "flatlist" | "nowrap begin" => out.push_str(arg(0)),
+            "lang" => out.push_str(arg(1)),
+            "url" => out.push_str(if positional.len() > 1 { arg(1) } else { arg(0) }),
+            "convert" | "cvt" => {
+                out.push_str(arg(0));
+                out.push(' ');
+                out.push_str(arg(1));
+            },
+            "ubl" | "unbulleted list" | "hlist" | "bulleted list" | "bulleted" | "ordered list" => out.push_str(&positional.join("\n")),
+            "birth date" | "death date" | "birth date and age" | "death date and age" | "start date" |
+                "end date" | "start date and age" | "film date" | "dob" | "bda" => {
+                let day_first = self.get("df").is_some_and(|x| plain_text(&x.value).starts_with('y'));
+                out.push_str(&format_date(arg(0), arg(1), arg(2), day_first));
+            },
+            "marriage" => {
+                out.push_str(arg(0));
+                if !arg(1).is_empty() {
+                    out.push_str(" (m. ");
+                    out.push_str(arg(1));
+                    out.push(')');
+                }
+            },
+            "'" => out.push('\''),
+            "!" => out.push('|'),
+            "=" => out.push('='),
+            "nbsp" => out.push(' '),
+            "ndash" => out.push('–'),
+            "mdash" => out.push('—'),
+            "snd" | "spaced ndash" => out.push_str(" – "),
+            "·" | "dot" | "middot" => out.push_str(" · "),
+            _ if name.starts_with("lang-") => out.push_str(arg(0)),
+            _ => (),
+        }
+    }
+}
+
+/// Formats the date of templates like `{{birth date|1952|3|11}}`.
+fn format_date(year: &str, month: &str, day: &str, day_first: bool) -> String {
+    let month_name = month.parse::<usize>().ok().and_then(|x| MONTHS.get(x.wrapping_sub(1)));
+    match (month_name, day.parse::<u32>()) {
+        (Some(month), Ok(day)) if day_first => format!("{} {} {}", day, month, year),
+        (Some(month), Ok(day)) => format!("{} {}, {}", month, day, year),
+        (Some(month), _) => format!("{} {}", month, year),
+        _ => year.to_owned(),
+    }
+}
+
+/// Removes the html tags in `text`, turning line breaks into new lines.
+pub(crate) fn strip_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('<') {
+        out.push_str(&rest[..i]);
+        let tag = &rest[i + 1..];
+        let is_tag = tag.trim_start_matches('/').starts_with(|c: char| c.is_ascii_alphabetic());
+        match tag.find('>') {
+            Some(end) if is_tag => {
+                let name = tag.trim_start_matches('/');
+                let name = &name[..name.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(name.len())];
+                if name.eq_ignore_ascii_case("br") {
+                    out.push('\n');
+                }
+                rest = &tag[end + 1..];
+            },
+            _ => {
+                out.push('<');
+                rest = tag;
+            },
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Replaces the html entities of `text` with the characters they stand for.
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let end = rest.bytes().take(12).position(|x| x == b';');
+        let decoded = end.and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "nbsp" | "ensp" | "emsp" | "thinsp" => ' ',
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "ndash" => '–',
+                "mdash" => '—',
+                "minus" => '−',
+                "times" => '×',
+                "deg" => '°',
+                "middot" => '·',
+                "hellip" => '…',
+                _ => {
+                    let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => entity.strip_prefix('#').and_then(|x| x.parse().ok()),
+                    };
+                    code.and_then(char::from_u32)?
+                },
+            };
+            Some((c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            },
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Joins the spaces inside each line, trims the lines, removes the list
+/// markers at their start and keeps at most one empty line in a row.
+pub(crate) fn clean_whitespace(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_start().trim_start_matches(['*', '#', ':', ';']);
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() && lines.last().is_none_or(|x| x.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|x| x.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// The length in bytes of the character at `i`.
+fn char_len(s: &str, i: usize) -> usize {
+    s[i..].chars().next().map_or(1, |c| c.len_utf8())
+}
+
+/// Joins adjacent text nodes.
+fn merge_text(nodes: Vec<Node>) -> Vec<Node> {
+    let mut merged: Vec<Node> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        if let Node::Text(ref text) = node {
+            if text.is_empty() {
+                continue;
+            }
+            if let Some(Node::Text(ref mut last)) = merged.last_mut() {
+                last.push_str(text);
+                continue;
+            }
+        }
+        merged.push(node);
+    }
+    merged
+}
+
+/// An extension tag found in the text.
+struct TagParts<'t> {
+    name: &'t str,
+    attributes: &'t str,
+    content: Option<&'t str>,
+    end: usize,
+}
+
+/// Reads a `<ref>` or raw extension tag starting at `i`.
+fn tag_parts(s: &str, i: usize) -> Option<TagParts<'_>> {
+    let rest = &s[i + 1..];
+    let name_len = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+    let name = &rest[..name_len];
+    let lower = name.to_ascii_lowercase();
+    if lower != "ref" && !RAW_TAGS.contains(&&*lower) {
+        return None;
+    }
+    let after_name = i + 1 + name_len;
+    if !matches!(s[after_name..].chars().next(), Some(' ') | Some('\t') | Some('\n') | Some('>') | Some('/')) {
+        return None;
+    }
+    let close = after_name + s[after_name..].find('>')?;
+    if s[..close].ends_with('/') {
+        return Some(TagParts {
+            name,
+            attributes: &s[after_name..close - 1],
+            content: None,
+            end: close + 1,
+        });
+    }
+    let end_tag = format!("</{}>", lower);
+    let content_end = find_ignore_case(s, &end_tag, close + 1)?;
+    Some(TagParts {
+        name,
+        attributes: &s[after_name..close],
+        content: Some(&s[close + 1..content_end]),
+        end: content_end + end_tag.len(),
+    })
+}
+
+/// If a comment, template, link or tag starts at `i`, returns where it ends.
+fn skip_construct(s: &str, i: usize) -> Option<usize> {
+    let rest = &s[i..];
+    if let Some(comment) = rest.strip_prefix("<!--") {
+        comment.find("-->").map(|x| i + 4 + x + 3)
+    } else if rest.starts_with("{{") {
+        skip_braces(s, i)
+    } else if rest.starts_with("[[") {
+        skip_link(s, i)
+    } else if rest.starts_with('<') {
+        tag_parts(s, i).map(|x| x.end)
+    } else {
+        None
+    }
+}
+
+fn skip_braces(s: &str, i: usize) -> Option<usize> {
+    if s[i..].starts_with("{{{") {
+        if let Some(end) = skip_argument(s, i) {
+            return Some(end);
+        }
+    }
+    skip_template(s, i)
+}
+
+thread_local! {
+    /// The positions from which the end of a template, argument or link
+    /// was already looked for and not found while parsing, by kind,
+    /// position and end of the text. Without it, the rest of the text is
+    /// scanned again from every nested opening bracket, which takes
+    /// exponential time.
+    static UNCLOSED: RefCell<HashSet<(&'static str, usize, usize)>> = RefCell::new(HashSet::new());
+}
+
+/// Positions are kept as addresses, since parts of the text are parsed as
+/// slices of it.
+fn unclosed_key(kind: &'static str, s: &str, j: usize) -> (&'static str, usize, usize) {
+    (kind, s.as_ptr() as usize + j, s.as_ptr() as usize + s.len())
+}
+
+/// Whether looking for the end of a `kind` construct from `j` already failed.
+fn is_unclosed(kind: &'static str, s: &str, j: usize) -> bool {
+    UNCLOSED.with(|x| x.borrow().contains(&unclosed_key(kind, s, j)))
+}
+
+/// Records that looking for the end of a construct failed from each of
+/// `visited`.
+fn set_unclosed(s: &str, visited: Vec<(&'static str, usize)>) {
+    UNCLOSED.with(|x| x.borrow_mut().extend(visited.into_iter().map(|(kind, j)| unclosed_key(kind, s, j))));
+}
+
+fn skip_template(s: &str, i: usize) -> Option<usize> {
+    let mut j = i + 2;
+    let mut visited = Vec::new();
+    while j < s.len() && !is_unclosed("template", s, j) {
+        visited.push(("template", j));
+        if s[j..].starts_with("}}") {
+            return Some(j + 2);
+        }
+        match skip_construct(s, j) {
+            Some(end) => j = end,
+            None => j += char_len(s, j),
+        }
+    }
+    set_unclosed(s, visited);
+    None
+}
+
+fn skip_argument(s: &str, i: usize) -> Option<usize> {
+    let mut j = i + 3;
+    let mut visited = Vec::new();
+    while j < s.len() && !is_unclosed("argument", s, j) {
+        visited.push(("argument", j));
+        if s[j..].starts_with("}}}") {
+            return Some(j + 3);
+        }
+        if s[j..].starts_with("}}") {
+            break;
+        }
+        match skip_construct(s, j) {
+            Some(end) => j = end,
+            None => j += char_len(s, j),
+        }
+    }
+    set_unclosed(s, visited);
+    None
+}
+
+/// Finds the end of a `[[...]]` link, which cannot have a line break in its
+/// target.
+fn skip_link(s: &str, i: usize) -> Option<usize> {
+    let mut j = i + 2;
+    let mut kind = "link target";
+    let mut visited = Vec::new();
+    while j < s.len() && !is_unclosed(kind, s, j) {
+        visited.push((kind, j));
+        let rest = &s[j..];
+        if rest.starts_with("]]") {
+            return Some(j + 2);
+        }
+        if kind == "link target" {
+            if rest.starts_with('\n') {
+                break;
+            }
+            if rest.starts_with('|') {
+                kind = "link";
+            }
+        }
+        match skip_construct(s, j) {
+            Some(end) => j = end,
+            None => j += char_len(s, j),
+        }
+    }
+    set_unclosed(s, visited);
+    None
+}
+
+/// Splits `s` at each of `separators` outside of templates, links, tags
+/// and comments. Returns each part with the separator before it, which is
+/// empty for the first part.
+fn split_top<'t>(s: &'t str, separators: &[&'static str]) -> Vec<(&'static str, &'t str)> {
+    let mut parts = Vec::new();
+    let mut separator = "";
+    let mut start = 0;
+    let mut j = 0;
+    while j < s.len() {
+        if let Some(sep) = separators.iter().find(|x| s[j..].starts_with(**x)) {
+            parts.push((separator, &s[start..j]));
+            separator = sep;
+            j += sep.len();
+            start = j;
+            continue;
+        }
+        match skip_construct(s, j) {
+            Some(end) => j = end,
+            None => j += char_len(s, j),
+        }
+    }
+    parts.push((separator, &s[start..]));
+    parts
+}
+
+/// Finds the end of the line starting at `i`, skipping the line breaks
+/// inside templates, links, tags and comments.
+fn line_end(s: &str, i: usize) -> usize {
+    let mut j = i;
+    while j < s.len() {
+        if s[j..].starts_with('\n') {
+            return j;
+        }
+        match skip_construct(s, j) {
+            Some(end) => j = end,
+            None => j += char_len(s, j),
+        }
+    }
+    s.len()
+}
+
+enum Token {
+    Node(Node),
+    /// A run of apostrophes.
+    Quotes(usize),
+    Newline,
+}
+
+/// Parses text with no block structure, like the content of a link or a
+/// template parameter.
+fn parse_inline(s: &str) -> Vec<Node> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+    while i < s.len() {
+        let rest = &s[i..];
+        let parsed = if let Some(comment) = rest.strip_prefix("<!--") {
+            comment.find("-->").map(|x| (Node::Comment(comment[..x].to_owned()), i + 4 + x + 3))
+        } else if rest.starts_with('<') {
+            parse_tag(s, i)
+        } else if rest.starts_with("{{") {
+            parse_braces(s, i)
+        } else if rest.starts_with("[[") {
+            parse_internal_link(s, i)
+        } else if rest.starts_with('[') {
+            parse_external_link(s, i)
+        } else if BARE_PROTOCOLS.iter().any(|x| rest.get(..x.len()).is_some_and(|p| p.eq_ignore_ascii_case(x))) &&
+                !s[..i].ends_with(|c: char| c.is_alphanumeric()) {
+            parse_bare_url(s, i)
+        } else {
+            None
+        };
+        if let Some((node, end)) = parsed {
+            if !text.is_empty() {
+                tokens.push(Token::Node(Node::Text(std::mem::take(&mut text))));
+            }
+            tokens.push(Token::Node(node));
+            i = end;
+            continue;
+        }
+        if rest.starts_with("''") || rest.starts_with('\n') {
+            if !text.is_empty() {
+                tokens.push(Token::Node(Node::Text(std::mem::take(&mut text))));
+            }
+            if rest.starts_with('\n') {
+                tokens.push(Token::Newline);
+                i += 1;
+            } else {
+                let count = rest.len() - rest.trim_start_matches('\'').len();
+                tokens.push(Token::Quotes(count));
+                i += count;
+            }
+            continue;
+        }
+        let len = char_len(s, i);
+        text.push_str(&s[i..i + len]);
+        i += len;
+    }
+    if !text.is_empty() {
+        tokens.push(Token::Node(Node::Text(text)));
+    }
+    group_formatting(tokens)
+}
+
+/// Bold or italic text being read.
+struct Frame {
+    bold: bool,
+    nodes: Vec<Node>,
+}
+
+/// Turns the runs of apostrophes into bold and italic nodes. Formatting
+/// left open at the end of a line is kept as text.
+fn group_formatting(tokens: Vec<Token>) -> Vec<Node> {
+    fn current<'f>(root: &'f mut Vec<Node>, stack: &'f mut [Frame]) -> &'f mut Vec<Node> {
+        match stack.last_mut() {
+            Some(frame) => &mut frame.nodes,
+            None => root,
+        }
+    }
+    fn marks(bold: bool) -> &'static str {
+        if bold { "'''" } else { "''" }
+    }
+    fn toggle(root: &mut Vec<Node>, stack: &mut Vec<Frame>, bold: bool) {
+        if stack.last().is_some_and(|x| x.bold == bold) {
+            let frame = stack.pop().unwrap();
+            let node = if bold { Node::Bold(merge_text(frame.nodes)) } else { Node::Italic(merge_text(frame.nodes)) };
+            current(root, stack).push(node);
+        } else if stack.iter().any(|x| x.bold == bold) {
+            // Closing the outer formatting first is not supported
+            current(root, stack).push(Node::Text(marks(bold).to_owned()));
+        } else {
+            stack.push(Frame { bold, nodes: Vec::new() });
+        }
+    }
+    fn unwind(root: &mut Vec<Node>, stack: &mut Vec<Frame>) {
+        while let Some(frame) = stack.pop() {
+            let parent = current(root, stack);
+            parent.push(Node::Text(marks(frame.bold).to_owned()));
+            parent.extend(frame.nodes);
+        }
+    }
+
+    let mut root = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Node(node) => current(&mut root, &mut stack).push(node),
+            Token::Newline => {
+                unwind(&mut root, &mut stack);
+                root.push(Node::Text("\n".to_owned()));
+            },
+            Token::Quotes(mut count) => {
+                let extra = match count {
+                    4 => 1,
+                    c if c > 5 => c - 5,
+                    _ => 0,
+                };
+                if extra > 0 {
+                    current(&mut root, &mut stack).push(Node::Text("'".repeat(extra)));
+                    count -= extra;
+                }
+                match count {
+                    2 => toggle(&mut root, &mut stack, false),
+                    3 => toggle(&mut root, &mut stack, true),
+                    _ => {
+                        let bold_open = stack.iter().any(|x| x.bold);
+                        let italic_open = stack.iter().any(|x| !x.bold);
+                        if bold_open && italic_open {
+                            let top = stack.last().is_some_and(|x| x.bold);
+                            toggle(&mut root, &mut stack, top);
+                            toggle(&mut root, &mut stack, !top);
+                        } else if bold_open {
+                            toggle(&mut root, &mut stack, true);
+                            toggle(&mut root, &mut stack, false);
+                        } else {
+                            toggle(&mut root, &mut stack, false);
+                            toggle(&mut root, &mut stack, true);
+                        }
+                    },
+                }
+            },
+        }
+    }
+    unwind(&mut root, &mut stack);
+    merge_text(root)
+}
+
+fn parse_tag(s: &str, i: usize) -> Option<(Node, usize)> {
+    let parts = tag_parts(s, i)?;
+    let node = if parts.name.eq_ignore_ascii_case("ref") {
+        Node::Ref(Ref {
+            tag: parts.name.to_owned(),
+            attributes: parts.attributes.to_owned(),
+            content: parts.content.map(parse_inline),
+        })
+    } else if parts.name == "nowiki" && parts.attributes.is_empty() && parts.content.is_some() {
+        Node::Nowiki(parts.content.unwrap_or("").to_owned())
+    } else {
+        Node::Tag(Tag {
+            name: parts.name.to_owned(),
+            attributes: parts.attributes.to_owned(),
+            content: parts.content.map(|x| x.to_owned()),
+        })
+    };
+    // The closing tag is written in the case of the opening one
+    let written = node.to_wikitext();
+    if written != s[i..parts.end] {
+        return None;
+    }
+    Some((node, parts.end))
+}
+
+fn parse_braces(s: &str, i: usize) -> Option<(Node, usize)> {
+    if s[i..].starts_with("{{{") {
+        if let Some(end) = skip_argument(s, i) {
+            let inner = &s[i + 3..end - 3];
+            let parts = split_top(inner, &["|"]);
+            let name = parts[0].1;
+            let default = if parts.len() > 1 { Some(parse_inline(&inner[name.len() + 1..])) } else { None };
+            return Some((Node::Argument(Argument { name: name.to_owned(), default }), end));
+        }
+    }
+    let end = skip_template(s, i)?;
+    let inner = &s[i + 2..end - 2];
+    let parts = split_top(inner, &["|"]);
+    let name = parts[0].1;
+    if let Some(colon) = name.find(':') {
+        let function = name[..colon].trim();
+        if function.starts_with('#') || PARSER_FUNCTIONS.contains(&&*function.to_lowercase()) {
+            let mut args = vec![parse_inline(&name[colon + 1..])];
+            args.extend(parts[1..].iter().map(|x| parse_inline(x.1)));
+            return Some((Node::ParserFunction(ParserFunction {
+                name: name[..colon].to_owned(),
+                args,
+            }), end));
+        }
+    }
+    let params = parts[1..]
+        .iter()
+        .map(|&(_, param)| {
+            let split = split_top(param, &["="]);
+            if split.len() > 1 {
+                let key = split[0].1;
+                TemplateParam {
+                    name: Some(key.to_owned()),
+                    value: parse_inline(&param[key.len() + 1..]),
+                }
+            } else {
+                TemplateParam { name: None, value: parse_inline(param) }
+            }
+        })
+        .collect();
+    Some((Node::Template(Template { name: name.to_owned(), params }), end))
+}
+
+fn parse_internal_link(s: &str, i: usize) -> Option<(Node, usize)> {
+    let end = skip_link(s, i)?;
+    let inner = &s[i + 2..end - 2];
+    let parts = split_top(inner, &["|"]);
+    let target = parts[0].1;
+    if target.trim().is_empty() || target.contains(['[', ']', '{', '}', '<', '>']) {
+        return None;
+    }
+    let text = if parts.len() > 1 { Some(parse_inline(&inner[target.len() + 1..])) } else { None };
+    let lower = target.trim_start_matches(':').trim_start().to_lowercase();
+    let trail_len = if ["file:", "image:", "category:"].iter().any(|x| lower.starts_with(x)) && !target.starts_with(':') {
+        0
+    } else {
+        s[end..].find(|c: char| !c.is_ascii_lowercase()).unwrap_or(s.len() - end)
+    };
+    Some((Node::InternalLink(InternalLink {
+        target: target.to_owned(),
+        text,
+        trail: s[end..end + trail_len].to_owned(),
+    }), end + trail_len))
+}
+
+fn parse_external_link(s: &str, i: usize) -> Option<(Node, usize)> {
+    let rest = &s[i + 1..];
+    if !PROTOCOLS.iter().any(|x| rest.len() > x.len() && rest.get(..x.len()).is_some_and(|p| p.eq_ignore_ascii_case(x))) {
+        return None;
+    }
+    let mut j = i + 1;
+    while j < s.len() && !s[j..].starts_with(']') {
+        if s[j..].starts_with('\n') {
+            return None;
+        }
+        match skip_construct(s, j) {
+            Some(end) => j = end,
+            None => j += char_len(s, j),
+        }
+    }
+    if j >= s.len() {
+        return None;
+    }
+    let inner = &s[i + 1..j];
+    let (url, text) = match inner.find(' ') {
+        Some(space) => (&inner[..space], Some(parse_inline(&inner[space + 1..]))),
+        None => (inner, None),
+    };
+    Some((Node::ExternalLink(ExternalLink { url: url.to_owned(), text, bracketed: true }), j + 1))
+}
+
+fn parse_bare_url(s: &str, i: usize) -> Option<(Node, usize)> {
+    let rest = &s[i..];
+    let mut len = rest
+        .find(|c: char| c.is_whitespace() || "[]<>\"{}|".contains(c))
+        .unwrap_or(rest.len());
+    if let Some(quotes) = rest[..len].find("''") {
+        len = quotes;
+    }
+    let mut url = &rest[..len];
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        let trimmed = if trimmed.ends_with(')') && !trimmed.contains('(') { &trimmed[..trimmed.len() - 1] } else { trimmed };
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
+    }
+    if !url.contains("://") || url.ends_with("://") {
+        return None;
+    }
+    Some((Node::ExternalLink(ExternalLink { url: url.to_owned(), text: None, bracketed: false }), i + url.len()))
+}
+
+/// Parses text with block structure: headings, paragraphs, lists and tables.
+fn parse_blocks(s: &str) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    let mut i = 0;
+    while i < s.len() {
+        let end = line_end(s, i);
+        let line = &s[i..end];
+        let mut block_end = end;
+        if let Some((table, table_end)) = parse_table(s, i) {
+            nodes.push(Node::Table(table));
+            i = table_end;
+            continue;
+        } else if let Some(heading) = parse_heading(line) {
+            nodes.push(Node::Heading(heading));
+        } else if line.starts_with(['*', '#', ':', ';']) {
+            let mut items = Vec::new();
+            let mut start = i;
+            loop {
+                let line = &s[start..block_end];
+                let marker_len = line.len() - line.trim_start_matches(['*', '#', ':', ';']).len();
+                items.push(ListItem {
+                    marker: line[..marker_len].to_owned(),
+                    content: parse_inline(&line[marker_len..]),
+                });
+                if block_end >= s.len() || !s[block_end + 1..].starts_with(['*', '#', ':', ';']) {
+                    break;
+                }
+                start = block_end + 1;
+                block_end = line_end(s, start);
+            }
+            nodes.push(Node::List(items));
+        } else if line.trim().is_empty() {
+            nodes.push(Node::Text(line.to_owned()));
+        } else {
+            // Collect the following lines that are not blocks themselves
+            while block_end < s.len() {
+                let next = block_end + 1;
+                let next_end = line_end(s, next);
+                let next_line = &s[next..next_end];
+                if next_line.trim().is_empty() || next_line.starts_with(['*', '#', ':', ';']) ||
+                        parse_heading(next_line).is_some() || parse_table(s, next).is_some() {
+                    break;
+                }
+                block_end = next_end;
+            }
+            nodes.push(Node::Paragraph(parse_inline(&s[i..block_end])));
+        }
+        if block_end < s.len() {
+            nodes.push(Node::Text("\n".to_owned()));
+        }
+        i = block_end + 1;
+    }
+    merge_text(nodes)
+}
+
+fn parse_heading(line: &str) -> Option<Heading> {
+    let trimmed = line.trim_end_matches([' ', '\t']);
+    let leading = trimmed.len() - trimmed.trim_start_matches('=').len();
+    let trailing = trimmed.len() - trimmed.trim_end_matches('=').len();
+    let level = leading.min(trailing).min(6);
+    if level == 0 || trimmed.len() < 2 * level + 1 {
+        return None;
+    }
+    Some(Heading {
+        level,
+        content: parse_inline(&trimmed[level..trimmed.len() - level]),
+        trailing: line[trimmed.len()..].to_owned(),
+    })
+}
+
+/// A table cell with its content not parsed yet.
+struct RawCell<'t> {
+    header: bool,
+    marker: &'static str,
+    attributes: Option<&'t str>,
+    content: String,
+}
+
+/// Splits the attributes from the content of a cell or caption.
+fn split_attributes(raw: &str) -> (Option<&str>, &str) {
+    let parts = split_top(raw, &["|"]);
+    if parts.len() > 1 {
+        let attributes = parts[0].1;
+        (Some(attributes), &raw[attributes.len() + 1..])
+    } else {
+        (None, raw)
+    }
+}
+
+/// Parses a table starting at `i`, returning it and the offset after its
+/// closing `|}`. Tables with no closing `|}` are not parsed.
+fn parse_table(s: &str, i: usize) -> Option<(Table, usize)> {
+    if !s[i..].starts_with("{|") {
+        return None;
+    }
+    let first_end = line_end(s, i);
+    let mut attributes = s[i + 2..first_end].to_owned();
+    let mut caption: Option<(Option<&str>, String)> = None;
+    let mut rows: Vec<(Option<String>, Vec<RawCell>)> = Vec::new();
+    let mut depth = 0;
+    let mut j = first_end;
+    loop {
+        if j >= s.len() {
+            return None;
+        }
+        let start = j + 1;
+        let end = line_end(s, start);
+        let line = &s[start..end];
+        j = end;
+
+        let is_text = depth > 0 || !line.starts_with(['|', '!']) || (line.starts_with("|+") && (caption.is_some() || !rows.is_empty()));
+        if line.starts_with("{|") {
+            depth += 1;
+        } else if line.starts_with("|}") && depth > 0 {
+            depth -= 1;
+        } else if line.starts_with("|}") {
+            let rows = rows
+                .into_iter()
+                .map(|(attributes, cells)| TableRow {
+                    attributes,
+                    cells: cells
+                        .into_iter()
+                        .map(|cell| TableCell {
+                            header: cell.header,
+                            marker: cell.marker.to_owned(),
+                            attributes: cell.attributes.map(|x| x.to_owned()),
+                            content: parse_blocks(&cell.content),
+                        })
+                        .collect(),
+                })
+                .collect();
+            let caption = caption.map(|(attributes, content)| TableCaption {
+                attributes: attributes.map(|x| x.to_owned()),
+                content: parse_blocks(&content),
+            });
+            return Some((Table { attributes, caption, rows }, start + 2));
+        }
+        if is_text {
+            // Continues the content of the previous cell, caption or row
+            let last = match rows.last_mut() {
+                Some(&mut (_, ref mut cells)) if !cells.is_empty() => &mut cells.last_mut().unwrap().content,
+                Some(&mut (Some(ref mut row), _)) => row,
+                _ => match caption {
+                    Some((_, ref mut content)) => content,
+                    None => &mut attributes,
+                },
+            };
+            last.push('\n');
+            last.push_str(line);
+        } else if let Some(raw) = line.strip_prefix("|+") {
+            let (attributes, content) = split_attributes(raw);
+            caption = Some((attributes, content.to_owned()));
+        } else if let Some(row) = line.strip_prefix("|-") {
+            rows.push((Some(row.to_owned()), Vec::new()));
+        } else {
+            let header = line.starts_with('!');
+            let separators: &[&'static str] = if header { &["!!", "||"] } else { &["||"] };
+            if rows.is_empty() {
+                rows.push((None, Vec::new()));
+            }
+            let cells = &mut rows.last_mut().unwrap().1;
+            for (separator, raw) in split_top(&line[1..], separators) {
+                let (attributes, content) = split_attributes(raw);
+                cells.push(RawCell {
+                    header,
+                    marker: if separator.is_empty() { if header { "!" } else { "|" } } else { separator },
+                    attributes,
+                    content: content.to_owned(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, plain_text, to_wikitext, ExternalLink, Heading, InternalLink, ListItem, Node, Template, TemplateParam};
+
+    fn text(s: &str) -> Node {
+        Node::Text(s.to_owned())
+    }
+
+    #[test]
+    fn round_trip() {
+        let sources = [
+            "== History ==\nThe '''first''' ''[[World War I|Great War]]s'' began.<ref name=\"a\">{{cite web|url=https://example.com|title=A}}</ref>\n\n* one\n** two\n# three\n; term : definition\n",
+            "{{Infobox country\n| name = Argentina\n| capital = [[Buenos Aires]]\n| population = {{formatnum:45000000}} <!-- estimate -->\n| {{#if: {{{x|}}} | yes | no }}\n}}\n'''Argentina''' is a country.<ref name=\"a\" />",
+            "{| class=\"wikitable\"\n|+ Results\n|-\n! Year !! Winner\n|-\n| rowspan=\"2\" | 1990 || [[A|a]]\n|-\n| {{flag|B}}\nmore text\n{|\n| nested\n|}\n|}\nAfter <nowiki>[[not a link]]</nowiki> see [https://example.com the site] or http://example.org/x.\n<math>x^2</math>",
+            "Ça [[España]]ñ [ftp:ö] &ññññññ ö\n'''''both''''' and '''bold ''italic''''' and ''unclosed\n'''' four, [[File:A.jpg|thumb|A [[b]] c]]s, {{{1}}} and [[ ]] and {{broken",
+        ];
+        for source in sources {
+            assert_eq!(to_wikitext(&parse(source)), source);
+            plain_text(&parse(source));
+        }
+    }
+
+    #[test]
+    fn unclosed_brackets() {
+        let sources = [
+            "{{a|".repeat(1000),
+            "[[".repeat(1000),
+            "{{{".repeat(1000),
+            "{{a|[[b|".repeat(500) + "}}",
+        ];
+        for source in sources {
+            assert_eq!(to_wikitext(&parse(&source)), source);
+        }
+    }
+
+    #[test]
+    fn structure() {
+        let nodes = parse("== A ==\n'''b''' [[c|d]]e {{t|1|k = v}} [http://x.com y]");
+        assert_eq!(nodes[0], Node::Heading(Heading { level: 2, content: vec![text(" A ")], trailing: "".to_owned() }));
+        assert_eq!(nodes[1], text("\n"));
+        let paragraph = match nodes[2] {
+            Node::Paragraph(ref content) => content,
+            _ => panic!("not a paragraph"),
+        };
+        assert_eq!(paragraph[0], Node::Bold(vec![text("b")]));
+        assert_eq!(paragraph[2], Node::InternalLink(InternalLink {
+            target: "c".to_owned(),
+            text: Some(vec![text("d")]),
+            trail: "e".to_owned(),
+        }));
+        let template = Template {
+            name: "t".to_owned(),
+            params: vec![
+                TemplateParam { name: None, value: vec![text("1")] },
+                TemplateParam { name: Some("k ".to_owned()), value: vec![text(" v")] },
+            ],
+        };
+        assert_eq!(paragraph[4], Node::Template(template.clone()));
+        assert_eq!(template.get("1").unwrap().value, vec![text("1")]);
+        assert_eq!(template.get("k").unwrap().value, vec![text(" v")]);
+        assert_eq!(paragraph[6], Node::ExternalLink(ExternalLink {
+            url: "http://x.com".to_owned(),
+            text: Some(vec![text("y")]),
+            bracketed: true,
+        }));
+
+        let nodes = parse("* a\n*# b");
+        assert_eq!(nodes, vec![Node::List(vec![
+            ListItem { marker: "*".to_owned(), content: vec![text(" a")] },
+            ListItem { marker: "*#".to_owned(), content: vec![text(" b")] },
+        ])]);
+
+        let nodes = parse("{|\n! A !! B\n|-\n| style=\"x\" | 1 || 2\n|}");
+        let table = match nodes[0] {
+            Node::Table(ref table) => table,
+            _ => panic!("not a table"),
+        };
+        assert_eq!(table.rows.len(), 2);
+        assert!(table.rows[0].cells.iter().all(|x| x.header));
+        assert_eq!(table.rows[1].cells[0].attributes, Some(" style=\"x\" ".to_owned()));
+        assert_eq!(table.rows[1].cells[1].marker, "||");
+
+        match parse("{{#if: a | b }}<ref name=x>c</ref>")[0] {
+            Node::Paragraph(ref content) => {
+                match content[0] {
+                    Node::ParserFunction(ref function) => {
+                        assert_eq!(function.name(), "#if");
+                        assert_eq!(function.args.len(), 2);
+                    },
+                    _ => panic!("not a parser function"),
+                }
+                match content[1] {
+                    Node::Ref(ref r) => assert_eq!(r.name(), Some("x".to_owned())),
+                    _ => panic!("not a ref"),
+                }
+            },
+            _ => panic!("not a paragraph"),
+        }
+    }
+
+    #[test]
+    fn plain() {
+        let nodes = parse("'''[[Paris]]''' is the [[capital city|capital]]s of [[France]].<ref>{{cite web|title=A}}</ref><!-- x -->\n[[File:Paris.jpg|thumb|Paris]]{{Use dmy dates}}\n* {{convert|105|km2|sqmi}}&nbsp;wide<br/>[https://paris.fr site] {{lang|fr|Paris}}\n\n\n{{death date and age|2000|1|2|1950|3|4}}");
+        assert_eq!(plain_text(&nodes), "Paris is the capitals of France.\n\n105 km2 wide\nsite Paris\n\nJanuary 2, 2000");
+    }
+}
@@ -223,7 +223,7 @@ mod tests {
         let wikipedia = w();
         let page = wikipedia.page_from_title("Bikeshedding".to_owned());
         assert_eq!(
-                page.get_sections().await.unwrap(),
+                page.get_sections().await.unwrap().into_iter().map(|x| x.line).collect::<Vec<_>>(),
                 vec![
                 "Argument".to_owned(),
                 "Examples".to_owned(),
@@ -241,7 +241,7 @@ mod tests {
         let wikipedia = w();
         let page = wikipedia.page_from_pageid("4138548".to_owned());
         assert_eq!(
-                page.get_sections().await.unwrap(),
+                page.get_sections().await.unwrap().into_iter().map(|x| x.line).collect::<Vec<_>>(),
                 vec![
                 "Argument".to_owned(),
                 "Examples".to_owned(),
@@ -268,6 +268,256 @@ mod tests {
         assert!(languages.contains(&("en".to_owned(), "English".to_owned())));
         assert!(languages.contains(&("es".to_owned(), "español".to_owned())));
     }
+
+    #[tokio::test]
+    async fn autocomplete() {
+        let wikipedia = w();
+        let results = wikipedia.autocomplete("Buenos Air", 5).await.unwrap();
+        assert!(!results.is_empty());
+        assert!(results.iter().any(|s| s.title == "Buenos Aires"));
+    }
+
+    #[tokio::test]
+    async fn category_members() {
+        let wikipedia = w();
+        let query = wikipedia_wasm::iter::CategoryMemberQuery {
+            types: vec![wikipedia_wasm::iter::CategoryMemberType::Page],
+            ..Default::default()
+        };
+        let members = wikipedia.category_members("Category:Capitals in South America".to_owned(), query).await.unwrap()
+            .collect_vec::<Vec<_>>().await;
+        assert!(members.iter().any(|m| m.title == "Buenos Aires"));
+        assert!(members.iter().all(|m| m.ns == 0));
+    }
+
+    #[tokio::test]
+    async fn visible_categories() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Argentina".to_owned());
+        let query = wikipedia_wasm::iter::CategoryQuery { exclude_hidden: true };
+        let categories = page.get_categories_with(query).await.unwrap().collect_vec::<Vec<_>>().await;
+        assert!(!categories.is_empty());
+        assert!(categories.iter().all(|c| !c.hidden && !c.title.starts_with("Category:")));
+    }
+
+    #[tokio::test]
+    async fn backlinks() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Law of triviality".to_owned());
+        let query = wikipedia_wasm::iter::LinkingPageQuery { namespaces: vec![0], ..Default::default() };
+        let mut backlinks = page.get_backlinks(query).await.unwrap();
+        let first = backlinks.next().await.unwrap();
+        assert_eq!(first.ns, 0);
+    }
+
+    #[tokio::test]
+    async fn transcluded_in() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Template:Infobox country".to_owned());
+        let query = wikipedia_wasm::iter::LinkingPageQuery { namespaces: vec![0], ..Default::default() };
+        let mut pages = page.get_transcluded_in(query).await.unwrap();
+        let mut titles = Vec::new();
+        pages.for_each_interrupted(|p| {
+            titles.push(p.title);
+            if titles.len() == 10 {
+                return None;
+            }
+            Some(())
+        }).await;
+        assert_eq!(titles.len(), 10);
+    }
+
+    #[tokio::test]
+    async fn templates() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Argentina".to_owned());
+        let query = wikipedia_wasm::iter::TemplateQuery { namespaces: vec![10] };
+        let templates = page.get_templates_with(query).await.unwrap().collect_vec::<Vec<_>>().await;
+        assert!(templates.iter().all(|t| t.ns == 10));
+        assert!(templates.iter().any(|t| t.title == "Template:Infobox country"));
+    }
+
+    #[tokio::test]
+    async fn links_to_titles() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Argentina".to_owned());
+        let query = wikipedia_wasm::iter::LinkQuery {
+            titles: vec!["Buenos Aires".to_owned(), "Not a real article about nothing".to_owned()],
+            ..Default::default()
+        };
+        let links = page.get_links_with(query).await.unwrap().collect_vec::<Vec<_>>().await;
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].title, "Buenos Aires".to_owned());
+        assert!(links[0].exists);
+    }
+
+    #[tokio::test]
+    async fn external_url_usage() {
+        let wikipedia = w();
+        let query = wikipedia_wasm::iter::ReferenceQuery {
+            protocol: Some("https".to_owned()),
+            domain: Some("*.who.int".to_owned()),
+        };
+        let usages = wikipedia.external_url_usage(query, 5).await.unwrap();
+        assert_eq!(usages.len(), 5);
+        assert!(usages.iter().all(|u| u.reference.protocol == "https" && u.reference.domain.ends_with("who.int")));
+    }
+
+    #[tokio::test]
+    async fn citations() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Law of triviality".to_owned());
+        let citations = page.get_citations().await.unwrap();
+        assert!(!citations.is_empty());
+        assert!(citations.iter().any(|c| c.anchor.is_some() && c.title.is_some()));
+    }
+
+    #[tokio::test]
+    async fn page_images_with() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Argentina".to_owned());
+        let query = wikipedia_wasm::iter::ImageQuery {
+            details: true,
+            license: true,
+            thumb_width: Some(100),
+            ..Default::default()
+        };
+        let image = page.get_images_with(query).await.unwrap().next().await.unwrap();
+        assert!(image.mime.is_some());
+        assert!(image.width.is_some());
+        assert!(image.license.is_some());
+        assert!(image.thumbnail.unwrap().width <= 100);
+    }
+
+
+    #[tokio::test]
+    async fn thumbnail() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Argentina".to_owned());
+        let thumbnail = page.get_thumbnail(200).await.unwrap().unwrap();
+        assert!(thumbnail.width <= 200);
+        assert!(!thumbnail.file.is_empty());
+        let images = wikipedia.get_page_images(&["Argentina".to_owned(), "Chile".to_owned()], Some(100)).await.unwrap();
+        assert_eq!(images.len(), 2);
+        assert!(images.iter().all(|(_, image)| image.is_some()));
+    }
+
+
+    #[tokio::test]
+    async fn description_and_wikidata_id() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Douglas Adams".to_owned());
+        assert!(page.get_description(wikipedia_wasm::DescriptionSource::Local).await.unwrap().is_some());
+        assert_eq!(page.get_wikidata_id().await.unwrap(), Some("Q42".to_owned()));
+    }
+
+
+    #[tokio::test]
+    async fn wikidata_entity() {
+        let wikipedia = w();
+        let wikidata = wikipedia.wikidata();
+        let entity = wikidata.get_entity("Q42").await.unwrap().unwrap();
+        assert_eq!(entity.label("en"), Some("Douglas Adams"));
+        assert_eq!(entity.values("P31"), vec![&wikipedia_wasm::wikidata::DataValue::EntityId("Q5".to_owned())]);
+        let results = wikidata.search_entities("Douglas Adams").await.unwrap();
+        assert!(results.iter().any(|x| x.id == "Q42"));
+    }
+
+
+    #[tokio::test]
+    async fn sparql_query() {
+        let wikipedia = w();
+        let sparql = wikipedia_wasm::sparql::Sparql::<wikipedia_wasm::http::default::Client>::default();
+        let results = sparql.query("SELECT ?article WHERE { ?article schema:about wd:Q42; schema:isPartOf <https://en.wikipedia.org/> }").await.unwrap();
+        let page = results.bindings[0]["article"].to_page(&wikipedia).unwrap();
+        assert!(page == wikipedia.page_from_title("Douglas Adams".to_owned()));
+    }
+
+
+    #[tokio::test]
+    async fn all_coordinates() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Tranquility Base".to_owned());
+        let coordinates = page.get_all_coordinates().await.unwrap();
+        assert!(coordinates.iter().any(|x| x.globe == "moon"));
+    }
+
+
+    #[tokio::test]
+    async fn geosearch_with() {
+        let wikipedia = w();
+        let query = wikipedia_wasm::GeoQuery {
+            coordinates: Some((40.750556, -73.993611)),
+            radius: Some(200),
+            ..Default::default()
+        };
+        let results = wikipedia.geosearch_with(query).await.unwrap();
+        assert!(results.iter().any(|x| x.title == "Madison Square Garden"));
+        assert!(results.windows(2).all(|x| x[0].dist <= x[1].dist));
+    }
+
+
+    #[tokio::test]
+    async fn section_tree() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Bikeshedding".to_owned());
+        let tree = page.get_section_tree().await.unwrap();
+        assert_eq!(tree[0].section.anchor, "Argument");
+        assert!(tree.iter().all(|x| x.section.toclevel == 1));
+    }
+
+
+    #[tokio::test]
+    async fn section_by_anchor() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_pageid("4138548".to_owned());
+        let query = wikipedia_wasm::SectionQuery {
+            format: wikipedia_wasm::SectionFormat::Wikitext,
+            include_subsections: true,
+        };
+        let wikitext = page.get_section_by_anchor("Examples", query).await.unwrap().unwrap();
+        assert!(wikitext.contains("[["));
+        assert!(!wikitext.starts_with("=="));
+    }
+
+
+    #[tokio::test]
+    async fn wikitext() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Bikeshedding".to_owned());
+        let wikitext = page.get_wikitext().await.unwrap();
+        assert_eq!(wikitext.content_model, "wikitext");
+        assert!(wikitext.revid > 0);
+        let section = page.get_section_wikitext("1").await.unwrap();
+        assert!(section.content.starts_with("=="));
+        assert!(wikitext.content.contains(&*section.content));
+    }
+
+
+    #[tokio::test]
+    async fn parse() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Bikeshedding".to_owned());
+        let options = wikipedia_wasm::ParseOptions {
+            props: vec![wikipedia_wasm::ParseProp::Text, wikipedia_wasm::ParseProp::Sections],
+            disable_edit_section: true,
+            section: Some("1".to_owned()),
+            ..Default::default()
+        };
+        let result = page.parse(options).await.unwrap();
+        assert!(result.text.unwrap().contains("<p>"));
+        assert!(!result.sections.is_empty());
+    }
+
+
+    #[tokio::test]
+    async fn parse_wikitext() {
+        let wikipedia = w();
+        let text = "'''Hello''' [[World]]\n\n".repeat(500);
+        let html = wikipedia.parse_wikitext(&text, Some("Sandbox")).await.unwrap();
+        assert!(html.contains("<b>Hello</b>"));
+        assert_eq!(wikipedia.expand_templates("{{#expr: 2 + 2}}").await.unwrap(), "4");
+    }
 }
 
 #[cfg(feature = "http-client")]
@@ -495,7 +745,7 @@ mod wasm_tests {
         let wikipedia = w();
         let page = wikipedia.page_from_title("Bikeshedding".to_owned());
         assert_eq!(
-            page.get_sections().await.unwrap(),
+            page.get_sections().await.unwrap().into_iter().map(|x| x.line).collect::<Vec<_>>(),
             vec![
                 "Argument".to_owned(),
                 "Examples".to_owned(),
@@ -513,7 +763,7 @@ mod wasm_tests {
         let wikipedia = w();
         let page = wikipedia.page_from_pageid("4138548".to_owned());
         assert_eq!(
-            page.get_sections().await.unwrap(),
+            page.get_sections().await.unwrap().into_iter().map(|x| x.line).collect::<Vec<_>>(),
             vec![
                 "Argument".to_owned(),
                 "Examples".to_owned(),
@@ -540,4 +790,254 @@ mod wasm_tests {
         assert!(languages.contains(&("en".to_owned(), "English".to_owned())));
         assert!(languages.contains(&("es".to_owned(), "español".to_owned())));
     }
+
+    #[wasm_bindgen_test]
+    async fn autocomplete() {
+        let wikipedia = w();
+        let results = wikipedia.autocomplete("Buenos Air", 5).await.unwrap();
+        assert!(!results.is_empty());
+        assert!(results.iter().any(|s| s.title == "Buenos Aires"));
+    }
+
+    #[wasm_bindgen_test]
+    async fn category_members() {
+        let wikipedia = w();
+        let query = wikipedia_wasm::iter::CategoryMemberQuery {
+            types: vec![wikipedia_wasm::iter::CategoryMemberType::Page],
+            ..Default::default()
+        };
+        let members = wikipedia.category_members("Category:Capitals in South America".to_owned(), query).await.unwrap()
+            .collect_vec::<Vec<_>>().await;
+        assert!(members.iter().any(|m| m.title == "Buenos Aires"));
+        assert!(members.iter().all(|m| m.ns == 0));
+    }
+
+    #[wasm_bindgen_test]
+    async fn visible_categories() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Argentina".to_owned());
+        let query = wikipedia_wasm::iter::CategoryQuery { exclude_hidden: true };
+        let categories = page.get_categories_with(query).await.unwrap().collect_vec::<Vec<_>>().await;
+        assert!(!categories.is_empty());
+        assert!(categories.iter().all(|c| !c.hidden && !c.title.starts_with("Category:")));
+    }
+
+    #[wasm_bindgen_test]
+    async fn backlinks() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Law of triviality".to_owned());
+        let query = wikipedia_wasm::iter::LinkingPageQuery { namespaces: vec![0], ..Default::default() };
+        let mut backlinks = page.get_backlinks(query).await.unwrap();
+        let first = backlinks.next().await.unwrap();
+        assert_eq!(first.ns, 0);
+    }
+
+    #[wasm_bindgen_test]
+    async fn transcluded_in() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Template:Infobox country".to_owned());
+        let query = wikipedia_wasm::iter::LinkingPageQuery { namespaces: vec![0], ..Default::default() };
+        let mut pages = page.get_transcluded_in(query).await.unwrap();
+        let mut titles = Vec::new();
+        pages.for_each_interrupted(|p| {
+            titles.push(p.title);
+            if titles.len() == 10 {
+                return None;
+            }
+            Some(())
+        }).await;
+        assert_eq!(titles.len(), 10);
+    }
+
+    #[wasm_bindgen_test]
+    async fn templates() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Argentina".to_owned());
+        let query = wikipedia_wasm::iter::TemplateQuery { namespaces: vec![10] };
+        let templates = page.get_templates_with(query).await.unwrap().collect_vec::<Vec<_>>().await;
+        assert!(templates.iter().all(|t| t.ns == 10));
+        assert!(templates.iter().any(|t| t.title == "Template:Infobox country"));
+    }
+
+    #[wasm_bindgen_test]
+    async fn links_to_titles() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Argentina".to_owned());
+        let query = wikipedia_wasm::iter::LinkQuery {
+            titles: vec!["Buenos Aires".to_owned(), "Not a real article about nothing".to_owned()],
+            ..Default::default()
+        };
+        let links = page.get_links_with(query).await.unwrap().collect_vec::<Vec<_>>().await;
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].title, "Buenos Aires".to_owned());
+        assert!(links[0].exists);
+    }
+
+    #[wasm_bindgen_test]
+    async fn external_url_usage() {
+        let wikipedia = w();
+        let query = wikipedia_wasm::iter::ReferenceQuery {
+            protocol: Some("https".to_owned()),
+            domain: Some("*.who.int".to_owned()),
+        };
+        let usages = wikipedia.external_url_usage(query, 5).await.unwrap();
+        assert_eq!(usages.len(), 5);
+        assert!(usages.iter().all(|u| u.reference.protocol == "https" && u.reference.domain.ends_with("who.int")));
+    }
+
+    #[wasm_bindgen_test]
+    async fn citations() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Law of triviality".to_owned());
+        let citations = page.get_citations().await.unwrap();
+        assert!(!citations.is_empty());
+        assert!(citations.iter().any(|c| c.anchor.is_some() && c.title.is_some()));
+    }
+
+    #[wasm_bindgen_test]
+    async fn page_images_with() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Argentina".to_owned());
+        let query = wikipedia_wasm::iter::ImageQuery {
+            details: true,
+            license: true,
+            thumb_width: Some(100),
+            ..Default::default()
+        };
+        let image = page.get_images_with(query).await.unwrap().next().await.unwrap();
+        assert!(image.mime.is_some());
+        assert!(image.width.is_some());
+        assert!(image.license.is_some());
+        assert!(image.thumbnail.unwrap().width <= 100);
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn thumbnail() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Argentina".to_owned());
+        let thumbnail = page.get_thumbnail(200).await.unwrap().unwrap();
+        assert!(thumbnail.width <= 200);
+        assert!(!thumbnail.file.is_empty());
+        let images = wikipedia.get_page_images(&["Argentina".to_owned(), "Chile".to_owned()], Some(100)).await.unwrap();
+        assert_eq!(images.len(), 2);
+        assert!(images.iter().all(|(_, image)| image.is_some()));
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn description_and_wikidata_id() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Douglas Adams".to_owned());
+        assert!(page.get_description(wikipedia_wasm::DescriptionSource::Local).await.unwrap().is_some());
+        assert_eq!(page.get_wikidata_id().await.unwrap(), Some("Q42".to_owned()));
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn wikidata_entity() {
+        let wikipedia = w();
+        let wikidata = wikipedia.wikidata();
+        let entity = wikidata.get_entity("Q42").await.unwrap().unwrap();
+        assert_eq!(entity.label("en"), Some("Douglas Adams"));
+        assert_eq!(entity.values("P31"), vec![&wikipedia_wasm::wikidata::DataValue::EntityId("Q5".to_owned())]);
+        let results = wikidata.search_entities("Douglas Adams").await.unwrap();
+        assert!(results.iter().any(|x| x.id == "Q42"));
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn sparql_query() {
+        let wikipedia = w();
+        let sparql = wikipedia_wasm::sparql::Sparql::<wikipedia_wasm::http::default::Client>::default();
+        let results = sparql.query("SELECT ?article WHERE { ?article schema:about wd:Q42; schema:isPartOf <https://en.wikipedia.org/> }").await.unwrap();
+        let page = results.bindings[0]["article"].to_page(&wikipedia).unwrap();
+        assert!(page == wikipedia.page_from_title("Douglas Adams".to_owned()));
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn all_coordinates() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Tranquility Base".to_owned());
+        let coordinates = page.get_all_coordinates().await.unwrap();
+        assert!(coordinates.iter().any(|x| x.globe == "moon"));
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn geosearch_with() {
+        let wikipedia = w();
+        let query = wikipedia_wasm::GeoQuery {
+            coordinates: Some((40.750556, -73.993611)),
+            radius: Some(200),
+            ..Default::default()
+        };
+        let results = wikipedia.geosearch_with(query).await.unwrap();
+        assert!(results.iter().any(|x| x.title == "Madison Square Garden"));
+        assert!(results.windows(2).all(|x| x[0].dist <= x[1].dist));
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn section_tree() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Bikeshedding".to_owned());
+        let tree = page.get_section_tree().await.unwrap();
+        assert_eq!(tree[0].section.anchor, "Argument");
+        assert!(tree.iter().all(|x| x.section.toclevel == 1));
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn section_by_anchor() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_pageid("4138548".to_owned());
+        let query = wikipedia_wasm::SectionQuery {
+            format: wikipedia_wasm::SectionFormat::Wikitext,
+            include_subsections: true,
+        };
+        let wikitext = page.get_section_by_anchor("Examples", query).await.unwrap().unwrap();
+        assert!(wikitext.contains("[["));
+        assert!(!wikitext.starts_with("=="));
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn wikitext() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Bikeshedding".to_owned());
+        let wikitext = page.get_wikitext().await.unwrap();
+        assert_eq!(wikitext.content_model, "wikitext");
+        assert!(wikitext.revid > 0);
+        let section = page.get_section_wikitext("1").await.unwrap();
+        assert!(section.content.starts_with("=="));
+        assert!(wikitext.content.contains(&*section.content));
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn parse() {
+        let wikipedia = w();
+        let page = wikipedia.page_from_title("Bikeshedding".to_owned());
+        let options = wikipedia_wasm::ParseOptions {
+            props: vec![wikipedia_wasm::ParseProp::Text, wikipedia_wasm::ParseProp::Sections],
+            disable_edit_section: true,
+            section: Some("1".to_owned()),
+            ..Default::default()
+        };
+        let result = page.parse(options).await.unwrap();
+        assert!(result.text.unwrap().contains("<p>"));
+        assert!(!result.sections.is_empty());
+    }
+
+
+    #[wasm_bindgen_test]
+    async fn parse_wikitext() {
+        let wikipedia = w();
+        let text = "'''Hello''' [[World]]\n\n".repeat(500);
+        let html = wikipedia.parse_wikitext(&text, Some("Sandbox")).await.unwrap();
+        assert!(html.contains("<b>Hello</b>"));
+        assert_eq!(wikipedia.expand_templates("{{#expr: 2 + 2}}").await.unwrap(), "4");
+    }
 }